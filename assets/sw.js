@@ -1,9 +1,8 @@
 var cacheName = 'egui-template-pwa';
+/* Trunk adds a hash to the names of the wasm and js files, so they are cached when first fetched */
 var filesToCache = [
     './',
     './index.html',
-    './app_status.js',
-    './app_status_bg.wasm',
 ];
 
 /* Start the service worker and cache all of the app's content */
@@ -15,11 +14,22 @@ self.addEventListener('install', function (e) {
     );
 });
 
-/* Serve cached content when offline */
+/* Serve the app from the network when online, and from the cache when offline.
+   Requests to GitHub are left alone, the app keeps its own copy of the last fetched data. */
 self.addEventListener('fetch', function (e) {
+    if (e.request.method !== 'GET' || new URL(e.request.url).origin !== self.location.origin) {
+        return;
+    }
+
     e.respondWith(
-        caches.match(e.request).then(function (response) {
-            return response || fetch(e.request);
+        fetch(e.request).then(function (response) {
+            var copy = response.clone();
+            caches.open(cacheName).then(function (cache) {
+                cache.put(e.request, copy);
+            });
+            return response;
+        }).catch(function () {
+            return caches.match(e.request);
         })
     );
 });
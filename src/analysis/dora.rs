@@ -0,0 +1,241 @@
+use chrono::{DateTime, Duration, Utc};
+
+use crate::analysis::median;
+use crate::github::pulls::PullRequest;
+use crate::github::runs::WorkflowRun;
+
+/// Observations from one or more repositories within a time window.
+/// Kept apart from [`DoraMetrics`] so repositories can be pooled into team wide metrics.
+#[derive(Default, Clone)]
+pub struct DoraSamples {
+    deployments: usize,
+    failed_deployments: usize,
+    open_to_merge: Vec<Duration>,
+    merge_to_deploy: Vec<Duration>,
+    lead_times: Vec<Duration>,
+    restore_times: Vec<Duration>,
+}
+
+pub struct DoraMetrics {
+    pub deployments: usize,
+    pub deployments_per_week: f64,
+    pub lead_time: Option<Duration>,
+    pub open_to_merge: Option<Duration>,
+    pub merge_to_deploy: Option<Duration>,
+    pub change_failure_rate: Option<f64>,
+    pub time_to_restore: Option<Duration>,
+}
+
+impl DoraSamples {
+    /// Every run of the deploy workflow counts as a deployment attempt, and a merged pull request
+    /// is considered deployed by the first successful deploy of its merge commit or any later commit.
+    pub fn collect(pulls: &[PullRequest], deploy_runs: &[WorkflowRun], since: DateTime<Utc>) -> Self {
+        let mut deploys = deploy_runs.iter()
+            .filter(|run| run.is_success() || run.is_failure())
+            .filter_map(|run| Some((run.created()?, run.updated()?, run)))
+            .collect::<Vec<_>>();
+        deploys.sort_by_key(|(created, _, _)| *created);
+
+        let successful = deploys.iter()
+            .filter(|(_, _, run)| run.is_success())
+            .collect::<Vec<_>>();
+
+        let mut samples = Self::default();
+
+        for (i, (created, finished, run)) in deploys.iter().enumerate() {
+            if *created < since {
+                continue;
+            }
+
+            if run.is_success() {
+                samples.deployments += 1;
+                continue;
+            }
+
+            samples.failed_deployments += 1;
+
+            // Time to restore is measured from the first failure in a row of failures.
+            let previous_failed = i > 0 && deploys[i - 1].2.is_failure();
+            if !previous_failed {
+                if let Some((_, restored, _)) = successful.iter().find(|(c, _, _)| c > created) {
+                    samples.restore_times.push(*restored - *finished);
+                }
+            }
+        }
+
+        for pr in pulls {
+            let (Some(opened), Some(merged)) = (pr.created(), pr.merged()) else { continue };
+            if merged < since {
+                continue;
+            }
+
+            samples.open_to_merge.push(merged - opened);
+
+            let deployed = successful.iter()
+                .find(|(_, _, run)| Some(&run.head_sha) == pr.merge_commit_sha.as_ref())
+                .or_else(|| successful.iter().find(|(created, _, _)| *created >= merged));
+
+            if let Some((_, deployed, _)) = deployed {
+                samples.merge_to_deploy.push(*deployed - merged);
+                samples.lead_times.push(*deployed - opened);
+            }
+        }
+
+        samples
+    }
+
+    pub fn extend(&mut self, other: &Self) {
+        self.deployments += other.deployments;
+        self.failed_deployments += other.failed_deployments;
+        self.open_to_merge.extend(other.open_to_merge.iter());
+        self.merge_to_deploy.extend(other.merge_to_deploy.iter());
+        self.lead_times.extend(other.lead_times.iter());
+        self.restore_times.extend(other.restore_times.iter());
+    }
+
+    pub fn metrics(&self, window: Duration) -> DoraMetrics {
+        let attempts = self.deployments + self.failed_deployments;
+
+        DoraMetrics {
+            deployments: self.deployments,
+            deployments_per_week: self.deployments as f64 * 7.0 / window.num_days().max(1) as f64,
+            lead_time: median(self.lead_times.clone()),
+            open_to_merge: median(self.open_to_merge.clone()),
+            merge_to_deploy: median(self.merge_to_deploy.clone()),
+            change_failure_rate: (attempts > 0).then(|| self.failed_deployments as f64 / attempts as f64),
+            time_to_restore: median(self.restore_times.clone()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::github::runs::parse_timestamp;
+
+    fn at(timestamp: &str) -> DateTime<Utc> {
+        parse_timestamp(timestamp).expect("timestamp")
+    }
+
+    fn deploy(id: i64, sha: &str, conclusion: &str, created_at: &str, updated_at: &str) -> WorkflowRun {
+        serde_json::from_value(serde_json::json!({
+            "id": id,
+            "name": "Deploy",
+            "head_sha": sha,
+            "head_branch": "main",
+            "path": ".github/workflows/deploy.yml",
+            "run_number": id,
+            "run_attempt": 1,
+            "event": "push",
+            "status": "completed",
+            "conclusion": conclusion,
+            "workflow_id": 9,
+            "url": "",
+            "html_url": "",
+            "pull_requests": [],
+            "created_at": created_at,
+            "updated_at": updated_at,
+            "jobs_url": "",
+            "logs_url": "",
+            "check_suite_url": "",
+            "artifacts_url": "",
+            "cancel_url": "",
+            "rerun_url": "",
+            "workflow_url": "",
+            "display_title": "Deploy",
+        })).expect("run")
+    }
+
+    fn pull(number: i32, created_at: &str, merged_at: Option<&str>, merge_commit_sha: Option<&str>) -> PullRequest {
+        let branch = serde_json::json!({ "ref": "main", "sha": "c1", "repo": { "id": 1, "url": "", "name": "aap-api" } });
+        serde_json::from_value(serde_json::json!({
+            "id": number,
+            "number": number,
+            "url": "",
+            "head": branch,
+            "base": branch,
+            "html_url": null,
+            "title": null,
+            "body": null,
+            "state": "closed",
+            "user": { "login": "alice" },
+            "created_at": created_at,
+            "updated_at": created_at,
+            "merged_at": merged_at,
+            "merge_commit_sha": merge_commit_sha,
+        })).expect("pull request")
+    }
+
+    /// Two weeks from the 1st of March with two deployments, two failed ones in a row between
+    /// them, and two pull requests merged, deployed by their own commit and by a later one.
+    fn samples() -> DoraSamples {
+        let deploys = [
+            deploy(1, "old", "success", "2024-02-20T10:00:00Z", "2024-02-20T10:10:00Z"),
+            deploy(2, "a", "success", "2024-03-02T10:00:00Z", "2024-03-02T10:10:00Z"),
+            deploy(3, "b", "failure", "2024-03-03T10:00:00Z", "2024-03-03T10:10:00Z"),
+            deploy(4, "c", "failure", "2024-03-03T12:00:00Z", "2024-03-03T12:10:00Z"),
+            deploy(5, "d", "cancelled", "2024-03-03T13:00:00Z", "2024-03-03T13:01:00Z"),
+            deploy(6, "e", "success", "2024-03-04T10:00:00Z", "2024-03-04T10:10:00Z"),
+        ];
+        let pulls = [
+            pull(1, "2024-03-01T10:00:00Z", Some("2024-03-02T09:00:00Z"), Some("a")),
+            pull(2, "2024-03-02T12:00:00Z", Some("2024-03-03T11:00:00Z"), Some("x")),
+            // Merged before the window, and never merged.
+            pull(3, "2024-02-10T10:00:00Z", Some("2024-02-20T09:00:00Z"), Some("old")),
+            pull(4, "2024-03-01T10:00:00Z", None, None),
+        ];
+        DoraSamples::collect(&pulls, &deploys, at("2024-03-01T00:00:00Z"))
+    }
+
+    #[test]
+    fn deployment_frequency_counts_successful_deploys_in_the_window() {
+        let metrics = samples().metrics(Duration::days(14));
+
+        assert_eq!(metrics.deployments, 2);
+        assert_eq!(metrics.deployments_per_week, 1.0);
+    }
+
+    #[test]
+    fn lead_time_runs_from_opened_to_deployed() {
+        let metrics = samples().metrics(Duration::days(14));
+
+        assert_eq!(metrics.open_to_merge, Some(Duration::hours(23)));
+        // 1h 10m by its own merge commit, 23h 10m by the next deploy after the merge.
+        assert_eq!(metrics.merge_to_deploy, Some(Duration::hours(12) + Duration::minutes(10)));
+        // 24h 10m and 46h 10m.
+        assert_eq!(metrics.lead_time, Some(Duration::hours(35) + Duration::minutes(10)));
+    }
+
+    #[test]
+    fn change_failure_rate_leaves_out_cancelled_deploys() {
+        let metrics = samples().metrics(Duration::days(14));
+
+        assert_eq!(metrics.change_failure_rate, Some(0.5));
+    }
+
+    #[test]
+    fn time_to_restore_runs_from_the_first_failure_in_a_row() {
+        let metrics = samples().metrics(Duration::days(14));
+
+        assert_eq!(metrics.time_to_restore, Some(Duration::hours(24)));
+    }
+
+    #[test]
+    fn repositories_are_pooled() {
+        let mut pooled = samples();
+        pooled.extend(&samples());
+        let metrics = pooled.metrics(Duration::days(14));
+
+        assert_eq!((metrics.deployments, metrics.deployments_per_week), (4, 2.0));
+        assert_eq!(metrics.change_failure_rate, Some(0.5));
+        assert_eq!(metrics.lead_time, Some(Duration::hours(35) + Duration::minutes(10)));
+    }
+
+    #[test]
+    fn nothing_deployed_has_no_rates() {
+        let metrics = DoraSamples::default().metrics(Duration::days(14));
+
+        assert_eq!((metrics.deployments, metrics.deployments_per_week), (0, 0.0));
+        assert_eq!((metrics.lead_time, metrics.change_failure_rate, metrics.time_to_restore), (None, None, None));
+    }
+}
@@ -0,0 +1,229 @@
+use std::collections::BTreeMap;
+
+use crate::github::jobs::Job;
+use crate::github::runs::{WorkflowRun, WorkflowRuns};
+
+/// Jobs of the failed attempts before a success, by run id and attempt, see [`failed_attempts`].
+pub type AttemptJobs = BTreeMap<(i64, i32), Vec<Job>>;
+
+/// How often a workflow fails on a commit and then passes on the same commit without changes.
+#[derive(Debug, Clone)]
+pub struct Flakiness {
+    pub repo: String,
+    pub workflow: String,
+    pub commits: usize,
+    pub flaky_commits: usize,
+    pub failed_commits: usize,
+    /// Flaky commits each job failed on, by job name.
+    pub flaky_jobs: BTreeMap<String, usize>,
+    pub latest_flake_url: Option<String>,
+}
+
+impl Flakiness {
+    pub fn flake_rate(&self) -> f64 {
+        if self.commits == 0 {
+            return 0.0;
+        }
+        self.flaky_commits as f64 / self.commits as f64
+    }
+
+    /// The jobs failing most often first.
+    pub fn flakiest_jobs(&self) -> Vec<(&str, usize)> {
+        let mut jobs = self.flaky_jobs.iter().map(|(job, count)| (job.as_str(), *count)).collect::<Vec<_>>();
+        jobs.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        jobs
+    }
+}
+
+/// Flaky workflows across all repositories, most flaky first. Workflows without flakes are left out.
+pub fn flaky_workflows(repo_with_runs: &BTreeMap<String, WorkflowRuns>, attempt_jobs: &AttemptJobs) -> Vec<Flakiness> {
+    let mut report = repo_with_runs.iter().flat_map(|(repo, runs)| {
+        by_workflow(runs).into_values().map(|runs| flakiness(repo, runs, attempt_jobs)).collect::<Vec<_>>()
+    }).filter(|f| f.flaky_commits > 0).collect::<Vec<_>>();
+
+    report.sort_by(|a, b| b.flake_rate().total_cmp(&a.flake_rate()).then(b.flaky_commits.cmp(&a.flaky_commits)));
+    report
+}
+
+/// The attempts whose jobs tell whether and where a commit flaked, as run id and attempt: the
+/// attempt before each successful re-run, and failed runs followed by a success on their commit.
+pub fn failed_attempts(runs: &WorkflowRuns) -> Vec<(i64, i32)> {
+    by_workflow(runs).into_values().flat_map(|runs| {
+        by_commit(runs).into_values().flat_map(|runs| {
+            let passed = runs.iter().any(|run| run.is_success());
+            runs.into_iter().filter_map(move |run| match (run.is_success(), run.is_failure()) {
+                (true, _) if run.run_attempt > 1 => Some((run.id, run.run_attempt - 1)),
+                (_, true) if passed => Some((run.id, run.run_attempt)),
+                _ => None,
+            })
+        }).collect::<Vec<_>>()
+    }).collect()
+}
+
+fn by_workflow(runs: &WorkflowRuns) -> BTreeMap<i64, Vec<&WorkflowRun>> {
+    runs.workflow_runs.iter().fold(BTreeMap::new(), |mut acc: BTreeMap<i64, Vec<&WorkflowRun>>, run| {
+        acc.entry(run.workflow_id).or_default().push(run);
+        acc
+    })
+}
+
+/// Completed runs of each commit, oldest first.
+fn by_commit(runs: Vec<&WorkflowRun>) -> BTreeMap<String, Vec<&WorkflowRun>> {
+    let mut by_commit = runs.into_iter()
+        .filter(|run| run.conclusion.is_some())
+        .fold(BTreeMap::new(), |mut acc: BTreeMap<String, Vec<&WorkflowRun>>, run| {
+            acc.entry(run.head_sha.clone()).or_default().push(run);
+            acc
+        });
+
+    by_commit.values_mut().for_each(|runs| runs.sort_by(|a, b| a.created_at.cmp(&b.created_at)));
+    by_commit
+}
+
+fn flakiness(repo: &str, runs: Vec<&WorkflowRun>, attempt_jobs: &AttemptJobs) -> Flakiness {
+    let workflow = runs.first().and_then(|run| run.name.clone()).unwrap_or_default();
+    let by_commit = by_commit(runs);
+
+    let mut latest_flake: Option<&WorkflowRun> = None;
+    let mut flaky_commits = 0;
+    let mut failed_commits = 0;
+    let mut flaky_jobs = BTreeMap::<String, usize>::new();
+
+    for runs in by_commit.values() {
+        let failed_jobs = |id: i64, attempt: i32| attempt_jobs.get(&(id, attempt)).into_iter().flatten()
+            .filter(|job| job.is_failure())
+            .map(|job| job.name.clone())
+            .collect::<Vec<_>>();
+
+        // A success after an attempt that failed, rather than was cancelled: a re-run of the same
+        // run, known to have failed from the jobs of the attempt before, or another run.
+        let flake = runs.iter().enumerate().find_map(|(i, run)| {
+            if !run.is_success() {
+                return None;
+            }
+
+            let retried = match run.run_attempt > 1 {
+                true => failed_jobs(run.id, run.run_attempt - 1),
+                false => vec![],
+            };
+            let failed_before = runs[..i].iter().filter(|r| r.is_failure()).collect::<Vec<_>>();
+            if retried.is_empty() && failed_before.is_empty() {
+                return None;
+            }
+
+            let mut jobs = retried;
+            jobs.extend(failed_before.iter().flat_map(|r| failed_jobs(r.id, r.run_attempt)));
+            Some((*run, jobs))
+        });
+
+        if let Some((run, mut jobs)) = flake {
+            flaky_commits += 1;
+            jobs.sort();
+            jobs.dedup();
+            jobs.into_iter().for_each(|job| *flaky_jobs.entry(job).or_default() += 1);
+            if latest_flake.is_none_or(|latest| latest.created_at < run.created_at) {
+                latest_flake = Some(run);
+            }
+        } else if runs.iter().any(|run| run.is_failure()) {
+            failed_commits += 1;
+        }
+    }
+
+    Flakiness {
+        repo: repo.to_string(),
+        workflow,
+        commits: by_commit.len(),
+        flaky_commits,
+        failed_commits,
+        flaky_jobs,
+        latest_flake_url: latest_flake.map(|run| run.html_url.clone()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(id: i64, attempt: i32, conclusion: &str, created_at: &str) -> WorkflowRun {
+        serde_json::from_value(serde_json::json!({
+            "id": id,
+            "name": "Build",
+            "head_sha": "c1",
+            "head_branch": "main",
+            "path": ".github/workflows/build.yml",
+            "run_number": id,
+            "run_attempt": attempt,
+            "event": "push",
+            "status": "completed",
+            "conclusion": conclusion,
+            "workflow_id": 7,
+            "url": "",
+            "html_url": format!("https://github.com/navikt/aap-api/actions/runs/{}", id),
+            "pull_requests": [],
+            "created_at": created_at,
+            "updated_at": created_at,
+            "jobs_url": "",
+            "logs_url": "",
+            "check_suite_url": "",
+            "artifacts_url": "",
+            "cancel_url": "",
+            "rerun_url": "",
+            "workflow_url": "",
+            "display_title": "Build",
+        })).expect("run")
+    }
+
+    fn jobs(run_id: i64, attempt: i32, conclusions: &[(&str, &str)]) -> ((i64, i32), Vec<Job>) {
+        let jobs = conclusions.iter().enumerate().map(|(i, (name, conclusion))| Job {
+            id: i as i64,
+            run_id,
+            run_attempt: attempt,
+            name: name.to_string(),
+            status: Some(String::from("completed")),
+            conclusion: Some(conclusion.to_string()),
+            html_url: None,
+        }).collect();
+        ((run_id, attempt), jobs)
+    }
+
+    fn runs(runs: Vec<WorkflowRun>) -> WorkflowRuns {
+        WorkflowRuns { total_count: runs.len() as i32, workflow_runs: runs }
+    }
+
+    #[test]
+    fn success_on_retry_after_a_failed_attempt_is_a_flake() {
+        let runs = runs(vec![run(1, 2, "success", "2024-01-01T12:00:00Z")]);
+        let attempt_jobs = AttemptJobs::from([jobs(1, 1, &[("build", "success"), ("test", "failure")])]);
+
+        assert_eq!(failed_attempts(&runs), vec![(1, 1)]);
+        let flakiness = flakiness("aap-api", runs.workflow_runs.iter().collect(), &attempt_jobs);
+        assert_eq!((flakiness.flaky_commits, flakiness.failed_commits), (1, 0));
+        assert_eq!(flakiness.flakiest_jobs(), vec![("test", 1)]);
+    }
+
+    #[test]
+    fn success_on_retry_after_a_cancelled_attempt_is_not_a_flake() {
+        let runs = runs(vec![run(1, 2, "success", "2024-01-01T12:00:00Z")]);
+        let attempt_jobs = AttemptJobs::from([jobs(1, 1, &[("build", "cancelled"), ("test", "cancelled")])]);
+
+        let flakiness = flakiness("aap-api", runs.workflow_runs.iter().collect(), &attempt_jobs);
+        assert_eq!((flakiness.flaky_commits, flakiness.failed_commits), (0, 0));
+        // Nor before the jobs of the attempt are known.
+        let unknown = super::flakiness("aap-api", runs.workflow_runs.iter().collect(), &AttemptJobs::new());
+        assert_eq!(unknown.flaky_commits, 0);
+    }
+
+    #[test]
+    fn failing_on_every_attempt_is_not_a_flake() {
+        let runs = runs(vec![
+            run(1, 1, "failure", "2024-01-01T12:00:00Z"),
+            run(2, 2, "failure", "2024-01-01T13:00:00Z"),
+        ]);
+        let attempt_jobs = AttemptJobs::from([jobs(2, 1, &[("test", "failure")])]);
+
+        assert!(failed_attempts(&runs).is_empty());
+        let flakiness = flakiness("aap-api", runs.workflow_runs.iter().collect(), &attempt_jobs);
+        assert_eq!((flakiness.flaky_commits, flakiness.failed_commits), (0, 1));
+        assert!(flaky_workflows(&BTreeMap::from([(String::from("aap-api"), runs)]), &attempt_jobs).is_empty());
+    }
+}
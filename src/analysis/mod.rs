@@ -0,0 +1,82 @@
+use chrono::Duration;
+
+pub mod dora;
+pub mod flaky;
+pub mod reviews;
+pub mod status;
+
+/// The middle duration, or the mean of the two in the middle of an even number of them.
+pub fn median(mut durations: Vec<Duration>) -> Option<Duration> {
+    durations.sort();
+    let middle = durations.len() / 2;
+    match durations.len() % 2 {
+        0 => Some((*durations.get(middle.checked_sub(1)?)? + durations[middle]) / 2),
+        _ => durations.get(middle).copied(),
+    }
+}
+
+/// Nearest rank percentile, `p` between 0 and 1: the smallest duration at least `p` of them are
+/// no longer than.
+pub fn percentile(mut durations: Vec<Duration>, p: f64) -> Option<Duration> {
+    durations.sort();
+    let rank = ((durations.len() as f64 * p).ceil() as usize).clamp(1, durations.len().max(1));
+    durations.get(rank - 1).copied()
+}
+
+/// Short human readable duration, e.g. `3d 4h` or `12m`.
+pub fn format_duration(duration: Duration) -> String {
+    let minutes = duration.num_minutes();
+    match (minutes / (60 * 24), minutes / 60 % 24, minutes % 60) {
+        (0, 0, m) => format!("{}m", m),
+        (0, h, m) => format!("{}h {}m", h, m),
+        (d, h, _) => format!("{}d {}h", d, h),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn minutes(minutes: &[i64]) -> Vec<Duration> {
+        minutes.iter().copied().map(Duration::minutes).collect()
+    }
+
+    #[test]
+    fn median_of_nothing_is_none() {
+        assert_eq!(median(vec![]), None);
+        assert_eq!(percentile(vec![], 0.9), None);
+    }
+
+    #[test]
+    fn median_of_an_odd_number_is_the_middle_one() {
+        assert_eq!(median(minutes(&[30, 10, 20])), Some(Duration::minutes(20)));
+        assert_eq!(median(minutes(&[5])), Some(Duration::minutes(5)));
+    }
+
+    #[test]
+    fn median_of_an_even_number_is_the_mean_of_the_middle_two() {
+        assert_eq!(median(minutes(&[40, 10, 20, 30])), Some(Duration::minutes(25)));
+        assert_eq!(median(minutes(&[10, 11])), Some(Duration::seconds(630)));
+    }
+
+    #[test]
+    fn percentiles_are_of_the_nearest_rank() {
+        let durations = minutes(&[10, 20, 30, 40, 50, 60, 70, 80, 90, 100]);
+
+        assert_eq!(percentile(durations.clone(), 0.0), Some(Duration::minutes(10)));
+        assert_eq!(percentile(durations.clone(), 0.5), Some(Duration::minutes(50)));
+        assert_eq!(percentile(durations.clone(), 0.9), Some(Duration::minutes(90)));
+        assert_eq!(percentile(durations.clone(), 0.91), Some(Duration::minutes(100)));
+        assert_eq!(percentile(durations, 1.0), Some(Duration::minutes(100)));
+        assert_eq!(percentile(minutes(&[30, 10, 20]), 0.5), Some(Duration::minutes(20)));
+    }
+
+    #[test]
+    fn durations_are_formatted_in_their_largest_units() {
+        assert_eq!(format_duration(Duration::seconds(59)), "0m");
+        assert_eq!(format_duration(Duration::minutes(12)), "12m");
+        assert_eq!(format_duration(Duration::minutes(61)), "1h 1m");
+        assert_eq!(format_duration(Duration::hours(24)), "1d 0h");
+        assert_eq!(format_duration(Duration::hours(76) + Duration::minutes(59)), "3d 4h");
+    }
+}
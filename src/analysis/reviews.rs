@@ -0,0 +1,228 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use chrono::{DateTime, Duration, Utc};
+
+use crate::analysis::percentile;
+use crate::github::pulls::{PullRequest, Review, User};
+
+/// Pages of pull requests fetched per repository, most recently updated first.
+pub const HISTORY_PAGES: i32 = 2;
+
+/// Pull requests in a page of history.
+pub const PER_PAGE: usize = 100;
+
+/// Upper bounds (in hours) of the histogram buckets, the last bucket is open ended.
+pub const BUCKETS: [(&str, i64); 6] = [
+    ("< 1d", 24),
+    ("1-3d", 72),
+    ("3-7d", 168),
+    ("1-2w", 336),
+    ("2-4w", 672),
+    ("> 4w", i64::MAX),
+];
+
+pub struct LatencyStats {
+    pub count: usize,
+    pub median: Option<Duration>,
+    pub p90: Option<Duration>,
+    pub histogram: Vec<usize>,
+}
+
+impl LatencyStats {
+    fn new(durations: Vec<Duration>) -> Self {
+        let histogram = BUCKETS.iter().enumerate().map(|(i, (_, upper))| {
+            let lower = if i == 0 { i64::MIN } else { BUCKETS[i - 1].1 };
+            durations.iter().filter(|d| d.num_hours() >= lower && d.num_hours() < *upper).count()
+        }).collect();
+
+        Self {
+            count: durations.len(),
+            median: percentile(durations.clone(), 0.5),
+            p90: percentile(durations, 0.9),
+            histogram,
+        }
+    }
+}
+
+pub struct ReviewerLoad {
+    pub login: String,
+    pub reviews: usize,
+    pub pull_requests: usize,
+}
+
+pub struct ReviewReport {
+    pub open_age: LatencyStats,
+    pub time_to_first_review: LatencyStats,
+    pub time_to_merge: LatencyStats,
+    pub reviewers: Vec<ReviewerLoad>,
+}
+
+/// Whether a pull request may have been reviewed after `since`, as reviewing it updates it.
+pub fn may_be_reviewed_since(pr: &PullRequest, since: DateTime<Utc>) -> bool {
+    pr.state.as_deref() == Some("open") || pr.updated().is_some_and(|updated| updated >= since)
+}
+
+/// Whether pull requests updated after `since` were left out by [`HISTORY_PAGES`], as the last
+/// page fetched was full and updated after `since` all the way down.
+pub fn is_truncated(last_page: &[PullRequest], since: DateTime<Utc>) -> bool {
+    last_page.len() >= PER_PAGE && last_page.last().and_then(|pr| pr.updated()).is_some_and(|updated| updated >= since)
+}
+
+/// Review hygiene for pull requests reviewed or merged after `since`, and those still open.
+/// Reviewer load is limited to `members` unless it is empty.
+pub fn review_report(
+    pulls: &BTreeMap<String, BTreeMap<i32, PullRequest>>,
+    reviews: &BTreeMap<String, BTreeMap<i32, Vec<Review>>>,
+    members: &[User],
+    since: DateTime<Utc>,
+) -> ReviewReport {
+    let now = Utc::now();
+    let mut open_age = vec![];
+    let mut time_to_first_review = vec![];
+    let mut time_to_merge = vec![];
+    let mut load = members.iter()
+        .map(|member| (member.login.clone(), (0, BTreeSet::new())))
+        .collect::<BTreeMap<String, (usize, BTreeSet<(String, i32)>)>>();
+
+    for (repo, prs) in pulls {
+        for pr in prs.values() {
+            let Some(opened) = pr.created() else { continue };
+            let author = pr.user.as_ref().map(|user| user.login.clone());
+
+            if pr.state.as_deref() == Some("open") {
+                open_age.push(now - opened);
+            }
+
+            if let Some(merged) = pr.merged().filter(|merged| *merged >= since) {
+                time_to_merge.push(merged - opened);
+            }
+
+            let pr_reviews = reviews.get(repo).and_then(|r| r.get(&pr.number)).cloned().unwrap_or_default();
+            let mut by_others = pr_reviews.iter()
+                .filter(|review| review.user.as_ref().map(|user| user.login.clone()) != author)
+                .filter_map(|review| Some((review.submitted()?, review.user.as_ref()?.login.clone())))
+                .collect::<Vec<_>>();
+            by_others.sort();
+
+            if let Some((first, _)) = by_others.first().filter(|(first, _)| *first >= since) {
+                time_to_first_review.push(*first - opened);
+            }
+
+            by_others.into_iter()
+                .filter(|(submitted, _)| *submitted >= since)
+                .filter(|(_, login)| members.is_empty() || members.iter().any(|member| &member.login == login))
+                .for_each(|(_, login)| {
+                    let (count, prs) = load.entry(login).or_default();
+                    *count += 1;
+                    prs.insert((repo.clone(), pr.number));
+                });
+        }
+    }
+
+    let mut reviewers = load.into_iter()
+        .map(|(login, (reviews, prs))| ReviewerLoad { login, reviews, pull_requests: prs.len() })
+        .collect::<Vec<_>>();
+    reviewers.sort_by(|a, b| b.reviews.cmp(&a.reviews).then(a.login.cmp(&b.login)));
+
+    ReviewReport {
+        open_age: LatencyStats::new(open_age),
+        time_to_first_review: LatencyStats::new(time_to_first_review),
+        time_to_merge: LatencyStats::new(time_to_merge),
+        reviewers,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(timestamp: &str) -> DateTime<Utc> {
+        crate::github::runs::parse_timestamp(timestamp).expect("timestamp")
+    }
+
+    fn pull(number: i32, state: &str, created_at: &str, updated_at: &str, merged_at: Option<&str>) -> PullRequest {
+        let branch = serde_json::json!({ "ref": "main", "sha": "c1", "repo": { "id": 1, "url": "", "name": "aap-api" } });
+        serde_json::from_value(serde_json::json!({
+            "id": number,
+            "number": number,
+            "url": "",
+            "head": branch,
+            "base": branch,
+            "html_url": null,
+            "title": null,
+            "body": null,
+            "state": state,
+            "user": { "login": "alice" },
+            "created_at": created_at,
+            "updated_at": updated_at,
+            "merged_at": merged_at,
+            "merge_commit_sha": null,
+        })).expect("pull request")
+    }
+
+    fn review(login: &str, submitted_at: &str) -> Review {
+        serde_json::from_value(serde_json::json!({
+            "id": 1,
+            "user": { "login": login },
+            "state": "APPROVED",
+            "submitted_at": submitted_at,
+        })).expect("review")
+    }
+
+    #[test]
+    fn latencies_are_bucketed_by_their_upper_bound() {
+        let stats = LatencyStats::new([1, 23, 24, 100, 700, 2, 30, 50, 60, 5000].into_iter().map(Duration::hours).collect());
+
+        assert_eq!(stats.count, 10);
+        assert_eq!(stats.median, Some(Duration::hours(30)));
+        assert_eq!(stats.p90, Some(Duration::hours(700)));
+        assert_eq!(stats.histogram, vec![3, 4, 1, 0, 0, 2]);
+    }
+
+    #[test]
+    fn no_latencies_have_no_percentiles() {
+        let stats = LatencyStats::new(vec![]);
+
+        assert_eq!((stats.count, stats.median, stats.p90), (0, None, None));
+        assert_eq!(stats.histogram, vec![0; BUCKETS.len()]);
+    }
+
+    #[test]
+    fn first_reviews_count_when_given_in_the_window() {
+        let since = at("2024-03-01T00:00:00Z");
+        let pulls = BTreeMap::from([(String::from("aap-api"), BTreeMap::from([
+            // Opened before the window, first reviewed in it, in a leap year.
+            (1, pull(1, "closed", "2024-02-20T00:00:00Z", "2024-03-02T00:00:00Z", Some("2024-03-02T00:00:00Z"))),
+            // First reviewed before the window.
+            (2, pull(2, "closed", "2024-02-01T00:00:00Z", "2024-03-03T00:00:00Z", None)),
+        ]))]);
+        let reviews = BTreeMap::from([(String::from("aap-api"), BTreeMap::from([
+            (1, vec![review("alice", "2024-02-21T00:00:00Z"), review("bob", "2024-03-01T12:00:00Z")]),
+            (2, vec![review("bob", "2024-02-02T00:00:00Z"), review("carol", "2024-03-03T00:00:00Z")]),
+        ]))]);
+
+        let report = review_report(&pulls, &reviews, &[], since);
+
+        // The author's own review doesn't count.
+        assert_eq!(report.time_to_first_review.count, 1);
+        assert_eq!(report.time_to_first_review.median, Some(Duration::hours(10 * 24 + 12)));
+        assert_eq!(report.time_to_merge.median, Some(Duration::days(11)));
+        let load = report.reviewers.iter().map(|r| (r.login.as_str(), r.reviews, r.pull_requests)).collect::<Vec<_>>();
+        assert_eq!(load, vec![("bob", 1, 1), ("carol", 1, 1)]);
+    }
+
+    #[test]
+    fn a_full_last_page_updated_in_the_window_is_truncated() {
+        let since = at("2024-03-01T00:00:00Z");
+        let page = |updated_at: &str, len: usize| (0..len as i32)
+            .map(|n| pull(n, "closed", "2024-01-01T00:00:00Z", updated_at, None))
+            .collect::<Vec<_>>();
+
+        assert!(is_truncated(&page("2024-03-02T00:00:00Z", PER_PAGE), since));
+        assert!(!is_truncated(&page("2024-02-28T00:00:00Z", PER_PAGE), since));
+        assert!(!is_truncated(&page("2024-03-02T00:00:00Z", PER_PAGE - 1), since));
+        assert!(may_be_reviewed_since(&pull(1, "closed", "2024-01-01T00:00:00Z", "2024-03-02T00:00:00Z", None), since));
+        assert!(!may_be_reviewed_since(&pull(1, "closed", "2024-03-02T00:00:00Z", "2024-02-28T00:00:00Z", None), since));
+        assert!(may_be_reviewed_since(&pull(1, "open", "2024-01-01T00:00:00Z", "2024-01-01T00:00:00Z", None), since));
+    }
+}
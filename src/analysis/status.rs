@@ -0,0 +1,28 @@
+use std::collections::BTreeMap;
+
+use crate::github::runs::{WorkflowRun, WorkflowRuns};
+
+/// Branches taken for the default branch of repositories it isn't known of yet.
+pub const DEFAULT_BRANCHES: [&str; 2] = ["main", "master"];
+
+/// Latest completed run of each workflow on `default_branch`, by workflow name.
+pub fn latest_default_branch_runs<'a>(runs: &'a WorkflowRuns, default_branch: Option<&str>) -> BTreeMap<String, &'a WorkflowRun> {
+    let mut latest = BTreeMap::<String, &WorkflowRun>::new();
+
+    let on_default_branch = |branch: &str| match default_branch {
+        Some(default_branch) => branch == default_branch,
+        None => DEFAULT_BRANCHES.contains(&branch),
+    };
+
+    runs.workflow_runs.iter()
+        .filter(|run| run.head_branch.as_deref().is_some_and(on_default_branch))
+        .filter(|run| run.conclusion.is_some())
+        .for_each(|run| {
+            let name = run.name.clone().unwrap_or_default();
+            if latest.get(&name).is_none_or(|other| other.created_at < run.created_at) {
+                latest.insert(name, run);
+            }
+        });
+
+    latest
+}
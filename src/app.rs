@@ -1,50 +1,171 @@
-use std::collections::{BTreeMap, HashSet};
-use std::sync::{Arc, Mutex};
+use std::collections::BTreeMap;
 
 use eframe::epaint::Color32;
 use egui::TextFormat;
-use ehttp::Response;
 
-use crate::github::github_client::{GitHubApi, Pulls, Runs, Teams};
-use crate::github::pulls::PullRequest;
+use crate::analysis::flaky;
+use crate::analysis::reviews::{is_truncated, may_be_reviewed_since, HISTORY_PAGES};
+use crate::config::Config;
+use crate::demo::{self, Demo};
+use crate::forge::{ChangeRequest, Forge, Group, PipelineDefinition, PipelineRun};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::github::app_auth::AppAuth;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::github::cassette::{Cassette, Mode};
+use crate::github::github_client::{spawn_then, BulkPulls, Pulls, Runs, Teams, Workflows};
+use crate::github::pulls::{PullRequest, User};
 use crate::github::runs::WorkflowRuns;
 use crate::github::teams::Team;
 use crate::github::workflows::Workflow;
-use crate::ui::table::Table;
+use crate::history::{self, History};
+use crate::offline::Data;
+use crate::profile::{self, Profile};
+use crate::token_store;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::ui::cassette::CassetteFile;
+use crate::ui::config_file::{ConfigAction, ConfigFile};
+#[cfg(not(target_arch = "wasm32"))]
+use std::sync::Arc;
 
 impl eframe::App for TemplateApp {
     /// Called each time the UI needs repainting, which may be many times per second.
     /// Put your widgets into a `SidePanel`, `TopPanel`, `CentralPanel`, `Window` or `Area`.
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
         let Self {
-            token,
+            profiles,
+            active,
             show_token,
-            pr_table,
-            run_table,
-            state,
-            repositories,
-            new_repo,
-            team,
-            teams: _,
-            teams_responses: _,
-            github,
-            pulls: _,
-            workflows: _,
-            runs: _,
+            config_file,
+            history,
+            #[cfg(not(target_arch = "wasm32"))]
+            app_auth,
+            #[cfg(not(target_arch = "wasm32"))]
+            cassette_file,
+            #[cfg(not(target_arch = "wasm32"))]
+            cassette,
+            demo,
         } = self;
 
+        // Recording or replaying applies to every profile, including ones added since.
+        #[cfg(not(target_arch = "wasm32"))]
+        profiles.iter_mut().for_each(|profile| {
+            profile.github.cassette = cassette.clone();
+            profile.forges.use_cassette(cassette);
+        });
+
         egui::TopBottomPanel::top("top_panel").show(ctx, |ui| {
             ui.horizontal_wrapped(|ui| {
-                ui.label("Personal Access Token:");
-                ui.add(egui::TextEdit::singleline(token).password(!show_token.clone()));
+                if let Some(shown) = demo {
+                    if !shown.ui(ui) {
+                        *demo = None;
+                    }
+                    return;
+                }
 
-                if ui.add(egui::SelectableLabel::new(show_token.clone(), "👁"))
-                    .on_hover_text("Show/hide token")
-                    .clicked() { *show_token = !show_token.clone(); };
+                profile::switcher_ui(ui, profiles, active);
+                ui.separator();
+
+                let Profile { token, token_store, forges, login, token_check, github, state, .. } = &mut profiles[*active];
+
+                #[cfg(not(target_arch = "wasm32"))]
+                let authenticated_as_app = app_auth_ui(ui, app_auth, &github.org);
+                #[cfg(target_arch = "wasm32")]
+                let authenticated_as_app = false;
+
+                if !authenticated_as_app {
+                    ui.label("Personal Access Token:");
+                    let editing = ui.add(egui::TextEdit::singleline(token).password(!*show_token)).has_focus();
+
+                    if ui.add(egui::SelectableLabel::new(*show_token, "👁"))
+                        .on_hover_text("Show/hide token")
+                        .clicked() { *show_token = !*show_token; };
+
+                    ui.checkbox(&mut token_store.remember, "Remember").on_hover_text(token_store::REMEMBER);
+                    if let Some(e) = token_store.error().or(forges.token_store_error()) {
+                        ui.colored_label(Color32::from_rgb(255, 100, 100), e);
+                    }
+
+                    login.ui(ui, github, token);
+                    token_check.ui(ui, github, token, editing, state.required_scopes());
+                }
+
+                ui.separator();
+                ui.label("API:");
+                ui.add(egui::TextEdit::singleline(&mut github.base_url).desired_width(200.0))
+                    .on_hover_text("GitHub API, or a shared backend started with `aap_status --serve` holding the token");
+                ui.label("Org:");
+                ui.add(egui::TextEdit::singleline(&mut github.org).desired_width(80.0));
+
+                #[cfg(not(target_arch = "wasm32"))]
+                match cassette.mode() {
+                    Mode::Record => { ui.colored_label(Color32::from_rgb(255, 100, 100), "⏺ Recording"); }
+                    Mode::Replay => { ui.label("▶ Replaying, offline"); }
+                    Mode::Off => {}
+                }
+
+                ui.separator();
+                if ui.button("🎭 Demo").on_hover_text("Show generated data instead, no token needed").clicked() {
+                    *demo = Some(Box::new(Demo::new(demo::SIZES[0])));
+                }
             });
         });
 
+        // The demo stands in for the active profile, with a history of its own.
+        let demo_mode = demo.is_some();
+        let mut regenerate = false;
+        let (profile, history) = match demo {
+            Some(demo) => (&mut demo.profile, &demo.history),
+            None => (&mut profiles[*active], &*history),
+        };
+
+        let Profile {
+            name: _,
+            token,
+            token_store: _,
+            github,
+            forges,
+            repositories,
+            new_repo,
+            team,
+            state,
+            pr_table,
+            run_table,
+            flaky_table,
+            charts,
+            dora,
+            review_stats,
+            notifications,
+            fetched,
+            teams,
+            teams_responses,
+            pulls,
+            workflows,
+            runs,
+            default_branches,
+            open_pulls,
+            change_requests,
+            pipeline_runs,
+            pipeline_definitions,
+            groups,
+            attempt_jobs,
+            pull_history,
+            truncated_history,
+            reviews,
+            team_members,
+            login: _,
+            token_check: _,
+        } = profile;
+
+        let all_pulls = forges.all_change_requests(&pulls.lock().unwrap(), &open_pulls.lock().unwrap(), &change_requests.lock().unwrap());
+
+        // Compared once per response rather than every frame.
+        let last_fetched = fetched.lock().unwrap().last_fetched();
+        if notifications.is_unchecked(last_fetched) {
+            notifications.check(last_fetched, &runs.lock().unwrap(), &default_branches.lock().unwrap(), &all_pulls);
+        }
+
         egui::SidePanel::left("side_panel").show(ctx, |ui| {
+            let shown = std::mem::discriminant(&*state);
             ui.heading("GitHub Status");
 
             ui.separator();
@@ -61,6 +182,34 @@ impl eframe::App for TemplateApp {
                 *state = State::Runs
             }
 
+            ui.separator();
+            ui.label("Workflows failing and passing on the same commit");
+
+            if ui.button("Flaky").clicked() {
+                *state = State::Flaky
+            }
+
+            ui.separator();
+            ui.label("Duration and success rate of a workflow");
+
+            if ui.button("Charts").clicked() {
+                *state = State::Charts
+            }
+
+            ui.separator();
+            ui.label("Deployment frequency, lead time and failures");
+
+            if ui.button("DORA").clicked() {
+                *state = State::Dora
+            }
+
+            ui.separator();
+            ui.label("Pull request age and review latency");
+
+            if ui.button("Reviews").clicked() {
+                *state = State::Reviews
+            }
+
             ui.separator();
             ui.label("Select repositories");
 
@@ -68,6 +217,13 @@ impl eframe::App for TemplateApp {
                 *state = State::Repositories
             }
 
+            ui.separator();
+            ui.label("Notify about failing workflows and review requests");
+
+            if ui.button("Notifications").clicked() {
+                *state = State::Notifications
+            }
+
             ui.separator();
             ui.label("Show teams");
 
@@ -78,42 +234,246 @@ impl eframe::App for TemplateApp {
             ui.separator();
             ui.label("Fetch data from GitHub");
 
-            if ui.button("Refresh").clicked() {
+            // Requests still queued for the view left are cancelled, those for this one go first.
+            if std::mem::discriminant(&*state) != shown {
+                github.scheduler.set_view(Some(&format!("{:?}", state)));
+            }
+            let refresh = ui.button("Refresh").clicked();
+            let queued = github.scheduler.running() + github.scheduler.waiting();
+            if queued > 0 {
+                ui.label(format!("{} requests pending", queued));
+                ctx.request_repaint();
+            }
+            if refresh && demo_mode {
+                // Generated data is made again instead of fetched.
+                regenerate = true;
+            } else if refresh {
+                // The installation token stands in for the profile's own, which is left as it is.
+                #[cfg(not(target_arch = "wasm32"))]
+                let mut installation_token;
+                #[cfg(not(target_arch = "wasm32"))]
+                let token = match app_auth {
+                    Some(Ok(app)) => match app.current(&github.org) {
+                        Some(current) => { installation_token = current; &mut installation_token }
+                        None => {
+                            let _ctx = ctx.clone();
+                            app.renew(&github.org, move || _ctx.request_repaint());
+                            return;
+                        }
+                    },
+                    _ => token,
+                };
+
+                // The history is synced in the background whatever view is on screen.
+                let background = &*github;
+                let github = &github.for_view(&format!("{:?}", state));
+
                 match state {
                     State::Pulls => {
-                        for repo in repositories.clone().into_iter() {
-                            let _pulls = self.pulls.clone();
-                            github.pull_requests(token, &repo.to_string(), move |response: Vec<PullRequest>| {
-                                *_pulls.lock().unwrap().entry(repo).or_default() = response;
+                        let (on_github, elsewhere): (Vec<String>, Vec<String>) = repositories.iter().cloned()
+                            .partition(|repo| forges.of(repo) == Forge::GitHub);
+
+                        for repo in elsewhere {
+                            let _change_requests = change_requests.clone();
+                            let _fetched = fetched.clone();
+                            let _repo = repo.clone();
+                            forges.change_requests(&repo, move |response: Vec<ChangeRequest>| {
+                                *_change_requests.lock().unwrap().entry(_repo).or_default() = response;
+                                _fetched.lock().unwrap().record(Data::Pulls);
                             });
                         }
+
+                        // A GraphQL query per batch of repositories, or a REST request per repository
+                        // when GraphQL isn't available, e.g. through a backend.
+                        let _open_pulls = open_pulls.clone();
+                        let _pulls = pulls.clone();
+                        let _fetched = fetched.clone();
+                        let _history = history.clone();
+                        let _github = github.clone();
+                        let mut _token = token.clone();
+                        let _repos = on_github.clone();
+                        github.open_pull_requests(token, &on_github, move |result| match result {
+                            Ok(found) => {
+                                _open_pulls.lock().unwrap().extend(found);
+                                _fetched.lock().unwrap().record(Data::Pulls);
+                            }
+                            Err(e) => {
+                                eprintln!("{}, falling back to REST", e);
+                                for repo in _repos {
+                                    let _open_pulls = _open_pulls.clone();
+                                    let _pulls = _pulls.clone();
+                                    let _fetched = _fetched.clone();
+                                    let _history = _history.clone();
+                                    let _key = history::key(&_github, &repo);
+                                    _github.pull_requests(&mut _token, &repo.to_string(), move |response: Vec<PullRequest>| {
+                                        _history.add_pulls(&_key, &response);
+                                        _open_pulls.lock().unwrap().remove(&repo);
+                                        *_pulls.lock().unwrap().entry(repo).or_default() = response;
+                                        _fetched.lock().unwrap().record(Data::Pulls);
+                                    });
+                                }
+                            }
+                        });
                     }
-                    State::Runs => {
+                    State::Runs | State::Flaky => {
                         for repo in repositories.clone().into_iter() {
-                            let _runs = self.runs.clone();
+                            if forges.of(&repo) != Forge::GitHub {
+                                let _pipeline_runs = pipeline_runs.clone();
+                                let _fetched = fetched.clone();
+                                let _repo = repo.clone();
+                                forges.pipeline_runs(&repo, move |response: Vec<PipelineRun>| {
+                                    *_pipeline_runs.lock().unwrap().entry(_repo).or_default() = response;
+                                    _fetched.lock().unwrap().record(Data::Runs);
+                                });
+                                continue;
+                            }
+
+                            if !default_branches.lock().unwrap().contains_key(&repo) {
+                                let (_default_branches, _github, _token, _repo) = (default_branches.clone(), github.clone(), token.clone(), repo.clone());
+                                let _key = repo.clone();
+                                spawn_then(async move { _github.fetch_default_branch(&_token, &_repo).await }, move |branch| {
+                                    _default_branches.lock().unwrap().insert(_key, branch);
+                                });
+                            }
+
+                            let _runs = runs.clone();
+                            let _fetched = fetched.clone();
+                            let _history = history.clone();
+                            let _key = history::key(github, &repo);
+                            // Which jobs failed before a success, once the runs tell which attempts did.
+                            let _flaky = (*state == State::Flaky).then(|| (attempt_jobs.clone(), github.clone(), token.clone(), repo.clone()));
                             github.runs(token, &repo.to_string(), move |response: WorkflowRuns| {
-                                *_runs.lock().unwrap().entry(repo).or_insert(WorkflowRuns::default()) = response;
+                                if let Some((_attempt_jobs, _github, _token, _repo)) = _flaky {
+                                    let missing = flaky::failed_attempts(&response).into_iter()
+                                        .filter(|key| !_attempt_jobs.lock().unwrap().contains_key(key))
+                                        .collect::<Vec<_>>();
+                                    for (run_id, attempt) in missing {
+                                        let (_attempt_jobs, _github, _token, _repo) = (_attempt_jobs.clone(), _github.clone(), _token.clone(), _repo.clone());
+                                        spawn_then(async move { _github.fetch_attempt_jobs(&_token, &_repo, run_id, attempt).await }, move |jobs| {
+                                            _attempt_jobs.lock().unwrap().insert((run_id, attempt), jobs);
+                                        });
+                                    }
+                                }
+                                _history.add_runs(&_key, &response.workflow_runs);
+                                *_runs.lock().unwrap().entry(repo).or_default() = response;
+                                _fetched.lock().unwrap().record(Data::Runs);
                             });
                         }
                         // for repo in repositories.clone().into_iter() {
-                        //     let _workflows = self.workflows.clone();
+                        //     let _workflows = workflows.clone();
                         //     github.workflows(token, &repo.to_string(), move |response: Vec<Workflow>| {
                         //         *_workflows.lock().unwrap().entry(repo).or_default() = response;
                         //     });
                         // }
                     }
+                    State::Charts => {
+                        if let Some(repo) = charts.repo.clone().filter(|repo| forges.of(repo) != Forge::GitHub) {
+                            let _pipeline_definitions = pipeline_definitions.clone();
+                            let _fetched = fetched.clone();
+                            let _repo = repo.clone();
+                            forges.pipeline_definitions(&repo, move |response: Vec<PipelineDefinition>| {
+                                *_pipeline_definitions.lock().unwrap().entry(_repo).or_default() = response;
+                                _fetched.lock().unwrap().record(Data::Workflows);
+                            });
+
+                            // Only the latest runs, as those of other forges are kept in no history.
+                            let _pipeline_runs = pipeline_runs.clone();
+                            let _repo = repo.clone();
+                            forges.pipeline_runs(&repo, move |response: Vec<PipelineRun>| {
+                                *_pipeline_runs.lock().unwrap().entry(_repo).or_default() = response;
+                            });
+                        } else if let Some(repo) = charts.repo.clone() {
+                            let _workflows = workflows.clone();
+                            let _fetched = fetched.clone();
+                            let _repo = repo.clone();
+                            github.workflows(token, &repo, move |response: Vec<Workflow>| {
+                                *_workflows.lock().unwrap().entry(_repo).or_default() = response;
+                                _fetched.lock().unwrap().record(Data::Workflows);
+                            });
+
+                            if let Some(workflow) = &charts.workflow {
+                                history.sync_workflow_runs(background, token, &repo, workflow);
+                            }
+                        }
+                    }
+                    State::Dora => {
+                        for repo in repositories.iter() {
+                            history.sync_pulls(background, token, repo);
+                            history.sync_workflow_runs(background, token, repo, &dora.deploy_workflow(repo));
+                        }
+                    }
+                    State::Reviews => {
+                        let since = review_stats.since();
+
+                        for repo in repositories.clone().into_iter() {
+                            for page in 1..=HISTORY_PAGES {
+                                let _pull_history = pull_history.clone();
+                                let _truncated_history = truncated_history.clone();
+                                let _reviews = reviews.clone();
+                                let _history = history.clone();
+                                let _key = history::key(github, &repo);
+                                let _repo = repo.clone();
+                                let _token = token.clone();
+                                let _github = github.clone();
+                                // The reviews of the pull requests in the page, once the page is in.
+                                spawn_then(async move {
+                                    let response = _github.fetch_pull_request_history(&_token, &_repo, page).await?;
+                                    _history.add_pulls(&_key, &response);
+                                    _pull_history.lock().unwrap().entry(_repo.clone()).or_default()
+                                        .extend(response.iter().map(|pr| (pr.number, pr.clone())));
+                                    if page == HISTORY_PAGES {
+                                        match is_truncated(&response, since) {
+                                            true => _truncated_history.lock().unwrap().insert(_repo.clone()),
+                                            false => _truncated_history.lock().unwrap().remove(&_repo),
+                                        };
+                                    }
+
+                                    let reviewed = response.iter()
+                                        .filter(|pr| may_be_reviewed_since(pr, since))
+                                        .map(|pr| async {
+                                            let number = pr.number;
+                                            match _github.fetch_reviews(&_token, &_repo, number).await {
+                                                Ok(response) => { _reviews.lock().unwrap().entry(_repo.clone()).or_default().insert(number, response); }
+                                                Err(e) => eprintln!("{}", e),
+                                            }
+                                        });
+                                    futures::future::join_all(reviewed).await;
+                                    Ok(())
+                                }, |()| {});
+                            }
+                        }
+
+                        if let Some(team) = team.clone() {
+                            let _team_members = team_members.clone();
+                            github.team_members(token, &team.slug, move |response: Vec<User>| {
+                                *_team_members.lock().unwrap() = response;
+                            });
+                        }
+                    }
                     State::Teams => {
                         for i in 1..=3 {
-                            let _teams_responses = self.teams_responses.clone();
-                            let url = format!("https://api.github.com/orgs/navikt/teams?per_page=100&page={}", i);
+                            let _teams_responses = teams_responses.clone();
+                            let _fetched = fetched.clone();
+                            let url = format!("{}/orgs/{}/teams?per_page=100&page={}", github.base_url, github.org, i);
 
                             github.teams(&url, token, move |teams_response| {
+                                if teams_response.ok {
+                                    _fetched.lock().unwrap().record(Data::Teams);
+                                }
                                 _teams_responses.lock().unwrap().push(teams_response.clone());
                             });
                         }
 
-                        let responses = self.teams_responses.clone();
-                        let teams = self.teams.clone();
+                        let in_use = forges.in_use();
+                        groups.lock().unwrap().retain(|forge, _| in_use.iter().any(|f| f.name() == forge));
+                        for forge in in_use {
+                            let _groups = groups.clone();
+                            forges.groups(forge, move |response: Vec<Group>| {
+                                _groups.lock().unwrap().insert(forge.name().to_string(), response);
+                            });
+                        }
+
+                        let responses = teams_responses.clone();
                         *teams.lock().unwrap() = responses.lock().unwrap().clone().into_iter().flat_map(|res| {
                             match serde_json::from_slice::<Vec<Team>>(&res.bytes) {
                                 Ok(teams) => {
@@ -135,8 +495,15 @@ impl eframe::App for TemplateApp {
             }
         });
 
+        let mut config_action = None;
+
         egui::CentralPanel::default().show(ctx, |ui| {
             use egui_extras::{Size, StripBuilder};
+
+            if let Some(data) = state.offline_data() {
+                fetched.lock().unwrap().banner_ui(ui, data);
+            }
+
             match state {
                 State::Pulls => {
                     ui.heading("Pull Requests");
@@ -146,7 +513,7 @@ impl eframe::App for TemplateApp {
                         .vertical(|mut strip| {
                             strip.cell(|ui| {
                                 egui::ScrollArea::horizontal().show(ui, |ui| {
-                                    pr_table.pull_requests_ui(ui, &self.pulls.lock().unwrap().clone())
+                                    pr_table.pull_requests_ui(ui, &all_pulls)
                                 });
                             });
                         });
@@ -159,13 +526,99 @@ impl eframe::App for TemplateApp {
                         .vertical(|mut strip| {
                             strip.cell(|ui| {
                                 egui::ScrollArea::horizontal().show(ui, |ui| {
-                                    let _runs = &self.runs.lock().unwrap().clone();
-                                    let _workflows = &self.workflows.lock().unwrap().clone();
-                                    run_table.workflow_runs_ui(ui, _runs)
+                                    let _runs = forges.all_pipeline_runs(&runs.lock().unwrap(), &pipeline_runs.lock().unwrap());
+                                    run_table.workflow_runs_ui(ui, &_runs)
+                                });
+                            });
+                        });
+                }
+                State::Flaky => {
+                    ui.heading("Flaky Workflows");
+
+                    let report = flaky::flaky_workflows(&runs.lock().unwrap(), &attempt_jobs.lock().unwrap());
+                    ui.label(format!("{} workflows passed on a commit after failing on it", report.len()));
+
+                    StripBuilder::new(ui)
+                        .size(Size::remainder().at_least(100.0))
+                        .vertical(|mut strip| {
+                            strip.cell(|ui| {
+                                egui::ScrollArea::horizontal().show(ui, |ui| {
+                                    flaky_table.flaky_workflows_ui(ui, &report)
                                 });
                             });
                         });
                 }
+                State::Charts => {
+                    ui.heading("Charts");
+
+                    let definitions = workflows.lock().unwrap().iter()
+                        .map(|(repo, workflows)| (repo.clone(), workflows.iter().map(PipelineDefinition::from).collect()))
+                        .filter(|(repo, _)| forges.of(repo) == Forge::GitHub)
+                        .chain(pipeline_definitions.lock().unwrap().clone().into_iter().filter(|(repo, _)| forges.of(repo) != Forge::GitHub))
+                        .collect::<BTreeMap<_, Vec<_>>>();
+                    charts.selection_ui(ui, repositories, &definitions);
+                    ui.separator();
+
+                    match (charts.repo.clone(), charts.workflow.clone()) {
+                        (Some(repo), Some(workflow)) if forges.of(&repo) != Forge::GitHub => {
+                            let _runs = pipeline_runs.lock().unwrap().get(&repo).into_iter().flatten()
+                                .filter(|run| run.definition_id == workflow)
+                                .cloned()
+                                .collect::<Vec<_>>();
+                            charts.workflow_runs_ui(ui, &_runs);
+                        }
+                        (Some(repo), Some(workflow)) => {
+                            let _runs = history.workflow_runs(&history::key(github, &repo), &workflow).values()
+                                .map(PipelineRun::from)
+                                .collect::<Vec<_>>();
+                            charts.workflow_runs_ui(ui, &_runs);
+                        }
+                        _ => { ui.label("Select a repository and press Refresh to list its workflows."); }
+                    }
+                }
+                State::Dora => {
+                    ui.heading("DORA Metrics");
+
+                    dora.settings_ui(ui, repositories);
+                    ui.separator();
+
+                    StripBuilder::new(ui)
+                        .size(Size::remainder().at_least(100.0))
+                        .vertical(|mut strip| {
+                            strip.cell(|ui| {
+                                egui::ScrollArea::horizontal().show(ui, |ui| {
+                                    let _pulls = repositories.iter()
+                                        .map(|repo| (repo.clone(), history.pulls(&history::key(github, repo))))
+                                        .collect();
+                                    let _deploy_runs = repositories.iter()
+                                        .map(|repo| (repo.clone(), history.workflow_runs(&history::key(github, repo), &dora.deploy_workflow(repo))))
+                                        .collect();
+                                    dora.metrics_ui(ui, &_pulls, &_deploy_runs)
+                                });
+                            });
+                        });
+                }
+                State::Reviews => {
+                    ui.heading("Reviews");
+
+                    ui.horizontal_wrapped(|ui| {
+                        review_stats.settings_ui(ui);
+                        ui.label(team.clone().map_or(String::from("Reviewer load for everyone, select a team to limit it"), |t| format!("Reviewer load for {}", t.name)));
+                    });
+                    ui.separator();
+
+                    egui::ScrollArea::vertical().show(ui, |ui| {
+                        let _pulls = &pull_history.lock().unwrap().clone();
+                        let _reviews = &reviews.lock().unwrap().clone();
+                        let _members = &team_members.lock().unwrap().clone();
+                        review_stats.report_ui(ui, _pulls, _reviews, _members, &truncated_history.lock().unwrap());
+                    });
+                }
+                State::Notifications => {
+                    ui.heading("Notifications");
+
+                    notifications.settings_ui(ui, &runs.lock().unwrap().clone(), repositories);
+                }
                 State::Repositories => {
                     ui.heading("Repositories");
 
@@ -182,7 +635,9 @@ impl eframe::App for TemplateApp {
                             job.append("❌", 0.0, red_text);
                             if ui.button(job).clicked() {
                                 repositories.remove(&repo);
+                                forges.set(&repo, Forge::GitHub);
                             };
+                            forges.repository_ui(ui, &repo);
                             ui.label(&repo);
                         });
                     });
@@ -208,62 +663,128 @@ impl eframe::App for TemplateApp {
                             repositories.insert(new_repo.clone());
                         }
                     });
+
+                    ui.separator();
+
+                    ui.horizontal(|ui| {
+                        ui.label("Requests to GitHub at once:");
+                        ui.add(egui::DragValue::new(&mut github.max_concurrency).clamp_range(1..=32))
+                            .on_hover_text("More is faster with many repositories, until GitHub limits the rate");
+                    });
+
+                    ui.separator();
+
+                    ui.label("Repositories hosted on GitLab or Forgejo are found here");
+                    forges.settings_ui(ui);
+
+                    ui.separator();
+
+                    ui.label("Share the repositories and settings of this profile");
+                    config_action = config_file.ui(ui);
+
+                    #[cfg(not(target_arch = "wasm32"))]
+                    {
+                        ui.separator();
+                        ui.label("Record the responses from GitHub, or replay them offline");
+                        cassette_file.ui(ui, cassette);
+                    }
                 }
                 State::Teams => {
                     ui.heading("Teams");
-                    ui.label(format!("Found {} teams in org/navikt", self.teams.lock().unwrap().clone().len()));
+                    ui.label(format!("Found {} teams in org/{}", teams.lock().unwrap().clone().len(), github.org));
 
                     let show_text = team.clone().map_or(String::from("Not selected"), |map| { map.name });
 
                     egui::ComboBox::from_label("team")
                         .selected_text(format!("{:?}", show_text))
                         .show_ui(ui, |ui| {
-                            self.teams.lock().unwrap().clone().into_iter().for_each(|t| {
+                            teams.lock().unwrap().clone().into_iter().for_each(|t| {
                                 ui.selectable_value(team, Some(t.clone()), &t.name);
                             });
                         });
+
+                    for (forge, groups) in groups.lock().unwrap().iter() {
+                        ui.separator();
+                        ui.label(format!("Found {} groups on {}", groups.len(), forge));
+                        ui.horizontal_wrapped(|ui| {
+                            groups.iter().for_each(|group| { ui.hyperlink_to(&group.name, &group.url); });
+                        });
+                    }
                 }
             };
         });
+
+        let shown = match demo {
+            Some(demo) => &mut demo.profile,
+            None => &mut profiles[*active],
+        };
+        match config_action {
+            Some(ConfigAction::Export) => config_file.export(&Config::from_profile(shown)),
+            Some(ConfigAction::Import(config)) => config.apply(shown),
+            None => {}
+        }
+
+        if regenerate {
+            if let Some(demo) = demo {
+                demo.generate();
+            }
+        }
     }
 
     /// Called by the frame work to save state before shutdown.
     fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        // Installation tokens of a GitHub App are short lived and renewed from the environment.
+        #[cfg(not(target_arch = "wasm32"))]
+        let save_token = !matches!(self.app_auth, Some(Ok(_)));
+        #[cfg(target_arch = "wasm32")]
+        let save_token = true;
+
+        if save_token {
+            for profile in &mut self.profiles {
+                profile.token_store.save(&profile.token);
+                profile.forges.save_tokens(profile.token_store.remember);
+            }
+        }
+
+        #[cfg(not(target_arch = "wasm32"))]
+        self.cassette_file.save(&self.cassette);
+
         eframe::set_value(storage, eframe::APP_KEY, self);
     }
 }
 
+/// Repositories watched until the user picks their own.
+pub const DEFAULT_REPOSITORIES: [&str; 13] = [
+    "aap-andre-ytelser",
+    "aap-api",
+    "aap-bot",
+    "aap-devtools",
+    "aap-inntekt",
+    "aap-libs",
+    "aap-meldeplikt",
+    "aap-oppgavestyring",
+    "aap-personopplysninger",
+    "aap-sink",
+    "aap-sykepengedager",
+    "aap-utbetaling",
+    "aap-vedtak",
+];
+
 impl Default for TemplateApp {
     fn default() -> Self {
         Self {
-            token: String::from("<GitHub PAT>"),
+            profiles: vec![Profile::default()],
+            active: 0,
             show_token: false,
-            pr_table: Table::default(),
-            run_table: Table::default(),
-            state: State::Repositories,
-            repositories: HashSet::from([
-                "aap-andre-ytelser".to_string(),
-                "aap-api".to_string(),
-                "aap-bot".to_string(),
-                "aap-devtools".to_string(),
-                "aap-inntekt".to_string(),
-                "aap-libs".to_string(),
-                "aap-meldeplikt".to_string(),
-                "aap-oppgavestyring".to_string(),
-                "aap-personopplysninger".to_string(),
-                "aap-sink".to_string(),
-                "aap-sykepengedager".to_string(),
-                "aap-utbetaling".to_string(),
-                "aap-vedtak".to_string(),
-            ]),
-            new_repo: String::from("<repo>"),
-            team: None,
-            teams: Arc::new(Mutex::new(vec![])),
-            teams_responses: Arc::new(Mutex::new(vec![])),
-            github: GitHubApi::default(),
-            pulls: Arc::new(Mutex::new(BTreeMap::new())),
-            workflows: Arc::new(Mutex::new(BTreeMap::new())),
-            runs: Arc::new(Mutex::new(BTreeMap::new())),
+            config_file: ConfigFile::default(),
+            history: History::default(),
+            #[cfg(not(target_arch = "wasm32"))]
+            app_auth: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            cassette_file: CassetteFile::default(),
+            #[cfg(not(target_arch = "wasm32"))]
+            cassette: Cassette::default(),
+            demo: None,
         }
     }
 }
@@ -276,51 +797,132 @@ impl TemplateApp {
 
         // Load previous app state (if any).
         // Note that you must enable the `persistence` feature for this to work.
-        if let Some(storage) = cc.storage {
-            return eframe::get_value(storage, eframe::APP_KEY).unwrap_or_default();
+        let mut app: Self = cc.storage
+            .and_then(|storage| eframe::get_value(storage, eframe::APP_KEY))
+            .unwrap_or_default();
+
+        // State saved before there were profiles holds the fields of a single profile.
+        if app.profiles.is_empty() {
+            let profile = cc.storage
+                .and_then(|storage| eframe::get_value::<Profile>(storage, eframe::APP_KEY))
+                .unwrap_or_default();
+            app.profiles.push(profile);
+        }
+        app.active = app.active.min(app.profiles.len() - 1);
+
+        app.history = History::open();
+
+        for profile in &mut app.profiles {
+            if let Some(token) = profile.token_store.load() {
+                profile.token = token;
+            }
+            profile.forges.load_tokens();
+            // Afterwards the view is set when it changes, see `update`.
+            profile.github.scheduler.set_view(Some(&format!("{:?}", profile.state)));
+        }
+
+        // Authenticate as a GitHub App when GITHUB_APP_ID and friends are set.
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            app.app_auth = AppAuth::from_env().map(|app_auth| app_auth.map(Arc::new));
+            app.cassette_file.start_from_env(&app.cassette);
+        }
+
+        app
+    }
+}
+
+/// Shows how the GitHub App authenticates, returning whether it does instead of the profile's token.
+#[cfg(not(target_arch = "wasm32"))]
+fn app_auth_ui(ui: &mut egui::Ui, app_auth: &Option<Result<Arc<AppAuth>, String>>, org: &str) -> bool {
+    let app = match app_auth {
+        Some(Ok(app)) => app,
+        Some(Err(e)) => {
+            ui.colored_label(Color32::from_rgb(255, 100, 100), format!("GitHub App: {}", e));
+            return false;
         }
+        None => return false,
+    };
+
+    // Renewed ahead of expiry, and after a failure only when Refresh is clicked.
+    if app.error().is_none() {
+        let ctx = ui.ctx().clone();
+        app.renew(org, move || ctx.request_repaint());
+    }
 
-        Default::default()
+    ui.label(format!("Authenticated as GitHub App {}", app.app_id()));
+    match (app.current(org), app.error()) {
+        (_, Some(e)) => { ui.colored_label(Color32::from_rgb(255, 100, 100), e); }
+        (None, None) => { ui.spinner(); }
+        (Some(_), None) => {}
     }
+    true
 }
 
-#[derive(PartialEq)]
+#[derive(PartialEq, Debug)]
 #[derive(serde::Deserialize, serde::Serialize)]
 pub enum State {
     Repositories,
     Teams,
     Pulls,
     Runs,
+    Flaky,
+    Charts,
+    Dora,
+    Reviews,
+    Notifications,
+}
+
+impl State {
+    /// The data shown in this view which is kept for offline use.
+    fn offline_data(&self) -> Option<Data> {
+        match self {
+            State::Pulls => Some(Data::Pulls),
+            State::Runs | State::Flaky | State::Notifications => Some(Data::Runs),
+            State::Charts => Some(Data::Workflows),
+            State::Teams => Some(Data::Teams),
+            State::Repositories | State::Dora | State::Reviews => None,
+        }
+    }
+
+    /// Token scopes needed to fetch the data shown in this view.
+    fn required_scopes(&self) -> &'static [&'static str] {
+        match self {
+            State::Runs | State::Flaky | State::Charts | State::Dora | State::Notifications => &["repo", "workflow"],
+            State::Teams | State::Reviews => &["repo", "read:org"],
+            State::Repositories | State::Pulls => &["repo"],
+        }
+    }
 }
 
 /// We derive Deserialize/Serialize so we can persist app state on shutdown.
 #[derive(serde::Deserialize, serde::Serialize)]
 #[serde(default)]
 pub struct TemplateApp {
-    token: String,
+    /// Empty when restoring state saved before there were profiles, see [`TemplateApp::new`].
+    #[serde(default)]
+    profiles: Vec<Profile>,
+    active: usize,
     show_token: bool,
-    pr_table: Table,
-    run_table: Table,
-    state: State,
-    repositories: HashSet<String>,
-    new_repo: String,
-    team: Option<Team>,
+    config_file: ConfigFile,
 
     #[serde(skip)]
-    teams: Arc<Mutex<Vec<Team>>>,
+    history: History,
 
+    #[cfg(not(target_arch = "wasm32"))]
+    /// Set when GITHUB_APP_ID is, with why the app can't authenticate if so.
     #[serde(skip)]
-    teams_responses: Arc<Mutex<Vec<Response>>>,
+    app_auth: Option<Result<Arc<AppAuth>, String>>,
 
-    #[serde(skip)]
-    github: GitHubApi,
+    #[cfg(not(target_arch = "wasm32"))]
+    cassette_file: CassetteFile,
 
+    /// Shared by the clients of all profiles.
+    #[cfg(not(target_arch = "wasm32"))]
     #[serde(skip)]
-    pulls: Arc<Mutex<BTreeMap<String, Vec<PullRequest>>>>,
+    cassette: Cassette,
 
+    /// Shown instead of the active profile while set.
     #[serde(skip)]
-    workflows: Arc<Mutex<BTreeMap<String, Vec<Workflow>>>>,
-
-    #[serde(skip)]
-    runs: Arc<Mutex<BTreeMap<String, WorkflowRuns>>>,
-}
\ No newline at end of file
+    demo: Option<Box<Demo>>,
+}
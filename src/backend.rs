@@ -0,0 +1,399 @@
+//! Shared backend holding the GitHub token for a whole team. It polls the repositories, caches the
+//! responses and serves them on the same paths as the GitHub REST API, so the web app only needs its
+//! API URL pointed here instead of a personal access token, and everyone shares one quota.
+//!
+//! Only the requests the app makes are answered, for the configured repositories and the teams of
+//! the organisation, so the token can't be used to read anything else. Clients give the shared
+//! secret as their token, which is required unless the backend only listens on localhost.
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use tiny_http::{Method, Request, Response};
+
+use crate::cli::Args;
+use crate::exporter::header;
+use crate::github::github_client::GITHUB_API;
+
+const USAGE: &str = "\
+Usage: aap_status --serve [--listen <address>] [--interval <seconds>] [--token <token>] [--repo <name>]...
+                          [--config <file>] [--secret <secret>] [--allow-origin <origin>]
+                          [--app-id <id> --private-key <pem file> [--installation-id <id>]]
+
+Polls the repositories every interval (default 300 seconds) and serves the cached GitHub API
+responses on http://<address> (default 127.0.0.1:9090). Point the API URL of the app there.
+The token defaults to the GITHUB_TOKEN environment variable, or authenticate as a GitHub App.
+
+Clients must give the secret (default the AAP_STATUS_SECRET environment variable) as their
+token, which is required to listen on other addresses than localhost. Browsers only let the web
+app served from the allowed origin (default http://127.0.0.1:8080) call the backend.";
+
+const LISTEN: &str = "127.0.0.1:9090";
+
+/// Where `trunk serve` serves the web app.
+const ALLOW_ORIGIN: &str = "http://127.0.0.1:8080";
+
+/// Requests answered at once. The rest wait for a worker, instead of a thread each.
+const WORKERS: usize = 8;
+
+/// Responses kept at most. The oldest are dropped first, and none outlive the poll interval.
+const MAX_CACHED: usize = 1000;
+
+/// Pages the app asks for at most, of pull request and workflow run history.
+const MAX_PAGE: u32 = 10;
+
+/// The OAuth device flow, passed on to github.com without the token since browsers can't reach it.
+const OAUTH_PATHS: [&str; 2] = ["/login/device/code", "/login/oauth/access_token"];
+
+#[derive(Clone)]
+struct Cached {
+    fetched: Instant,
+    status: u16,
+    body: Vec<u8>,
+    link: Option<String>,
+}
+
+type Cache = Arc<Mutex<HashMap<String, Cached>>>;
+
+/// Runs the backend with the command line arguments following `--serve`, returning the exit code.
+pub fn run(args: &[String]) -> i32 {
+    let args = match Args::parse(args) {
+        Ok(args) => args,
+        Err(e) => { eprintln!("{}\n\n{}", e, USAGE); return 2; }
+    };
+
+    if args.help {
+        println!("{}", USAGE);
+        return 0;
+    }
+
+    let listen = args.listen.clone().unwrap_or_else(|| String::from(LISTEN));
+    if args.secret.is_none() && !is_loopback(&listen) {
+        eprintln!("A secret is required to listen on {}, use --secret or set AAP_STATUS_SECRET\n\n{}", listen, USAGE);
+        return 2;
+    }
+
+    let server = match tiny_http::Server::http(&listen) {
+        Ok(server) => Arc::new(server),
+        Err(e) => { eprintln!("error: {:?} when listening on {}", e, listen); return 2; }
+    };
+
+    let args = Arc::new(args);
+    let cache: Cache = Arc::new(Mutex::new(HashMap::new()));
+
+    let _args = args.clone();
+    let _cache = cache.clone();
+    std::thread::spawn(move || poll(&_args, &_cache));
+
+    let workers = (0..WORKERS).map(|_| {
+        let (_server, _args, _cache) = (server.clone(), args.clone(), cache.clone());
+        std::thread::spawn(move || {
+            for request in _server.incoming_requests() {
+                handle(request, &_args, &_cache);
+            }
+        })
+    }).collect::<Vec<_>>();
+
+    workers.into_iter().for_each(|worker| { let _ = worker.join(); });
+    0
+}
+
+/// Keeps the data shown by the Pull Requests and Workflows views warm,
+/// with the same paths as `GitHubApi::pull_requests` and `GitHubApi::runs`.
+fn poll(args: &Args, cache: &Cache) {
+    let org = args.github().org;
+    loop {
+        for repo in &args.repos {
+            refresh(args, cache, &format!("/repos/{}/{}/pulls", org, repo));
+            refresh(args, cache, &format!("/repos/{}/{}/actions/runs?per_page=100", org, repo));
+        }
+        std::thread::sleep(args.interval);
+    }
+}
+
+fn handle(mut request: Request, args: &Args, cache: &Cache) {
+    let url = request.url().to_string();
+    let authorized = match &args.secret {
+        Some(secret) => request.headers().iter()
+            .filter(|h| h.field.equiv("Authorization"))
+            .filter_map(|h| h.value.as_str().trim().strip_prefix("Bearer "))
+            .any(|given| is_secret(given, secret)),
+        None => true,
+    };
+
+    let response = match request.method() {
+        Method::Options => Response::from_data(vec![]).with_status_code(204),
+        // Only the user's own credentials pass through here, never the token of the backend.
+        Method::Post if OAUTH_PATHS.contains(&url.as_str()) => {
+            let mut body = vec![];
+            match request.as_reader().read_to_end(&mut body) {
+                Ok(_) => oauth(&url, body),
+                Err(_) => Response::from_string("Bad request").with_status_code(400),
+            }
+        }
+        Method::Get if !authorized => Response::from_string("Unauthorized").with_status_code(401),
+        Method::Get => match allowed(&url, &args.github().org, &args.repos) {
+            Some(path) => {
+                let cached = cache.lock().unwrap().get(&path).cloned()
+                    .filter(|cached| cached.fetched.elapsed() < args.interval);
+
+                match cached.or_else(|| refresh(args, cache, &path)) {
+                    Some(cached) => {
+                        let response = Response::from_data(cached.body)
+                            .with_status_code(cached.status)
+                            .with_header(header("Content-Type", "application/json"));
+                        match cached.link {
+                            Some(link) => response.with_header(header("Link", &link)),
+                            None => response,
+                        }
+                    }
+                    None => Response::from_string("Bad gateway").with_status_code(502),
+                }
+            }
+            None => Response::from_string("Not found").with_status_code(404),
+        },
+        _ => Response::from_string("Not found").with_status_code(404),
+    };
+
+    let response = response
+        .with_header(header("Access-Control-Allow-Origin", args.allow_origin.as_deref().unwrap_or(ALLOW_ORIGIN)))
+        .with_header(header("Access-Control-Allow-Headers", "Accept, Authorization, Content-Type, User-Agent, Access-Control-Allow-Headers"))
+        .with_header(header("Access-Control-Expose-Headers", "Link"));
+
+    if let Err(e) = request.respond(response) {
+        eprintln!("error: {:?} when responding to {:?}", e, url);
+    }
+}
+
+fn oauth(path: &str, body: Vec<u8>) -> Response<std::io::Cursor<Vec<u8>>> {
+    let url = format!("https://github.com{}", path);
+
+    let request = ehttp::Request {
+        headers: ehttp::headers(&[
+            ("Accept", "application/json"),
+            ("Content-Type", "application/json"),
+            ("User-Agent", "aap_status backend"),
+        ]),
+        ..ehttp::Request::post(&url, body)
+    };
+
+    match ehttp::fetch_blocking(&request) {
+        Ok(res) => Response::from_data(res.bytes)
+            .with_status_code(res.status)
+            .with_header(header("Content-Type", "application/json")),
+        Err(e) => {
+            eprintln!("Error {:?} from {:?}", e, &url);
+            Response::from_string("Bad gateway").with_status_code(502)
+        }
+    }
+}
+
+fn is_loopback(listen: &str) -> bool {
+    ["127.", "localhost:", "[::1]:"].iter().any(|prefix| listen.starts_with(prefix))
+}
+
+/// A query parameter the app sends, and the values it sends.
+#[derive(Clone, Copy)]
+enum Param {
+    Fixed(&'static str, &'static str),
+    Page,
+    /// A filter on when runs were created, e.g. `>=2023-01-31`, with `<`, `=` and `>` encoded.
+    Created,
+}
+
+impl Param {
+    fn name(&self) -> &'static str {
+        match self {
+            Param::Fixed(name, _) => name,
+            Param::Page => "page",
+            Param::Created => "created",
+        }
+    }
+
+    fn allows(&self, value: &str) -> bool {
+        match self {
+            Param::Fixed(_, fixed) => value == *fixed,
+            Param::Page => value.parse::<u32>().is_ok_and(|page| (1..=MAX_PAGE).contains(&page)),
+            Param::Created => {
+                let decoded = value.replace("%3E", ">").replace("%3C", "<").replace("%3D", "=");
+                let date = decoded.trim_start_matches(['>', '<', '=']);
+                decoded.len() - date.len() <= 2 && chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d").is_ok()
+            }
+        }
+    }
+}
+
+const PER_PAGE: Param = Param::Fixed("per_page", "100");
+
+/// The path to fetch from GitHub and cache under for a request the app makes, with the query
+/// parameters in a fixed order, or `None` for anything else.
+fn allowed(url: &str, org: &str, repos: &[String]) -> Option<String> {
+    let (path, query) = url.split_once('?').unwrap_or((url, ""));
+    if path.contains("..") || path.contains('%') {
+        return None;
+    }
+
+    let segments = path.strip_prefix('/')?.split('/').collect::<Vec<_>>();
+    let params: &[Param] = match segments.as_slice() {
+        ["rate_limit"] => &[],
+        ["repos", owner, repo, rest @ ..] if *owner == org && repos.iter().any(|r| r == repo) => match rest {
+            [] => &[],
+            ["pulls"] => &[
+                Param::Fixed("state", "all"), Param::Fixed("sort", "updated"), Param::Fixed("direction", "desc"),
+                PER_PAGE, Param::Page,
+            ],
+            ["pulls", number, "reviews"] if number.parse::<u32>().is_ok() => &[PER_PAGE],
+            ["actions", "runs"] => &[PER_PAGE],
+            ["actions", "runs", id, "attempts", attempt, "jobs"] if id.parse::<i64>().is_ok() && attempt.parse::<i32>().is_ok() => &[PER_PAGE],
+            ["actions", "workflows"] => &[],
+            ["actions", "workflows", workflow, "runs"] if is_name(workflow) => &[PER_PAGE, Param::Page, Param::Created],
+            _ => return None,
+        },
+        ["orgs", owner, "teams"] if *owner == org => &[PER_PAGE, Param::Page],
+        ["orgs", owner, "teams", slug, "members"] if *owner == org && is_name(slug) => &[PER_PAGE],
+        _ => return None,
+    };
+
+    let mut query_params = BTreeMap::new();
+    for pair in query.split('&').filter(|pair| !pair.is_empty()) {
+        let (name, value) = pair.split_once('=')?;
+        let param = params.iter().find(|param| param.name() == name)?;
+        if !param.allows(value) || query_params.insert(param.name(), value).is_some() {
+            return None;
+        }
+    }
+
+    let query = query_params.iter().map(|(name, value)| format!("{}={}", name, value)).collect::<Vec<_>>();
+    match query.is_empty() {
+        true => Some(path.to_string()),
+        false => Some(format!("{}?{}", path, query.join("&"))),
+    }
+}
+
+/// Compares every byte whatever the first to differ, so the time taken doesn't give the secret away.
+fn is_secret(given: &str, secret: &str) -> bool {
+    let (given, secret) = (given.as_bytes(), secret.as_bytes());
+    given.len() == secret.len() && given.iter().zip(secret).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+/// Names of workflow files and team slugs.
+fn is_name(segment: &str) -> bool {
+    !segment.is_empty() && segment.chars().all(|c| c.is_ascii_alphanumeric() || "-_.".contains(c))
+}
+
+/// Fetches the path from GitHub, caching successful responses.
+fn refresh(args: &Args, cache: &Cache, path: &str) -> Option<Cached> {
+    let url = format!("{}{}", GITHUB_API, path);
+
+    let token = match args.current_token() {
+        Ok(token) => token,
+        Err(e) => {
+            eprintln!("{}", e);
+            return None;
+        }
+    };
+
+    let request = ehttp::Request {
+        headers: ehttp::headers(&[
+            ("Accept", "application/vnd.github+json"),
+            ("User-Agent", "aap_status backend"),
+            ("Authorization", format!("Bearer {}", token.trim()).as_str()),
+        ]),
+        ..ehttp::Request::get(&url)
+    };
+
+    match ehttp::fetch_blocking(&request) {
+        Ok(res) => {
+            let cached = Cached {
+                fetched: Instant::now(),
+                status: res.status,
+                link: res.headers.get("link").cloned(),
+                body: res.bytes,
+            };
+
+            if res.ok {
+                let mut cache = cache.lock().unwrap();
+                cache.retain(|_, cached| cached.fetched.elapsed() < args.interval);
+                if cache.len() >= MAX_CACHED {
+                    let oldest = cache.iter().min_by_key(|(_, cached)| cached.fetched).map(|(path, _)| path.clone());
+                    oldest.map(|path| cache.remove(&path));
+                }
+                cache.insert(path.to_string(), cached.clone());
+            }
+            Some(cached)
+        }
+        Err(e) => {
+            eprintln!("Error {:?} from {:?}", e, &url);
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn allowed_path(url: &str) -> Option<String> {
+        allowed(url, "navikt", &[String::from("aap-api")])
+    }
+
+    #[test]
+    fn requests_of_the_app_are_allowed_in_a_fixed_order() {
+        assert_eq!(allowed_path("/repos/navikt/aap-api"), Some(String::from("/repos/navikt/aap-api")));
+        assert_eq!(
+            allowed_path("/repos/navikt/aap-api/pulls?per_page=100&page=2&state=all&sort=updated&direction=desc"),
+            Some(String::from("/repos/navikt/aap-api/pulls?direction=desc&page=2&per_page=100&sort=updated&state=all")),
+        );
+        assert_eq!(
+            allowed_path("/repos/navikt/aap-api/actions/workflows/deploy.yml/runs?per_page=100&created=%3E%3D2024-03-01"),
+            Some(String::from("/repos/navikt/aap-api/actions/workflows/deploy.yml/runs?created=%3E%3D2024-03-01&per_page=100")),
+        );
+        assert!(allowed_path("/orgs/navikt/teams/aap/members?per_page=100").is_some());
+    }
+
+    #[test]
+    fn other_organisations_and_repositories_are_rejected() {
+        assert_eq!(allowed_path("/repos/other/aap-api/pulls"), None);
+        assert_eq!(allowed_path("/repos/navikt/secret-repo/pulls"), None);
+        assert_eq!(allowed_path("/orgs/other/teams?per_page=100"), None);
+        assert_eq!(allowed_path("/orgs/other/teams/aap/members?per_page=100"), None);
+    }
+
+    #[test]
+    fn paths_escaping_the_repositories_are_rejected() {
+        assert_eq!(allowed_path("/repos/navikt/aap-api/../secret-repo/pulls"), None);
+        assert_eq!(allowed_path("/repos/navikt/aap-api/actions/workflows/../runs"), None);
+        assert_eq!(allowed_path("/repos/navikt/aap-api/%2e%2e/secret-repo"), None);
+        assert_eq!(allowed_path("/orgs/navikt/teams/../../user/members?per_page=100"), None);
+    }
+
+    #[test]
+    fn unknown_endpoints_are_rejected() {
+        assert_eq!(allowed_path("/user"), None);
+        assert_eq!(allowed_path("/repos/navikt/aap-api/contents/README.md"), None);
+        assert_eq!(allowed_path("/repos/navikt/aap-api/actions/secrets"), None);
+        assert_eq!(allowed_path("/repos/navikt/aap-api/pulls/abc/reviews?per_page=100"), None);
+        assert_eq!(allowed_path("repos/navikt/aap-api"), None);
+    }
+
+    #[test]
+    fn unknown_repeated_or_odd_query_parameters_are_rejected() {
+        assert_eq!(allowed_path("/repos/navikt/aap-api/actions/runs?per_page=100&branch=main"), None);
+        assert_eq!(allowed_path("/repos/navikt/aap-api/actions/runs?per_page=100&per_page=100"), None);
+        assert_eq!(allowed_path("/repos/navikt/aap-api/actions/runs?per_page=1000"), None);
+        assert_eq!(allowed_path("/repos/navikt/aap-api/actions/runs?per_page"), None);
+        assert_eq!(allowed_path("/repos/navikt/aap-api/pulls?page=11"), None);
+        assert_eq!(allowed_path("/repos/navikt/aap-api?per_page=100"), None);
+        assert_eq!(allowed_path("/repos/navikt/aap-api/actions/workflows/deploy.yml/runs?created=yesterday"), None);
+    }
+
+    #[test]
+    fn only_the_whole_secret_is_the_secret() {
+        assert!(is_secret("s3cret", "s3cret"));
+        assert!(!is_secret("s3cres", "s3cret"));
+        assert!(!is_secret("s3cre", "s3cret"));
+        assert!(!is_secret("s3crett", "s3cret"));
+        assert!(!is_secret("", "s3cret"));
+    }
+}
@@ -0,0 +1,6 @@
+#![warn(clippy::all, rust_2018_idioms)]
+
+fn main() -> std::io::Result<()> {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    aap_status::tui::run(&args)
+}
@@ -0,0 +1,155 @@
+//! Command line arguments shared by the modes that run without the GUI.
+
+use std::time::Duration;
+
+use crate::app::DEFAULT_REPOSITORIES;
+use crate::config::Config;
+use crate::github::app_auth::AppAuth;
+use crate::github::cassette::{Cassette, Mode};
+use crate::github::github_client::GitHubApi;
+
+/// Where the GUI exports its configuration to unless told otherwise.
+pub const DEFAULT_CONFIG: &str = "aap_status.toml";
+
+pub struct Args {
+    pub json: bool,
+    pub help: bool,
+    pub token: String,
+    pub repos: Vec<String>,
+    /// The configuration exported from the GUI, if any.
+    pub config: Option<Config>,
+    /// Address to serve on, each mode has its own default.
+    pub listen: Option<String>,
+    /// Required from clients of the backend as their token, see [`crate::backend`].
+    pub secret: Option<String>,
+    /// The origin of the web app allowed to call the backend.
+    pub allow_origin: Option<String>,
+    pub interval: Duration,
+    pub app: Option<AppAuth>,
+    /// Where to save the responses recorded with `--record`.
+    pub record: Option<String>,
+    pub cassette: Cassette,
+}
+
+impl Args {
+    /// Parses `--json`, `--help`, `--token <token>`, `--repo <name>` (repeatable), `--config <file>`,
+    /// `--listen <address>`, `--interval <seconds>`, `--secret <secret>` and `--allow-origin <origin>`.
+    /// The token defaults to the GITHUB_TOKEN environment variable, and the secret to AAP_STATUS_SECRET.
+    ///
+    /// The repositories default to those of the configuration exported from the GUI, read from
+    /// `--config` or else from [`DEFAULT_CONFIG`] when there is one, and otherwise to the ones the
+    /// GUI starts with. The organisation and API of the configuration are used as well.
+    ///
+    /// Instead of a token, `--app-id <id>`, `--private-key <pem file>` and optionally
+    /// `--installation-id <id>` authenticate as a GitHub App, see [`AppAuth::from_env`] for the
+    /// environment variables doing the same.
+    ///
+    /// `--record <file>` saves the GitHub responses to a cassette file, and `--replay <file>`
+    /// answers requests from one without network or token.
+    pub fn parse(args: &[String]) -> Result<Self, String> {
+        let mut parsed = Self {
+            json: false,
+            help: false,
+            token: std::env::var("GITHUB_TOKEN").unwrap_or_default(),
+            repos: vec![],
+            config: None,
+            listen: None,
+            secret: std::env::var("AAP_STATUS_SECRET").ok().filter(|secret| !secret.is_empty()),
+            allow_origin: None,
+            interval: Duration::from_secs(300),
+            app: None,
+            record: None,
+            cassette: Cassette::default(),
+        };
+
+        let mut app_id = None;
+        let mut installation_id = None;
+        let mut private_key = None;
+
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--json" => parsed.json = true,
+                "--help" => parsed.help = true,
+                "--token" | "--repo" | "--config" | "--listen" | "--interval" | "--app-id" | "--installation-id" | "--private-key"
+                | "--record" | "--replay" | "--secret" | "--allow-origin" => {
+                    let value = args.next().ok_or_else(|| format!("Missing value for {}", arg))?.clone();
+                    match arg.as_str() {
+                        "--token" => parsed.token = value,
+                        "--repo" => parsed.repos.push(value),
+                        "--config" => parsed.config = Some(Config::load(&value)?),
+                        "--listen" => parsed.listen = Some(value),
+                        "--secret" => parsed.secret = Some(value),
+                        "--allow-origin" => parsed.allow_origin = Some(value),
+                        "--app-id" => app_id = Some(value),
+                        "--installation-id" => installation_id = Some(value),
+                        "--private-key" => private_key = Some(value),
+                        "--record" => {
+                            parsed.cassette.record();
+                            parsed.record = Some(value);
+                        }
+                        "--replay" => parsed.cassette.replay(Cassette::load(&value)?),
+                        _ => {
+                            let seconds = value.parse().map_err(|_| format!("Invalid interval {:?}", value))?;
+                            parsed.interval = Duration::from_secs(seconds);
+                        }
+                    }
+                }
+                _ => return Err(format!("Unknown argument {:?}", arg)),
+            }
+        }
+
+        if parsed.config.is_none() && std::path::Path::new(DEFAULT_CONFIG).exists() {
+            parsed.config = Some(Config::load(DEFAULT_CONFIG)?);
+        }
+        if parsed.repos.is_empty() {
+            parsed.repos = match &parsed.config {
+                Some(config) => config.github_repositories(),
+                None => DEFAULT_REPOSITORIES.iter().map(|repo| repo.to_string()).collect(),
+            };
+        }
+        parsed.repos.sort();
+
+        parsed.app = match (app_id, private_key) {
+            (Some(app_id), Some(path)) => {
+                let key = std::fs::read(&path).map_err(|e| format!("Could not read {}: {}", path, e))?;
+                Some(AppAuth::new(&app_id, installation_id, &key)?)
+            }
+            (Some(_), None) | (None, Some(_)) => return Err(String::from("--app-id and --private-key go together")),
+            (None, None) => AppAuth::from_env().transpose()?,
+        };
+
+        let replaying = parsed.cassette.mode() == Mode::Replay;
+        if parsed.token.is_empty() && parsed.app.is_none() && !parsed.help && !replaying {
+            return Err(String::from("Missing token, use --token or set GITHUB_TOKEN"));
+        }
+
+        Ok(parsed)
+    }
+
+    /// A client going through the cassette when recording or replaying.
+    pub fn github(&self) -> GitHubApi {
+        let github = self.config.as_ref().map(|config| config.github().clone()).unwrap_or_default();
+        GitHubApi {
+            cassette: self.cassette.clone(),
+            ..github
+        }
+    }
+
+    /// Writes what has been recorded so far with `--record`, if anything.
+    pub fn save_recording(&self) {
+        if let Some(path) = &self.record {
+            if let Err(e) = self.cassette.save(path) {
+                eprintln!("{}", e);
+            }
+        }
+    }
+
+    /// The installation token when authenticating as a GitHub App, otherwise the given token.
+    pub fn current_token(&self) -> Result<String, String> {
+        match &self.app {
+            Some(app) => app.token(&self.github().org),
+            None => Ok(self.token.clone()),
+        }
+    }
+}
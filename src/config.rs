@@ -0,0 +1,131 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde::{Deserialize, Serialize};
+
+use crate::forge::forgejo::ForgejoApi;
+use crate::forge::gitlab::GitLabApi;
+use crate::forge::Forge;
+use crate::github::github_client::GitHubApi;
+use crate::github::teams::Team;
+use crate::notifications::Notifications;
+use crate::profile::Profile;
+use crate::ui::charts::Charts;
+use crate::ui::dora::Dora;
+use crate::ui::reviews::ReviewStats;
+use crate::ui::table::Table;
+
+/// The shareable part of a profile: what to watch and how to show it, never the token.
+/// Lets a team lead hand everyone the same dashboard as a TOML or JSON file.
+#[derive(Deserialize, Serialize, Default)]
+#[serde(default)]
+pub struct Config {
+    github: GitHubApi,
+    repositories: BTreeSet<String>,
+    /// Repositories not on GitHub, and where they are.
+    forges: BTreeMap<String, Forge>,
+    gitlab: GitLabApi,
+    forgejo: ForgejoApi,
+    team: Option<Team>,
+    pr_table: Table,
+    run_table: Table,
+    flaky_table: Table,
+    charts: Charts,
+    dora: Dora,
+    review_stats: ReviewStats,
+    notifications: Notifications,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Format {
+    Toml,
+    Json,
+}
+
+impl Format {
+    /// JSON for `.json` files, TOML otherwise.
+    pub fn of(location: &str) -> Self {
+        match location.trim().to_lowercase().ends_with(".json") {
+            true => Format::Json,
+            false => Format::Toml,
+        }
+    }
+}
+
+impl Config {
+    pub fn from_profile(profile: &Profile) -> Self {
+        Self {
+            github: profile.github.clone(),
+            repositories: profile.repositories.iter().cloned().collect(),
+            forges: profile.forges.repositories.clone(),
+            gitlab: profile.forges.gitlab.clone(),
+            forgejo: profile.forges.forgejo.clone(),
+            team: profile.team.clone(),
+            pr_table: profile.pr_table.clone(),
+            run_table: profile.run_table.clone(),
+            flaky_table: profile.flaky_table.clone(),
+            charts: profile.charts.clone(),
+            dora: profile.dora.clone(),
+            review_stats: profile.review_stats.clone(),
+            notifications: profile.notifications.shared(),
+        }
+    }
+
+    /// Replaces the settings of `profile`, keeping its name and token.
+    pub fn apply(self, profile: &mut Profile) {
+        profile.github = self.github;
+        profile.repositories = self.repositories.into_iter().collect();
+        profile.forges.repositories = self.forges;
+        profile.forges.gitlab = self.gitlab;
+        profile.forges.forgejo = self.forgejo;
+        profile.team = self.team;
+        profile.pr_table = self.pr_table;
+        profile.run_table = self.run_table;
+        profile.flaky_table = self.flaky_table;
+        profile.charts = self.charts;
+        profile.dora = self.dora;
+        profile.review_stats = self.review_stats;
+        profile.notifications.apply(self.notifications);
+    }
+
+    /// Where GitHub is and which organisation, for the modes without the GUI.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn github(&self) -> &GitHubApi {
+        &self.github
+    }
+
+    /// Which repositories and workflows are muted, for the modes without the GUI.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn notifications(&self) -> &Notifications {
+        &self.notifications
+    }
+
+    /// The repositories on GitHub, for the modes without the GUI, which don't fetch from other forges.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn github_repositories(&self) -> Vec<String> {
+        self.repositories.iter()
+            .filter(|repo| self.forges.get(*repo).copied().unwrap_or_default() == Forge::GitHub)
+            .cloned()
+            .collect()
+    }
+
+    /// Reads an exported configuration, TOML or JSON like [`Format::of`] tells from the name.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn load(path: &str) -> Result<Self, String> {
+        let content = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+        Self::parse(&content, Format::of(path)).map_err(|e| format!("{}: {}", path, e))
+    }
+
+    pub fn to_string(&self, format: Format) -> Result<String, String> {
+        match format {
+            Format::Toml => toml::to_string_pretty(self).map_err(|e| e.to_string()),
+            Format::Json => serde_json::to_string_pretty(self).map_err(|e| e.to_string()),
+        }
+    }
+
+    pub fn parse(content: &str, format: Format) -> Result<Self, String> {
+        match format {
+            Format::Toml => toml::from_str(content).map_err(|e| e.to_string()),
+            Format::Json => serde_json::from_str(content).map_err(|e| e.to_string()),
+        }
+    }
+}
@@ -0,0 +1,473 @@
+//! Generated but realistic data for showing the dashboard at demos, taking screenshots and trying
+//! the UI with hundreds of repositories, all without a token. Nothing is fetched, and nothing of
+//! it is persisted.
+
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Duration, SecondsFormat, Utc};
+use egui::Ui;
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+
+use crate::github::github_client::GitHubApi;
+use crate::github::pulls::{PullRequest, Review, User};
+use crate::github::runs::{WorkflowRun, WorkflowRuns};
+use crate::github::teams::Team;
+use crate::github::workflows::Workflow;
+use crate::history::{self, History};
+use crate::offline::Data;
+use crate::profile::{Profile, ReviewsByRepo};
+
+/// How many repositories to generate, the larger ones for seeing how the UI copes.
+pub const SIZES: [usize; 3] = [12, 100, 500];
+
+const ORG: &str = "demo-org";
+/// How far back the history of each repository goes.
+const DAYS: i64 = 120;
+
+const NAMES: [&str; 24] = [
+    "aap-api", "aap-vedtak", "aap-sink", "aap-bot", "aap-inntekt", "aap-meldeplikt",
+    "aap-oppgavestyring", "aap-utbetaling", "aap-personopplysninger", "aap-sykepengedager",
+    "aap-libs", "aap-devtools", "aap-andre-ytelser", "aap-soknad", "aap-brev", "aap-statistikk",
+    "aap-mottak", "aap-behandlingsflyt", "aap-postmottak", "aap-tilgang", "aap-saksbehandling",
+    "aap-frontend", "aap-journalforing", "aap-arena",
+];
+
+const PEOPLE: [&str; 6] = ["alice", "bob", "carol", "dave", "erin", "frank"];
+const DEPENDABOT: &str = "dependabot[bot]";
+
+const TITLES: [&str; 10] = [
+    "Add endpoint for meldekort",
+    "Fix rounding of daily rates",
+    "Migrate to Kafka streams 3.6",
+    "Log correlation id on all requests",
+    "Remove unused feature toggle",
+    "Validate periods before saving",
+    "Speed up the search for cases",
+    "Handle missing employer gracefully",
+    "Split the vedtak service in two",
+    "Update the API documentation",
+];
+
+const DEPENDENCIES: [&str; 5] = ["ktor", "kotlin", "logback", "jackson", "testcontainers"];
+
+const TEAMS: [(&str, &str); 3] = [("Team AAP", "aap"), ("AAP admin", "aap-admin"), ("Team Tiltak", "tiltak")];
+
+/// Stands in for the active profile while shown, see [`TemplateApp`](crate::TemplateApp).
+pub struct Demo {
+    pub profile: Profile,
+    /// Only in memory, the real history is left alone.
+    pub history: History,
+    size: usize,
+}
+
+impl Demo {
+    pub fn new(size: usize) -> Self {
+        let mut demo = Self {
+            profile: Profile {
+                name: String::from("Demo"),
+                github: GitHubApi { org: ORG.to_string(), ..GitHubApi::default() },
+                ..Profile::default()
+            },
+            history: History::default(),
+            size,
+        };
+        demo.generate();
+        demo
+    }
+
+    /// Returns false when the user leaves the demo.
+    pub fn ui(&mut self, ui: &mut Ui) -> bool {
+        ui.label("🎭 Demo with generated data, nothing is fetched from GitHub");
+        ui.separator();
+
+        for size in SIZES {
+            if ui.selectable_label(self.size == size, format!("{} repositories", size)).clicked() && self.size != size {
+                self.size = size;
+                self.generate();
+            }
+        }
+
+        ui.separator();
+        !ui.button("Leave demo").clicked()
+    }
+
+    /// Generates the data again, up until now. The same size always gives the same data, while
+    /// what is shown in the views is kept.
+    pub fn generate(&mut self) {
+        let now = Utc::now();
+        let mut rng = Rng(0x9e37_79b9_7f4a_7c15 ^ self.size as u64);
+        let history = History::default();
+
+        let mut pulls = BTreeMap::new();
+        let mut runs = BTreeMap::new();
+        let mut workflows = BTreeMap::new();
+        let mut pull_history = BTreeMap::new();
+        let mut reviews: ReviewsByRepo = BTreeMap::new();
+
+        let repos = (0..self.size).map(repo_name).collect::<Vec<_>>();
+        for (i, repo) in repos.iter().enumerate() {
+            let generated = Repository::generate(&mut rng, repo, i as i64, now);
+            let key = history::key(&self.profile.github, repo);
+
+            history.add_runs(&key, &generated.runs);
+            history.add_pulls(&key, &generated.pulls);
+
+            let mut latest = generated.runs.clone();
+            latest.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+            latest.truncate(100);
+            runs.insert(repo.clone(), WorkflowRuns { total_count: generated.runs.len() as i32, workflow_runs: latest });
+
+            pulls.insert(repo.clone(), generated.pulls.iter().filter(|pr| pr.state.as_deref() == Some("open")).cloned().collect());
+            pull_history.insert(repo.clone(), generated.pulls.into_iter().map(|pr| (pr.number, pr)).collect());
+            reviews.insert(repo.clone(), generated.reviews);
+            workflows.insert(repo.clone(), generated.workflows);
+        }
+
+        let teams = TEAMS.iter().enumerate()
+            .map(|(i, (name, slug))| team(i as i64, name, slug))
+            .collect::<Vec<_>>();
+
+        let profile = &mut self.profile;
+        profile.repositories = repos.iter().cloned().collect();
+        if profile.charts.repo.as_ref().is_none_or(|repo| !profile.repositories.contains(repo)) {
+            profile.charts.repo = repos.first().cloned();
+            profile.charts.workflow = Some(workflow_id(0, 1).to_string());
+        }
+        profile.team = teams.first().cloned();
+
+        *profile.pulls.lock().unwrap() = pulls;
+        *profile.runs.lock().unwrap() = runs;
+        *profile.workflows.lock().unwrap() = workflows;
+        *profile.pull_history.lock().unwrap() = pull_history;
+        *profile.reviews.lock().unwrap() = reviews;
+        *profile.teams.lock().unwrap() = teams;
+        *profile.team_members.lock().unwrap() = PEOPLE.iter().map(|login| user(login)).collect();
+
+        let mut fetched = profile.fetched.lock().unwrap();
+        [Data::Pulls, Data::Runs, Data::Workflows, Data::Teams].into_iter().for_each(|data| fetched.record(data));
+
+        self.history = history;
+    }
+}
+
+fn repo_name(i: usize) -> String {
+    match i / NAMES.len() {
+        0 => NAMES[i].to_string(),
+        n => format!("{}-{}", NAMES[i % NAMES.len()], n + 1),
+    }
+}
+
+/// Workflow ids are unique across repositories, like on GitHub.
+fn workflow_id(repo: i64, workflow: i64) -> i64 {
+    1000 + repo * 10 + workflow
+}
+
+/// Everything generated for one repository.
+struct Repository {
+    workflows: Vec<Workflow>,
+    runs: Vec<WorkflowRun>,
+    pulls: Vec<PullRequest>,
+    reviews: BTreeMap<i32, Vec<Review>>,
+}
+
+impl Repository {
+    fn generate(rng: &mut Rng, repo: &str, index: i64, now: DateTime<Utc>) -> Self {
+        let workflows = [("Build", "build.yml"), ("Deploy", "deploy.yml"), ("CodeQL", "codeql.yml")].iter().enumerate()
+            .map(|(i, (name, file))| workflow(workflow_id(index, i as i64 + 1), name, file))
+            .collect::<Vec<_>>();
+        let [build, deploy, codeql] = [0, 1, 2].map(|i| run_template(repo, &workflows[i]));
+
+        let mut generated = Self {
+            workflows,
+            runs: vec![],
+            pulls: vec![],
+            reviews: BTreeMap::new(),
+        };
+
+        let mut run_id = index * 100_000;
+        let mut next_run_id = || { run_id += 1; run_id };
+        let mut number = 0;
+
+        // Merged pull requests, each built and deployed from the default branch. The newest ones
+        // of some repositories are still being built or deployed.
+        let mut merges = vec![];
+        let mut merged = now - Duration::days(DAYS) + rng.minutes(0, 96 * 60);
+        while merged < now {
+            merges.push(merged);
+            merged += rng.minutes(12 * 60, 96 * 60);
+        }
+        if rng.chance(0.15) {
+            merges.push(now - rng.minutes(1, 10));
+        }
+
+        for merged in merges {
+            number += 1;
+            let sha = rng.sha();
+            let author = if rng.chance(0.2) { DEPENDABOT } else { *rng.pick(&PEOPLE) };
+            let opened = merged - rng.minutes(60, 5 * 24 * 60);
+            let title = title(rng, author);
+            generated.pulls.push(pull_request(repo, index, number, &title, author, opened, merged, Some(merged), Some(&sha), &[]));
+
+            let reviewer = reviewer(rng, author);
+            let reviewed = opened + (merged - opened) / 2;
+            generated.reviews.insert(number, vec![review(next_run_id(), reviewer, "APPROVED", reviewed)]);
+
+            // A second attempt passing after the first failed is a flake.
+            let attempt = if rng.chance(0.015) { 2 } else { 1 };
+            let built = merged + rng.minutes(3, 12);
+            let conclusion = match (built > now, attempt, rng.below(100)) {
+                (true, _, _) => None,
+                (false, 2, _) => Some("success"),
+                (false, _, 0..=8) => Some("failure"),
+                (false, _, 9..=11) => Some("cancelled"),
+                _ => Some("success"),
+            };
+            generated.runs.push(run(&build, next_run_id(), &sha, "main", "push", merged, now.min(built), conclusion, attempt));
+
+            if conclusion == Some("success") {
+                let deployed = built + rng.minutes(2, 6);
+                let conclusion = match (deployed > now, rng.chance(0.08)) {
+                    (true, _) => None,
+                    (false, true) => Some("failure"),
+                    (false, false) => Some("success"),
+                };
+                generated.runs.push(run(&deploy, next_run_id(), &sha, "main", "push", built, now.min(deployed), conclusion, 1));
+            }
+        }
+
+        // Weekly code scanning.
+        let mut scanned = now - Duration::days(DAYS);
+        while scanned + Duration::days(7) < now {
+            scanned += Duration::days(7);
+            let conclusion = if rng.chance(0.05) { "failure" } else { "success" };
+            generated.runs.push(run(&codeql, next_run_id(), &rng.sha(), "main", "schedule", scanned, scanned + rng.minutes(4, 9), Some(conclusion), 1));
+        }
+
+        // Open pull requests, some left alone for weeks.
+        for _ in 0..rng.below(6) {
+            number += 1;
+            let sha = rng.sha();
+            let author = if rng.chance(0.3) { DEPENDABOT } else { *rng.pick(&PEOPLE) };
+            let opened = if rng.chance(0.25) {
+                now - rng.minutes(14 * 24 * 60, 60 * 24 * 60)
+            } else {
+                now - rng.minutes(30, 6 * 24 * 60)
+            };
+            let title = title(rng, author);
+            let reviewers = match rng.below(3) {
+                0 => vec![],
+                _ => vec![reviewer(rng, author)],
+            };
+            generated.pulls.push(pull_request(repo, index, number, &title, author, opened, now.min(opened + rng.minutes(10, 48 * 60)), None, None, &reviewers));
+
+            if rng.chance(0.4) {
+                let state = *rng.pick(&["COMMENTED", "CHANGES_REQUESTED"]);
+                let reviewed = now.min(opened + rng.minutes(30, 24 * 60));
+                generated.reviews.insert(number, vec![review(next_run_id(), reviewer(rng, author), state, reviewed)]);
+            }
+
+            let branch = format!("pr-{}", number);
+            let conclusion = *rng.pick(&[Some("success"), Some("success"), Some("success"), Some("failure"), None]);
+            generated.runs.push(run(&build, next_run_id(), &sha, &branch, "pull_request", opened, now.min(opened + rng.minutes(3, 12)), conclusion, 1));
+        }
+
+        generated
+    }
+}
+
+fn title(rng: &mut Rng, author: &str) -> String {
+    match author {
+        DEPENDABOT => {
+            let minor = rng.below(10);
+            format!("Bump {} from 2.{}.0 to 2.{}.0", rng.pick(&DEPENDENCIES), minor, minor + 1)
+        }
+        _ => rng.pick(&TITLES).to_string(),
+    }
+}
+
+fn reviewer(rng: &mut Rng, author: &str) -> &'static str {
+    let reviewer = *rng.pick(&PEOPLE);
+    match reviewer == author {
+        true => PEOPLE.iter().find(|person| **person != author).copied().unwrap_or(reviewer),
+        false => reviewer,
+    }
+}
+
+fn timestamp(time: DateTime<Utc>) -> String {
+    time.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+/// The models only deserialize from what GitHub returns, so the data is generated as that.
+fn parse<T: DeserializeOwned>(value: Value) -> T {
+    serde_json::from_value(value).expect("generated data matches the GitHub models")
+}
+
+fn user(login: &str) -> User {
+    parse(json!({ "login": login }))
+}
+
+fn team(id: i64, name: &str, slug: &str) -> Team {
+    parse(json!({
+        "name": name,
+        "id": id,
+        "node_id": format!("T_{}", id),
+        "slug": slug,
+        "description": null,
+        "privacy": "closed",
+        "url": format!("https://api.github.com/organizations/1/team/{}", id),
+        "html_url": format!("https://github.com/orgs/{}/teams/{}", ORG, slug),
+        "members_url": format!("https://api.github.com/organizations/1/team/{}/members{{/member}}", id),
+        "repositories_url": format!("https://api.github.com/organizations/1/team/{}/repos", id),
+        "permission": "pull",
+    }))
+}
+
+fn workflow(id: i64, name: &str, file: &str) -> Workflow {
+    parse(json!({
+        "id": id,
+        "node_id": format!("W_{}", id),
+        "name": name,
+        "path": format!(".github/workflows/{}", file),
+        "state": "active",
+    }))
+}
+
+/// A run of the workflow with the fields that can't be set afterwards, parsed once per workflow
+/// since there are thousands of runs.
+fn run_template(repo: &str, workflow: &Workflow) -> WorkflowRun {
+    let api = format!("https://api.github.com/repos/{}/{}/actions", ORG, repo);
+    parse(json!({
+        "id": 0,
+        "name": workflow.name,
+        "check_suite_id": null,
+        "check_suite_node_id": null,
+        "head_sha": "",
+        "head_branch": null,
+        "path": workflow.path,
+        "run_number": 0,
+        "run_attempt": 1,
+        "event": "push",
+        "status": null,
+        "conclusion": null,
+        "workflow_id": workflow.id,
+        "url": format!("{}/runs", api),
+        "html_url": format!("https://github.com/{}/{}", ORG, repo),
+        "pull_requests": [],
+        "created_at": "",
+        "updated_at": "",
+        "actor": null,
+        "triggering_actor": null,
+        "run_started_at": null,
+        "jobs_url": format!("{}/jobs", api),
+        "logs_url": format!("{}/runs/logs", api),
+        "check_suite_url": format!("https://api.github.com/repos/{}/{}/check-suites", ORG, repo),
+        "artifacts_url": format!("{}/artifacts", api),
+        "cancel_url": format!("{}/runs/cancel", api),
+        "rerun_url": format!("{}/runs/rerun", api),
+        "workflow_url": format!("{}/workflows/{}", api, workflow.id),
+        "display_title": workflow.name,
+    }))
+}
+
+#[allow(clippy::too_many_arguments)]
+fn run(
+    template: &WorkflowRun,
+    id: i64,
+    sha: &str,
+    branch: &str,
+    event: &str,
+    created: DateTime<Utc>,
+    finished: DateTime<Utc>,
+    conclusion: Option<&str>,
+    attempt: i32,
+) -> WorkflowRun {
+    let mut run = template.clone();
+    run.id = id;
+    run.head_sha = sha.to_string();
+    run.head_branch = Some(branch.to_string());
+    run.run_attempt = attempt;
+    run.event = event.to_string();
+    run.status = Some(String::from(if conclusion.is_some() { "completed" } else { "in_progress" }));
+    run.conclusion = conclusion.map(str::to_string);
+    run.html_url = format!("{}/actions/runs/{}", template.html_url, id);
+    run.created_at = timestamp(created);
+    run.updated_at = timestamp(finished);
+    run.run_started_at = Some(timestamp(created));
+    run
+}
+
+#[allow(clippy::too_many_arguments)]
+fn pull_request(
+    repo: &str,
+    repo_id: i64,
+    number: i32,
+    title: &str,
+    author: &str,
+    opened: DateTime<Utc>,
+    updated: DateTime<Utc>,
+    merged: Option<DateTime<Utc>>,
+    merge_commit_sha: Option<&str>,
+    reviewers: &[&str],
+) -> PullRequest {
+    let api = format!("https://api.github.com/repos/{}/{}", ORG, repo);
+    let repo_json = json!({ "id": repo_id, "url": api, "name": repo });
+    parse(json!({
+        "id": repo_id * 100_000 + number as i64,
+        "number": number,
+        "url": format!("{}/pulls/{}", api, number),
+        "head": { "ref": format!("pr-{}", number), "sha": format!("{:07x}", number), "repo": repo_json },
+        "base": { "ref": "main", "sha": "0000000", "repo": repo_json },
+        "html_url": format!("https://github.com/{}/{}/pull/{}", ORG, repo, number),
+        "title": title,
+        "body": null,
+        "state": if merged.is_some() { "closed" } else { "open" },
+        "user": { "login": author },
+        "created_at": timestamp(opened),
+        "updated_at": timestamp(merged.unwrap_or(updated)),
+        "merged_at": merged.map(timestamp),
+        "merge_commit_sha": merge_commit_sha,
+        "requested_reviewers": reviewers.iter().map(|login| json!({ "login": login })).collect::<Vec<_>>(),
+    }))
+}
+
+fn review(id: i64, reviewer: &str, state: &str, submitted: DateTime<Utc>) -> Review {
+    parse(json!({
+        "id": id,
+        "user": { "login": reviewer },
+        "state": state,
+        "submitted_at": timestamp(submitted),
+    }))
+}
+
+/// Xorshift, good enough for made up data and the same on every platform.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, n: u64) -> u64 {
+        self.next() % n
+    }
+
+    fn chance(&mut self, probability: f64) -> bool {
+        (self.below(10_000) as f64) < probability * 10_000.0
+    }
+
+    fn pick<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+        &items[self.below(items.len() as u64) as usize]
+    }
+
+    fn minutes(&mut self, min: i64, max: i64) -> Duration {
+        Duration::minutes(min + self.below((max - min) as u64 + 1) as i64)
+    }
+
+    fn sha(&mut self) -> String {
+        format!("{:040x}", self.next() as u128 * self.next() as u128)
+    }
+}
@@ -0,0 +1,161 @@
+//! Polls GitHub in the background and serves the status of the repositories as Prometheus metrics.
+
+use std::fmt::Write;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::analysis::status::latest_default_branch_runs;
+use crate::cli::Args;
+use crate::github::blocking::{self, Snapshot};
+
+/// How long to wait for GitHub to answer at all before giving up.
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Only reachable from this host unless `--listen` says otherwise, and clear of Prometheus' own 9090.
+const LISTEN: &str = "127.0.0.1:9919";
+
+const USAGE: &str = "\
+Usage: aap_status --metrics [--listen <address>] [--interval <seconds>] [--token <token>] [--repo <name>]...
+                            [--config <file>]
+                            [--app-id <id> --private-key <pem file> [--installation-id <id>]]
+                            [--record <file> | --replay <file>]
+
+Polls the repositories every interval (default 300 seconds) and serves Prometheus metrics
+on http://<address>/metrics (default 127.0.0.1:9919). Use e.g. --listen 0.0.0.0:9919 for
+Prometheus to scrape from other hosts.
+The token defaults to the GITHUB_TOKEN environment variable, or authenticate as a GitHub App.
+--record saves the responses from GitHub to a file after every poll, which --replay answers from
+without network.";
+
+/// Runs the exporter with the command line arguments following `--metrics`, returning the exit code.
+pub fn run(args: &[String]) -> i32 {
+    let args = match Args::parse(args) {
+        Ok(args) => args,
+        Err(e) => { eprintln!("{}\n\n{}", e, USAGE); return 2; }
+    };
+
+    if args.help {
+        println!("{}", USAGE);
+        return 0;
+    }
+
+    let listen = args.listen.clone().unwrap_or_else(|| String::from(LISTEN));
+    let server = match tiny_http::Server::http(&listen) {
+        Ok(server) => server,
+        Err(e) => { eprintln!("error: {:?} when listening on {}", e, listen); return 2; }
+    };
+
+    let metrics = Arc::new(Mutex::new(String::new()));
+
+    let _metrics = metrics.clone();
+    std::thread::spawn(move || poll(args, _metrics));
+
+    for request in server.incoming_requests() {
+        let response = match request.url() {
+            "/metrics" => tiny_http::Response::from_string(metrics.lock().unwrap().clone())
+                .with_header(header("Content-Type", "text/plain; version=0.0.4")),
+            _ => tiny_http::Response::from_string("Not found").with_status_code(404),
+        };
+
+        if let Err(e) = request.respond(response) {
+            eprintln!("error: {:?} when responding to metrics request", e);
+        }
+    }
+
+    0
+}
+
+fn poll(args: Args, metrics: Arc<Mutex<String>>) {
+    let github = args.github();
+
+    loop {
+        match args.current_token() {
+            Ok(token) => {
+                let snapshot = blocking::fetch(&github, &token, &args.repos, TIMEOUT);
+                snapshot.errors.iter().for_each(|e| eprintln!("{}", e));
+                *metrics.lock().unwrap() = render(&snapshot);
+                args.save_recording();
+            }
+            Err(e) => eprintln!("{}", e),
+        }
+        std::thread::sleep(args.interval);
+    }
+}
+
+pub(crate) fn header(name: &str, value: &str) -> tiny_http::Header {
+    tiny_http::Header::from_bytes(name.as_bytes(), value.as_bytes()).expect("valid header")
+}
+
+/// Formats the snapshot in the Prometheus text exposition format.
+fn render(snapshot: &Snapshot) -> String {
+    let mut out = String::new();
+
+    gauge(&mut out, "aap_status_open_pull_requests", "Open pull requests per repository.");
+    snapshot.pulls.iter().for_each(|(repo, prs)| {
+        let _ = writeln!(out, "aap_status_open_pull_requests{{repo=\"{}\"}} {}", escape(repo), prs.len());
+    });
+
+    let latest = snapshot.runs.iter()
+        .flat_map(|(repo, runs)| latest_default_branch_runs(runs, snapshot.default_branches.get(repo).map(String::as_str)).into_iter().map(move |(workflow, run)| (repo, workflow, run)))
+        .collect::<Vec<_>>();
+
+    gauge(&mut out, "aap_status_workflow_conclusion", "Conclusion of the latest run on the default branch, always 1.");
+    latest.iter().for_each(|(repo, workflow, run)| {
+        let _ = writeln!(
+            out, "aap_status_workflow_conclusion{{repo=\"{}\",workflow=\"{}\",conclusion=\"{}\"}} 1",
+            escape(repo), escape(workflow), escape(run.conclusion.as_deref().unwrap_or_default()),
+        );
+    });
+
+    gauge(&mut out, "aap_status_workflow_failing", "1 if the latest run on the default branch failed.");
+    latest.iter().for_each(|(repo, workflow, run)| {
+        let _ = writeln!(
+            out, "aap_status_workflow_failing{{repo=\"{}\",workflow=\"{}\"}} {}",
+            escape(repo), escape(workflow), i32::from(run.is_failure()),
+        );
+    });
+
+    gauge(&mut out, "aap_status_workflow_run_duration_seconds", "Duration of the latest run on the default branch.");
+    latest.iter().for_each(|(repo, workflow, run)| {
+        if let Some(duration) = run.duration_secs() {
+            let _ = writeln!(
+                out, "aap_status_workflow_run_duration_seconds{{repo=\"{}\",workflow=\"{}\"}} {}",
+                escape(repo), escape(workflow), duration,
+            );
+        }
+    });
+
+    gauge(&mut out, "aap_status_workflow_run_timestamp_seconds", "Creation time of the latest run on the default branch.");
+    latest.iter().for_each(|(repo, workflow, run)| {
+        if let Some(created) = run.created() {
+            let _ = writeln!(
+                out, "aap_status_workflow_run_timestamp_seconds{{repo=\"{}\",workflow=\"{}\"}} {}",
+                escape(repo), escape(workflow), created.timestamp(),
+            );
+        }
+    });
+
+    if let Some(rate_limit) = &snapshot.rate_limit {
+        gauge(&mut out, "aap_status_rate_limit_remaining", "Remaining GitHub API requests in the current window.");
+        let _ = writeln!(out, "aap_status_rate_limit_remaining {}", rate_limit.remaining);
+        gauge(&mut out, "aap_status_rate_limit_limit", "GitHub API requests allowed per window.");
+        let _ = writeln!(out, "aap_status_rate_limit_limit {}", rate_limit.limit);
+        gauge(&mut out, "aap_status_rate_limit_reset_timestamp_seconds", "When the GitHub API window resets.");
+        let _ = writeln!(out, "aap_status_rate_limit_reset_timestamp_seconds {}", rate_limit.reset);
+    }
+
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+    gauge(&mut out, "aap_status_last_poll_timestamp_seconds", "When GitHub was last polled.");
+    let _ = writeln!(out, "aap_status_last_poll_timestamp_seconds {}", now);
+
+    out
+}
+
+fn gauge(out: &mut String, name: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} gauge", name);
+}
+
+fn escape(label: &str) -> String {
+    label.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
@@ -0,0 +1,263 @@
+//! Pull requests, Actions runs and teams from the API of a Forgejo (or Gitea) instance.
+
+use std::collections::BTreeSet;
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::forge::{ChangeRequest, Group, PipelineDefinition, PipelineRun, Provider};
+use crate::github::cassette::Cassette;
+
+pub const CODEBERG_API: &str = "https://codeberg.org/api/v1";
+
+/// Client for the API of a Forgejo instance, usually self-hosted.
+#[derive(Deserialize, Serialize, Clone)]
+#[serde(default)]
+pub struct ForgejoApi {
+    pub base_url: String,
+    /// Organisation or user owning the repositories and teams.
+    pub owner: String,
+    #[serde(skip)]
+    pub cassette: Cassette,
+}
+
+impl Default for ForgejoApi {
+    fn default() -> Self {
+        Self {
+            base_url: CODEBERG_API.to_string(),
+            owner: String::new(),
+            cassette: Cassette::default(),
+        }
+    }
+}
+
+impl ForgejoApi {
+    /// The web interface, for links the API doesn't give.
+    fn web_url(&self) -> &str {
+        self.base_url.trim_end_matches('/').trim_end_matches("/api/v1")
+    }
+
+    fn get<T: DeserializeOwned>(
+        &self,
+        token: &str,
+        url: String,
+        callback: impl 'static + Send + FnOnce(T),
+    ) {
+        let request = ehttp::Request {
+            headers: ehttp::headers(&[
+                ("Accept", "application/json"),
+                ("User-Agent", "rust web-api-client demo"),
+                ("Authorization", format!("token {}", token.trim()).as_str()),
+            ]),
+            ..ehttp::Request::get(&url)
+        };
+
+        self.cassette.fetch(request, move |result: ehttp::Result<ehttp::Response>| {
+            match result {
+                // Error messages are objects, and would fail to parse as lists anyway.
+                Ok(res) if !res.ok => eprintln!("error: {} {} from {:?}", res.status, res.status_text, &url),
+                Ok(res) => {
+                    match serde_json::from_slice::<T>(&res.bytes) {
+                        Ok(value) => callback(value),
+                        Err(e) => eprintln!("error: {:?} when parsing {} with content {:?}", e, &url, res)
+                    }
+                }
+                Err(e) => eprintln!("Error {:?} from {:?}", e, &url)
+            }
+        });
+    }
+
+    /// The latest Actions tasks, which are jobs: a run with several jobs is listed once per job.
+    fn tasks(&self, token: &str, repo: &str, callback: impl 'static + Send + FnOnce(Vec<ActionTask>)) {
+        let url = format!("{}/repos/{}/{}/actions/tasks?limit=50", self.base_url, self.owner, repo);
+
+        self.get(token, url, move |response: ActionTasks| callback(response.workflow_runs));
+    }
+}
+
+impl Provider for ForgejoApi {
+    fn change_requests(
+        &self,
+        token: &mut String,
+        repo: &str,
+        callback: impl 'static + Send + FnOnce(Vec<ChangeRequest>),
+    ) {
+        let url = format!("{}/repos/{}/{}/pulls?state=open&limit=50", self.base_url, self.owner, repo);
+
+        self.get(token, url, move |pulls: Vec<ForgejoPullRequest>| {
+            callback(pulls.iter().map(ChangeRequest::from).collect())
+        });
+    }
+
+    fn pipeline_runs(
+        &self,
+        token: &mut String,
+        repo: &str,
+        callback: impl 'static + Send + FnOnce(Vec<PipelineRun>),
+    ) {
+        self.tasks(token, repo, move |tasks| callback(runs_of(&tasks)));
+    }
+
+    /// Forgejo has no list of workflows, so these are the workflows with recent runs.
+    fn pipeline_definitions(
+        &self,
+        token: &mut String,
+        repo: &str,
+        callback: impl 'static + Send + FnOnce(Vec<PipelineDefinition>),
+    ) {
+        self.tasks(token, repo, move |tasks| {
+            let workflows = tasks.into_iter().map(|task| task.workflow_id).collect::<BTreeSet<_>>();
+            callback(workflows.into_iter().map(|workflow| PipelineDefinition {
+                id: workflow.clone(),
+                name: workflow.clone(),
+                path: workflow,
+            }).collect())
+        });
+    }
+
+    fn groups(
+        &self,
+        token: &mut String,
+        callback: impl 'static + Send + FnOnce(Vec<Group>),
+    ) {
+        let url = format!("{}/orgs/{}/teams?limit=50", self.base_url, self.owner);
+        let teams_url = format!("{}/org/{}/teams", self.web_url(), self.owner);
+
+        self.get(token, url, move |teams: Vec<ForgejoTeam>| {
+            callback(teams.into_iter().map(|team| Group {
+                url: format!("{}/{}", teams_url, team.name),
+                slug: team.name.clone(),
+                name: team.name,
+            }).collect())
+        });
+    }
+}
+
+/// One run per workflow and run number, in the order of their newest job. The jobs aren't shown,
+/// so the run fails if any job failed, is pending while any job is, and is otherwise cancelled if a
+/// job was, or successful.
+fn runs_of(tasks: &[ActionTask]) -> Vec<PipelineRun> {
+    let mut runs: Vec<((String, i64), PipelineRun)> = vec![];
+    for task in tasks {
+        let key = (task.workflow_id.clone(), task.run_number);
+        match runs.iter_mut().find(|(run, _)| *run == key) {
+            Some((_, run)) => combine(run, PipelineRun::from(task)),
+            None => runs.push((key, PipelineRun::from(task))),
+        }
+    }
+    runs.into_iter().map(|(_, run)| run).collect()
+}
+
+fn combine(run: &mut PipelineRun, job: PipelineRun) {
+    let rank = |run: &PipelineRun| match (run.status.as_deref(), run.conclusion.as_deref()) {
+        (_, Some("failure")) => 6,
+        (Some("in_progress"), _) => 5,
+        (Some("waiting"), _) => 4,
+        (Some("queued"), _) => 3,
+        (_, Some("cancelled")) => 2,
+        (_, Some("success")) => 1,
+        _ => 0,
+    };
+
+    if rank(&job) > rank(run) {
+        run.status = job.status;
+        run.conclusion = job.conclusion;
+    }
+    if job.started_at.is_some() && (run.started_at.is_none() || job.started_at < run.started_at) {
+        run.started_at = job.started_at;
+    }
+    if job.updated_at > run.updated_at {
+        run.updated_at = job.updated_at;
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ForgejoPullRequest {
+    pub number: i64,
+    pub title: String,
+    pub html_url: String,
+    pub user: Option<ForgejoUser>,
+    pub created_at: Option<String>,
+    pub updated_at: Option<String>,
+    /// `null` rather than empty when nobody is asked.
+    pub requested_reviewers: Option<Vec<ForgejoUser>>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ForgejoUser {
+    pub login: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct ActionTasks {
+    workflow_runs: Vec<ActionTask>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ActionTask {
+    pub id: i64,
+    /// Name of the job.
+    pub name: String,
+    pub display_title: Option<String>,
+    pub head_branch: Option<String>,
+    pub head_sha: String,
+    pub run_number: i64,
+    pub event: String,
+    pub status: String,
+    /// File name of the workflow, e.g. `build.yml`.
+    pub workflow_id: String,
+    pub url: String,
+    pub run_started_at: Option<String>,
+    pub updated_at: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ForgejoTeam {
+    pub name: String,
+}
+
+impl From<&ForgejoPullRequest> for ChangeRequest {
+    fn from(pr: &ForgejoPullRequest) -> Self {
+        Self {
+            number: pr.number,
+            title: pr.title.clone(),
+            url: pr.html_url.clone(),
+            author: pr.user.as_ref().map(|user| user.login.clone()).unwrap_or_default(),
+            created_at: pr.created_at.clone(),
+            updated_at: pr.updated_at.clone(),
+            reviewers: pr.requested_reviewers.iter().flatten().map(|user| user.login.clone()).collect(),
+            labels: vec![],
+            review_decision: None,
+            checks: None,
+        }
+    }
+}
+
+impl From<&ActionTask> for PipelineRun {
+    fn from(task: &ActionTask) -> Self {
+        let (status, conclusion) = match task.status.as_str() {
+            "success" => ("completed", Some("success")),
+            "failure" => ("completed", Some("failure")),
+            "cancelled" => ("completed", Some("cancelled")),
+            "skipped" => ("completed", Some("skipped")),
+            "running" => ("in_progress", None),
+            "blocked" => ("waiting", None),
+            _ => ("queued", None),
+        };
+
+        Self {
+            id: task.id,
+            definition_id: task.workflow_id.clone(),
+            name: task.workflow_id.trim_end_matches(".yaml").trim_end_matches(".yml").to_string(),
+            event: task.event.clone(),
+            status: Some(status.to_string()),
+            conclusion: conclusion.map(String::from),
+            attempt: 1,
+            branch: task.head_branch.clone(),
+            sha: task.head_sha.clone(),
+            started_at: task.run_started_at.clone(),
+            updated_at: task.updated_at.clone(),
+            url: task.url.clone(),
+        }
+    }
+}
@@ -0,0 +1,112 @@
+use crate::forge::{ChangeRequest, Group, PipelineDefinition, PipelineRun, Provider};
+use crate::github::github_client::{GitHubApi, Pulls, Runs, Teams, Workflows};
+use crate::github::pulls::PullRequest;
+use crate::github::runs::WorkflowRun;
+use crate::github::teams::Team;
+use crate::github::workflows::Workflow;
+
+impl Provider for GitHubApi {
+    fn change_requests(
+        &self,
+        token: &mut String,
+        repo: &str,
+        callback: impl 'static + Send + FnOnce(Vec<ChangeRequest>),
+    ) {
+        self.pull_requests(token, repo, move |pulls: Vec<PullRequest>| {
+            callback(pulls.iter().map(ChangeRequest::from).collect())
+        });
+    }
+
+    fn pipeline_runs(
+        &self,
+        token: &mut String,
+        repo: &str,
+        callback: impl 'static + Send + FnOnce(Vec<PipelineRun>),
+    ) {
+        self.runs(token, repo, move |runs| {
+            callback(runs.workflow_runs.iter().map(PipelineRun::from).collect())
+        });
+    }
+
+    fn pipeline_definitions(
+        &self,
+        token: &mut String,
+        repo: &str,
+        callback: impl 'static + Send + FnOnce(Vec<PipelineDefinition>),
+    ) {
+        self.workflows(token, repo, move |workflows: Vec<Workflow>| {
+            callback(workflows.iter().map(PipelineDefinition::from).collect())
+        });
+    }
+
+    fn groups(
+        &self,
+        token: &mut String,
+        callback: impl 'static + Send + FnOnce(Vec<Group>),
+    ) {
+        let url = format!("{}/orgs/{}/teams?per_page=100", self.base_url, self.org);
+
+        self.teams(&url, token, move |res| {
+            match serde_json::from_slice::<Vec<Team>>(&res.bytes) {
+                Ok(teams) => callback(teams.iter().map(Group::from).collect()),
+                Err(e) => eprintln!("error: {:?} when parsing teams with content {:?}", e, res)
+            }
+        });
+    }
+}
+
+impl From<&PullRequest> for ChangeRequest {
+    fn from(pr: &PullRequest) -> Self {
+        Self {
+            number: pr.number.into(),
+            title: pr.title.clone().unwrap_or_default(),
+            url: pr.html_url.clone().unwrap_or_default(),
+            author: pr.user.as_ref().map(|user| user.login.clone()).unwrap_or_default(),
+            created_at: pr.created_at.clone(),
+            updated_at: pr.updated_at.clone(),
+            reviewers: pr.requested_reviewers.iter().map(|user| user.login.clone()).collect(),
+            labels: vec![],
+            review_decision: None,
+            checks: None,
+        }
+    }
+}
+
+impl From<&WorkflowRun> for PipelineRun {
+    fn from(run: &WorkflowRun) -> Self {
+        Self {
+            id: run.id,
+            definition_id: run.workflow_id.to_string(),
+            name: run.name.clone().unwrap_or_default(),
+            event: run.event.clone(),
+            status: run.status.clone(),
+            conclusion: run.conclusion.clone(),
+            attempt: run.run_attempt,
+            branch: run.head_branch.clone(),
+            sha: run.head_sha.clone(),
+            started_at: run.run_started_at.clone(),
+            updated_at: Some(run.updated_at.clone()),
+            url: run.html_url.clone(),
+        }
+    }
+}
+
+impl From<&Workflow> for PipelineDefinition {
+    fn from(workflow: &Workflow) -> Self {
+        Self {
+            id: workflow.id.to_string(),
+            name: workflow.name.clone(),
+            path: workflow.path.clone(),
+        }
+    }
+}
+
+impl From<&Team> for Group {
+    fn from(team: &Team) -> Self {
+        Self {
+            name: team.name.clone(),
+            slug: team.slug.clone(),
+            url: team.html_url.clone(),
+        }
+    }
+}
@@ -0,0 +1,221 @@
+//! Merge requests, pipelines and subgroups from the GitLab REST API, for projects mirrored there.
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::forge::{ChangeRequest, Group, PipelineDefinition, PipelineRun, Provider};
+use crate::github::cassette::Cassette;
+
+pub const GITLAB_API: &str = "https://gitlab.com/api/v4";
+
+/// GitLab has one pipeline per project, defined in this file.
+const PIPELINE_DEFINITION: &str = ".gitlab-ci.yml";
+
+/// Client for the GitLab REST API, of gitlab.com or a self-managed instance.
+#[derive(Deserialize, Serialize, Clone)]
+#[serde(default)]
+pub struct GitLabApi {
+    pub base_url: String,
+    /// Full path of the group holding the projects, e.g. `navikt/aap`.
+    pub group: String,
+    #[serde(skip)]
+    pub cassette: Cassette,
+}
+
+impl Default for GitLabApi {
+    fn default() -> Self {
+        Self {
+            base_url: GITLAB_API.to_string(),
+            group: String::new(),
+            cassette: Cassette::default(),
+        }
+    }
+}
+
+impl GitLabApi {
+    /// Projects are addressed by their URL-encoded full path.
+    fn project(&self, repo: &str) -> String {
+        format!("{}/{}", self.group.trim_matches('/'), repo).replace('/', "%2F")
+    }
+
+    fn get<T: DeserializeOwned>(
+        &self,
+        token: &str,
+        url: String,
+        callback: impl 'static + Send + FnOnce(T),
+    ) {
+        let request = ehttp::Request {
+            headers: ehttp::headers(&[
+                ("Accept", "application/json"),
+                ("User-Agent", "rust web-api-client demo"),
+                ("Authorization", format!("Bearer {}", token.trim()).as_str()),
+            ]),
+            ..ehttp::Request::get(&url)
+        };
+
+        self.cassette.fetch(request, move |result: ehttp::Result<ehttp::Response>| {
+            match result {
+                // Error messages are objects, and would fail to parse as lists anyway.
+                Ok(res) if !res.ok => eprintln!("error: {} {} from {:?}", res.status, res.status_text, &url),
+                Ok(res) => {
+                    match serde_json::from_slice::<T>(&res.bytes) {
+                        Ok(value) => callback(value),
+                        Err(e) => eprintln!("error: {:?} when parsing {} with content {:?}", e, &url, res)
+                    }
+                }
+                Err(e) => eprintln!("Error {:?} from {:?}", e, &url)
+            }
+        });
+    }
+}
+
+impl Provider for GitLabApi {
+    fn change_requests(
+        &self,
+        token: &mut String,
+        repo: &str,
+        callback: impl 'static + Send + FnOnce(Vec<ChangeRequest>),
+    ) {
+        let url = format!("{}/projects/{}/merge_requests?state=opened&per_page=100", self.base_url, self.project(repo));
+
+        self.get(token, url, move |merge_requests: Vec<MergeRequest>| {
+            callback(merge_requests.iter().map(ChangeRequest::from).collect())
+        });
+    }
+
+    fn pipeline_runs(
+        &self,
+        token: &mut String,
+        repo: &str,
+        callback: impl 'static + Send + FnOnce(Vec<PipelineRun>),
+    ) {
+        let url = format!("{}/projects/{}/pipelines?per_page=100", self.base_url, self.project(repo));
+
+        self.get(token, url, move |pipelines: Vec<Pipeline>| {
+            callback(pipelines.iter().map(PipelineRun::from).collect())
+        });
+    }
+
+    fn pipeline_definitions(
+        &self,
+        _token: &mut String,
+        _repo: &str,
+        callback: impl 'static + Send + FnOnce(Vec<PipelineDefinition>),
+    ) {
+        callback(vec![PipelineDefinition {
+            id: PIPELINE_DEFINITION.to_string(),
+            name: String::from("Pipeline"),
+            path: PIPELINE_DEFINITION.to_string(),
+        }]);
+    }
+
+    fn groups(
+        &self,
+        token: &mut String,
+        callback: impl 'static + Send + FnOnce(Vec<Group>),
+    ) {
+        let group = self.group.trim_matches('/').replace('/', "%2F");
+        let url = format!("{}/groups/{}/subgroups?per_page=100", self.base_url, group);
+
+        self.get(token, url, move |groups: Vec<GitLabGroup>| {
+            callback(groups.iter().map(Group::from).collect())
+        });
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MergeRequest {
+    /// The number shown in the project, unlike `id` which is unique across GitLab.
+    pub iid: i64,
+    pub title: String,
+    pub web_url: String,
+    pub author: Option<Author>,
+    pub created_at: Option<String>,
+    pub updated_at: Option<String>,
+    #[serde(default)]
+    pub reviewers: Vec<Author>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Author {
+    pub username: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Pipeline {
+    pub id: i64,
+    pub status: String,
+    /// What triggered it, e.g. `push`, `merge_request_event` or `schedule`.
+    #[serde(default)]
+    pub source: String,
+    #[serde(rename = "ref")]
+    pub _ref: Option<String>,
+    pub sha: String,
+    pub web_url: String,
+    pub created_at: Option<String>,
+    #[serde(default)]
+    pub updated_at: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct GitLabGroup {
+    pub name: String,
+    pub full_path: String,
+    pub web_url: String,
+}
+
+impl From<&MergeRequest> for ChangeRequest {
+    fn from(mr: &MergeRequest) -> Self {
+        Self {
+            number: mr.iid,
+            title: mr.title.clone(),
+            url: mr.web_url.clone(),
+            author: mr.author.as_ref().map(|author| author.username.clone()).unwrap_or_default(),
+            created_at: mr.created_at.clone(),
+            updated_at: mr.updated_at.clone(),
+            reviewers: mr.reviewers.iter().map(|author| author.username.clone()).collect(),
+            labels: vec![],
+            review_decision: None,
+            checks: None,
+        }
+    }
+}
+
+impl From<&Pipeline> for PipelineRun {
+    fn from(pipeline: &Pipeline) -> Self {
+        let (status, conclusion) = match pipeline.status.as_str() {
+            "success" => ("completed", Some("success")),
+            "failed" => ("completed", Some("failure")),
+            "canceled" => ("completed", Some("cancelled")),
+            "skipped" => ("completed", Some("skipped")),
+            "running" => ("in_progress", None),
+            "manual" => ("waiting", None),
+            _ => ("queued", None),
+        };
+
+        Self {
+            id: pipeline.id,
+            definition_id: PIPELINE_DEFINITION.to_string(),
+            name: String::from("Pipeline"),
+            event: pipeline.source.clone(),
+            status: Some(status.to_string()),
+            conclusion: conclusion.map(String::from),
+            attempt: 1,
+            branch: pipeline._ref.clone(),
+            sha: pipeline.sha.clone(),
+            started_at: pipeline.created_at.clone(),
+            updated_at: pipeline.updated_at.clone(),
+            url: pipeline.web_url.clone(),
+        }
+    }
+}
+
+impl From<&GitLabGroup> for Group {
+    fn from(group: &GitLabGroup) -> Self {
+        Self {
+            name: group.name.clone(),
+            slug: group.full_path.clone(),
+            url: group.web_url.clone(),
+        }
+    }
+}
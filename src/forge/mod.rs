@@ -0,0 +1,371 @@
+//! Forge-neutral view of pull requests, pipelines and teams, so repositories hosted somewhere
+//! other than GitHub, on GitLab or a Forgejo instance, can be shown in the same tables. Statuses
+//! and conclusions of pipeline runs use the words of GitHub Actions, which the other forges are
+//! mapped to.
+
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Utc};
+use egui::Ui;
+use serde::{Deserialize, Serialize};
+
+use crate::forge::forgejo::ForgejoApi;
+use crate::forge::gitlab::GitLabApi;
+use crate::github::cassette::Cassette;
+use crate::github::pulls::PullRequest;
+use crate::github::runs::{parse_timestamp, WorkflowRuns};
+use crate::token_store::TokenStore;
+
+pub mod forgejo;
+pub mod github;
+pub mod gitlab;
+
+/// A pull request on GitHub and Forgejo, a merge request on GitLab.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ChangeRequest {
+    pub number: i64,
+    pub title: String,
+    pub url: String,
+    pub author: String,
+    pub created_at: Option<String>,
+    pub updated_at: Option<String>,
+    /// Logins of the people asked to review it.
+    #[serde(default)]
+    pub reviewers: Vec<String>,
+    #[serde(default)]
+    pub labels: Vec<String>,
+    /// `APPROVED`, `CHANGES_REQUESTED` or `REVIEW_REQUIRED`. Only known from GitHub's GraphQL API.
+    #[serde(default)]
+    pub review_decision: Option<String>,
+    /// Combined state of the checks on the head commit, `SUCCESS`, `FAILURE` or `PENDING`.
+    /// Only known from GitHub's GraphQL API.
+    #[serde(default)]
+    pub checks: Option<String>,
+}
+
+/// A run of a workflow in GitHub or Forgejo Actions, a pipeline on GitLab.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct PipelineRun {
+    pub id: i64,
+    /// The [`PipelineDefinition`] this is a run of.
+    pub definition_id: String,
+    pub name: String,
+    pub event: String,
+    /// `queued`, `in_progress`, `waiting` or `completed`.
+    pub status: Option<String>,
+    /// `success`, `failure`, `cancelled` or `skipped` once completed.
+    pub conclusion: Option<String>,
+    pub attempt: i32,
+    pub branch: Option<String>,
+    pub sha: String,
+    pub started_at: Option<String>,
+    /// When it was last updated, which for completed runs is when it finished.
+    #[serde(default)]
+    pub updated_at: Option<String>,
+    pub url: String,
+}
+
+impl PipelineRun {
+    pub fn started(&self) -> Option<DateTime<Utc>> {
+        parse_timestamp(self.started_at.as_ref()?)
+    }
+
+    /// Wall clock time from the start until the last update, only known for completed runs.
+    pub fn duration_secs(&self) -> Option<i64> {
+        if self.status.as_deref() != Some("completed") {
+            return None;
+        }
+
+        let updated = parse_timestamp(self.updated_at.as_ref()?)?;
+        Some((updated - self.started()?).num_seconds())
+    }
+
+    pub fn is_success(&self) -> bool {
+        self.conclusion.as_deref() == Some("success")
+    }
+
+    pub fn is_failure(&self) -> bool {
+        self.conclusion.as_deref() == Some("failure")
+    }
+}
+
+/// A workflow file in GitHub or Forgejo Actions, the `.gitlab-ci.yml` on GitLab.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct PipelineDefinition {
+    pub id: String,
+    pub name: String,
+    pub path: String,
+}
+
+/// A team on GitHub and Forgejo, a subgroup on GitLab.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Group {
+    pub name: String,
+    pub slug: String,
+    pub url: String,
+}
+
+/// What the tables need from a forge. `repo` is the name of a repository in the organisation or
+/// group the provider is configured with.
+pub trait Provider {
+    /// Open change requests.
+    fn change_requests(
+        &self,
+        token: &mut String,
+        repo: &str,
+        callback: impl 'static + Send + FnOnce(Vec<ChangeRequest>),
+    );
+
+    /// The latest runs (up to 100), newest first.
+    fn pipeline_runs(
+        &self,
+        token: &mut String,
+        repo: &str,
+        callback: impl 'static + Send + FnOnce(Vec<PipelineRun>),
+    );
+
+    fn pipeline_definitions(
+        &self,
+        token: &mut String,
+        repo: &str,
+        callback: impl 'static + Send + FnOnce(Vec<PipelineDefinition>),
+    );
+
+    /// The first page (up to 100) of groups in the organisation.
+    fn groups(
+        &self,
+        token: &mut String,
+        callback: impl 'static + Send + FnOnce(Vec<Group>),
+    );
+}
+
+/// Where a repository is hosted.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum Forge {
+    #[default]
+    GitHub,
+    GitLab,
+    Forgejo,
+}
+
+impl Forge {
+    pub const ALL: [Forge; 3] = [Forge::GitHub, Forge::GitLab, Forge::Forgejo];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Forge::GitHub => "GitHub",
+            Forge::GitLab => "GitLab",
+            Forge::Forgejo => "Forgejo",
+        }
+    }
+}
+
+/// The repositories of a profile hosted elsewhere than GitHub, and how to reach those forges.
+/// GitHub itself is configured by [`crate::github::github_client::GitHubApi`] as before.
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct Forges {
+    /// Only repositories not on GitHub are listed.
+    pub repositories: BTreeMap<String, Forge>,
+    pub gitlab: GitLabApi,
+    /// Never persisted with the rest of the state, see [`TokenStore`].
+    #[serde(skip)]
+    pub gitlab_token: String,
+    pub gitlab_token_store: TokenStore,
+    pub forgejo: ForgejoApi,
+    #[serde(skip)]
+    pub forgejo_token: String,
+    pub forgejo_token_store: TokenStore,
+}
+
+impl Default for Forges {
+    fn default() -> Self {
+        Self {
+            repositories: BTreeMap::new(),
+            gitlab: GitLabApi::default(),
+            gitlab_token: String::new(),
+            gitlab_token_store: TokenStore::new("gitlab-token"),
+            forgejo: ForgejoApi::default(),
+            forgejo_token: String::new(),
+            forgejo_token_store: TokenStore::new("forgejo-token"),
+        }
+    }
+}
+
+impl Forges {
+    /// With the tokens stored in entries suffixed with `-{n}`, apart from those of other profiles.
+    pub fn numbered(n: usize) -> Self {
+        let mut forges = Self::default();
+        forges.gitlab_token_store = TokenStore::new(&format!("{}-{}", forges.gitlab_token_store.entry(), n));
+        forges.forgejo_token_store = TokenStore::new(&format!("{}-{}", forges.forgejo_token_store.entry(), n));
+        forges
+    }
+
+    pub fn load_tokens(&mut self) {
+        if let Some(token) = self.gitlab_token_store.load() {
+            self.gitlab_token = token;
+        }
+        if let Some(token) = self.forgejo_token_store.load() {
+            self.forgejo_token = token;
+        }
+    }
+
+    /// Stores the tokens if the profile's token is to be remembered, see [`TokenStore::save`].
+    pub fn save_tokens(&mut self, remember: bool) {
+        self.gitlab_token_store.remember = remember;
+        self.forgejo_token_store.remember = remember;
+        self.gitlab_token_store.save(&self.gitlab_token);
+        self.forgejo_token_store.save(&self.forgejo_token);
+    }
+
+    /// Why a token could not be read or stored, if one couldn't.
+    pub fn token_store_error(&self) -> Option<&str> {
+        self.gitlab_token_store.error().or(self.forgejo_token_store.error())
+    }
+
+    /// Removes the stored tokens, when the profile is deleted.
+    pub fn forget_tokens(&mut self) {
+        self.gitlab_token_store.save("");
+        self.forgejo_token_store.save("");
+    }
+
+    /// Records or replays the traffic to the other forges along with that to GitHub.
+    pub fn use_cassette(&mut self, cassette: &Cassette) {
+        self.gitlab.cassette = cassette.clone();
+        self.forgejo.cassette = cassette.clone();
+    }
+
+    pub fn of(&self, repo: &str) -> Forge {
+        self.repositories.get(repo).copied().unwrap_or_default()
+    }
+
+    pub fn set(&mut self, repo: &str, forge: Forge) {
+        match forge {
+            Forge::GitHub => self.repositories.remove(repo),
+            _ => self.repositories.insert(repo.to_string(), forge),
+        };
+    }
+
+    /// Change requests of a repository not on GitHub. Those on GitHub are fetched from GitHub
+    /// directly, and never asked for here.
+    pub fn change_requests(&mut self, repo: &str, callback: impl 'static + Send + FnOnce(Vec<ChangeRequest>)) {
+        debug_assert_ne!(self.of(repo), Forge::GitHub, "{} is fetched from GitHub directly", repo);
+        match self.of(repo) {
+            Forge::GitLab => self.gitlab.change_requests(&mut self.gitlab_token, repo, callback),
+            Forge::Forgejo => self.forgejo.change_requests(&mut self.forgejo_token, repo, callback),
+            Forge::GitHub => {}
+        }
+    }
+
+    /// Pipeline runs of a repository not on GitHub, see [`Forges::change_requests`].
+    pub fn pipeline_runs(&mut self, repo: &str, callback: impl 'static + Send + FnOnce(Vec<PipelineRun>)) {
+        debug_assert_ne!(self.of(repo), Forge::GitHub, "{} is fetched from GitHub directly", repo);
+        match self.of(repo) {
+            Forge::GitLab => self.gitlab.pipeline_runs(&mut self.gitlab_token, repo, callback),
+            Forge::Forgejo => self.forgejo.pipeline_runs(&mut self.forgejo_token, repo, callback),
+            Forge::GitHub => {}
+        }
+    }
+
+    /// Pipeline definitions of a repository not on GitHub, see [`Forges::change_requests`].
+    pub fn pipeline_definitions(&mut self, repo: &str, callback: impl 'static + Send + FnOnce(Vec<PipelineDefinition>)) {
+        debug_assert_ne!(self.of(repo), Forge::GitHub, "{} is fetched from GitHub directly", repo);
+        match self.of(repo) {
+            Forge::GitLab => self.gitlab.pipeline_definitions(&mut self.gitlab_token, repo, callback),
+            Forge::Forgejo => self.forgejo.pipeline_definitions(&mut self.forgejo_token, repo, callback),
+            Forge::GitHub => {}
+        }
+    }
+
+    /// Groups on a forge other than GitHub, whose teams are fetched from GitHub directly.
+    pub fn groups(&mut self, forge: Forge, callback: impl 'static + Send + FnOnce(Vec<Group>)) {
+        debug_assert_ne!(forge, Forge::GitHub, "teams are fetched from GitHub directly");
+        match forge {
+            Forge::GitLab => self.gitlab.groups(&mut self.gitlab_token, callback),
+            Forge::Forgejo => self.forgejo.groups(&mut self.forgejo_token, callback),
+            Forge::GitHub => {}
+        }
+    }
+
+    /// The forges other than GitHub with a repository on them.
+    pub fn in_use(&self) -> Vec<Forge> {
+        Forge::ALL.into_iter().filter(|forge| self.repositories.values().any(|f| f == forge)).collect()
+    }
+
+    /// Change requests of every repository. Those on GitHub come from `graphql` when fetched that
+    /// way and from the REST `github` otherwise, the rest from `others`.
+    pub fn all_change_requests(
+        &self,
+        github: &BTreeMap<String, Vec<PullRequest>>,
+        graphql: &BTreeMap<String, Vec<ChangeRequest>>,
+        others: &BTreeMap<String, Vec<ChangeRequest>>,
+    ) -> BTreeMap<String, Vec<ChangeRequest>> {
+        let from_rest = github.iter()
+            .filter(|(repo, _)| self.of(repo) == Forge::GitHub && !graphql.contains_key(*repo))
+            .map(|(repo, pulls)| (repo.clone(), pulls.iter().map(ChangeRequest::from).collect()));
+        let from_github = graphql.iter()
+            .filter(|(repo, _)| self.of(repo) == Forge::GitHub)
+            .map(|(repo, change_requests)| (repo.clone(), change_requests.clone()))
+            .chain(from_rest);
+        let from_others = others.iter()
+            .filter(|(repo, _)| self.of(repo) != Forge::GitHub)
+            .map(|(repo, change_requests)| (repo.clone(), change_requests.clone()));
+        from_github.chain(from_others).collect()
+    }
+
+    /// Pipeline runs of every repository, like [`Forges::all_change_requests`].
+    pub fn all_pipeline_runs(
+        &self,
+        github: &BTreeMap<String, WorkflowRuns>,
+        others: &BTreeMap<String, Vec<PipelineRun>>,
+    ) -> BTreeMap<String, Vec<PipelineRun>> {
+        let from_github = github.iter()
+            .filter(|(repo, _)| self.of(repo) == Forge::GitHub)
+            .map(|(repo, runs)| (repo.clone(), runs.workflow_runs.iter().map(PipelineRun::from).collect()));
+        let from_others = others.iter()
+            .filter(|(repo, _)| self.of(repo) != Forge::GitHub)
+            .map(|(repo, runs)| (repo.clone(), runs.clone()));
+        from_github.chain(from_others).collect()
+    }
+
+    /// Picks where a repository is hosted.
+    pub fn repository_ui(&mut self, ui: &mut Ui, repo: &str) {
+        let mut forge = self.of(repo);
+        egui::ComboBox::from_id_source(("forge", repo))
+            .selected_text(forge.name())
+            .width(70.0)
+            .show_ui(ui, |ui| {
+                for option in Forge::ALL {
+                    ui.selectable_value(&mut forge, option, option.name());
+                }
+            });
+        if forge != self.of(repo) {
+            self.set(repo, forge);
+        }
+    }
+
+    /// Where the forges other than GitHub are, and the tokens for them.
+    pub fn settings_ui(&mut self, ui: &mut Ui) {
+        ui.horizontal_wrapped(|ui| {
+            ui.label("GitLab API:");
+            ui.add(egui::TextEdit::singleline(&mut self.gitlab.base_url).desired_width(200.0));
+            ui.label("Group:");
+            ui.add(egui::TextEdit::singleline(&mut self.gitlab.group).desired_width(80.0))
+                .on_hover_text("Group holding the projects, e.g. navikt/aap");
+            ui.label("Token:");
+            ui.add(egui::TextEdit::singleline(&mut self.gitlab_token).password(true).desired_width(120.0))
+                .on_hover_text("GitLab personal access token with the read_api scope");
+        });
+        ui.horizontal_wrapped(|ui| {
+            ui.label("Forgejo API:");
+            ui.add(egui::TextEdit::singleline(&mut self.forgejo.base_url).desired_width(200.0))
+                .on_hover_text("e.g. https://forgejo.example.com/api/v1");
+            ui.label("Owner:");
+            ui.add(egui::TextEdit::singleline(&mut self.forgejo.owner).desired_width(80.0))
+                .on_hover_text("Organisation or user owning the repositories");
+            ui.label("Token:");
+            ui.add(egui::TextEdit::singleline(&mut self.forgejo_token).password(true).desired_width(120.0))
+                .on_hover_text("Forgejo access token with read access to repositories");
+        });
+    }
+}
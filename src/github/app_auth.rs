@@ -0,0 +1,194 @@
+//! Authentication as a GitHub App instead of with a personal access token. A short lived JWT signed
+//! with the app's private key is exchanged for an installation access token, which is renewed
+//! shortly before it expires. Always against github.com, whatever API the profiles point at, as a
+//! backend in between would get hold of the key's tokens.
+
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+use chrono::{DateTime, Duration, Utc};
+use jsonwebtoken::{Algorithm, EncodingKey, Header};
+use serde::{Deserialize, Serialize};
+
+use crate::github::github_client::{spawn, GitHubApi};
+use crate::github::runs::parse_timestamp;
+
+/// Installation tokens are valid for an hour, renew them this long before they expire.
+const RENEW_BEFORE_EXPIRY: Duration = Duration::minutes(5);
+
+pub struct AppAuth {
+    /// Of its own, so installation tokens never end up in a recorded cassette.
+    github: GitHubApi,
+    app_id: String,
+    installation_id: Option<String>,
+    key: EncodingKey,
+    /// By organisation, as the installation is looked up in the one asked for when no id is given.
+    token: Mutex<BTreeMap<String, InstallationToken>>,
+    renewing: AtomicBool,
+    /// Why the last renewal failed, until the next one is started.
+    error: Mutex<Option<String>>,
+}
+
+#[derive(Clone)]
+struct InstallationToken {
+    token: String,
+    expires_at: DateTime<Utc>,
+}
+
+#[derive(Serialize)]
+struct Claims {
+    iat: i64,
+    exp: i64,
+    iss: String,
+}
+
+#[derive(Deserialize)]
+struct AccessTokenResponse {
+    token: String,
+    expires_at: String,
+}
+
+#[derive(Deserialize)]
+struct Installation {
+    id: i64,
+    account: Account,
+}
+
+#[derive(Deserialize)]
+struct Account {
+    login: String,
+}
+
+impl AppAuth {
+    /// `private_key` is the PEM encoded key downloaded from the app settings.
+    pub fn new(app_id: &str, installation_id: Option<String>, private_key: &[u8]) -> Result<Self, String> {
+        let key = EncodingKey::from_rsa_pem(private_key).map_err(|e| format!("Invalid GitHub App private key: {}", e))?;
+
+        Ok(Self {
+            github: GitHubApi::default(),
+            app_id: app_id.to_string(),
+            installation_id,
+            key,
+            token: Mutex::new(BTreeMap::new()),
+            renewing: AtomicBool::new(false),
+            error: Mutex::new(None),
+        })
+    }
+
+    /// Reads GITHUB_APP_ID, GITHUB_APP_INSTALLATION_ID (optional, else the installation is looked up
+    /// in the organisation of the token) and either GITHUB_APP_PRIVATE_KEY or
+    /// GITHUB_APP_PRIVATE_KEY_FILE. Returns `None` when no app id is set.
+    pub fn from_env() -> Option<Result<Self, String>> {
+        let app_id = std::env::var("GITHUB_APP_ID").ok()?;
+        let installation_id = std::env::var("GITHUB_APP_INSTALLATION_ID").ok();
+
+        let private_key = match (std::env::var("GITHUB_APP_PRIVATE_KEY"), std::env::var("GITHUB_APP_PRIVATE_KEY_FILE")) {
+            (Ok(key), _) => Ok(key.into_bytes()),
+            (_, Ok(path)) => std::fs::read(&path).map_err(|e| format!("Could not read {}: {}", path, e)),
+            _ => Err(String::from("Set GITHUB_APP_PRIVATE_KEY or GITHUB_APP_PRIVATE_KEY_FILE for the GitHub App")),
+        };
+
+        Some(private_key.and_then(|key| Self::new(&app_id, installation_id, &key)))
+    }
+
+    pub fn app_id(&self) -> &str {
+        &self.app_id
+    }
+
+    /// A valid installation access token for `org`, renewed (blocking) when it is about to expire.
+    /// For the modes without an UI, which [`AppAuth::renew`] is for.
+    pub fn token(&self, org: &str) -> Result<String, String> {
+        futures::executor::block_on(self.fetch_token(org))
+    }
+
+    /// A valid installation access token for `org`, renewed when it is about to expire.
+    pub async fn fetch_token(&self, org: &str) -> Result<String, String> {
+        if let Some(current) = self.current(org) {
+            return Ok(current);
+        }
+
+        let renewed = self.installation_token(org).await?;
+        self.token.lock().unwrap().insert(org.to_string(), renewed.clone());
+        Ok(renewed.token)
+    }
+
+    /// The installation token unless it is about to expire, without waiting for a new one.
+    pub fn current(&self, org: &str) -> Option<String> {
+        self.token.lock().unwrap().get(org)
+            .filter(|t| t.expires_at - RENEW_BEFORE_EXPIRY > Utc::now())
+            .map(|t| t.token.clone())
+    }
+
+    pub fn error(&self) -> Option<String> {
+        self.error.lock().unwrap().clone()
+    }
+
+    /// Gets a new installation token in the background unless one is valid or on its way, then
+    /// calls `on_done` either way. Failures are kept for [`AppAuth::error`].
+    pub fn renew(self: &Arc<Self>, org: &str, on_done: impl 'static + Send + FnOnce()) {
+        if self.current(org).is_some() || self.renewing.swap(true, Ordering::AcqRel) {
+            return;
+        }
+        *self.error.lock().unwrap() = None;
+
+        let (app, org) = (self.clone(), org.to_string());
+        spawn(async move {
+            let result = app.fetch_token(&org).await;
+            *app.error.lock().unwrap() = result.err();
+            app.renewing.store(false, Ordering::Release);
+            on_done();
+        });
+    }
+
+    fn jwt(&self) -> Result<String, String> {
+        let now = Utc::now();
+        let claims = Claims {
+            // Allow for clock drift between us and GitHub.
+            iat: (now - Duration::seconds(60)).timestamp(),
+            exp: (now + Duration::minutes(9)).timestamp(),
+            iss: self.app_id.clone(),
+        };
+
+        jsonwebtoken::encode(&Header::new(Algorithm::RS256), &claims, &self.key)
+            .map_err(|e| format!("Could not sign GitHub App JWT: {}", e))
+    }
+
+    async fn installation_token(&self, org: &str) -> Result<InstallationToken, String> {
+        let jwt = self.jwt()?;
+
+        let installation_id = match &self.installation_id {
+            Some(id) => id.clone(),
+            None => self.find_installation(&jwt, org).await?,
+        };
+
+        let url = format!("{}/app/installations/{}/access_tokens", self.github.base_url, installation_id);
+        let request = ehttp::Request { method: String::from("POST"), ..self.github.get(&jwt, &url) };
+        let response: AccessTokenResponse = self.send(request).await?;
+
+        Ok(InstallationToken {
+            expires_at: parse_timestamp(&response.expires_at).ok_or_else(|| format!("Invalid expiry {:?}", response.expires_at))?,
+            token: response.token,
+        })
+    }
+
+    async fn find_installation(&self, jwt: &str, org: &str) -> Result<String, String> {
+        let url = format!("{}/app/installations", self.github.base_url);
+        let installations: Vec<Installation> = self.send(self.github.get(jwt, &url)).await?;
+
+        installations.into_iter()
+            .find(|installation| installation.account.login.eq_ignore_ascii_case(org))
+            .map(|installation| installation.id.to_string())
+            .ok_or_else(|| format!("GitHub App {} is not installed in {}", self.app_id, org))
+    }
+
+    async fn send<T: for<'de> Deserialize<'de>>(&self, request: ehttp::Request) -> Result<T, String> {
+        let url = request.url.clone();
+        let response = self.github.fetch_async(request).await.map_err(|e| format!("Error {:?} from {:?}", e, url))?;
+        if !response.ok {
+            return Err(format!("{} {} from {:?}: {}", response.status, response.status_text, url, String::from_utf8_lossy(&response.bytes)));
+        }
+
+        serde_json::from_slice(&response.bytes).map_err(|e| format!("error: {:?} when parsing response from {:?}", e, url))
+    }
+}
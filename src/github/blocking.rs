@@ -0,0 +1,85 @@
+//! Waits for the fetchers, for the modes without an UI event loop.
+
+use std::collections::BTreeMap;
+use std::sync::mpsc;
+use std::time::Duration;
+
+use crate::github::github_client::{spawn, GitHubApi};
+use crate::github::pulls::PullRequest;
+use crate::github::rate_limit::RateLimit;
+use crate::github::runs::WorkflowRuns;
+
+/// Open pull requests, latest runs and default branches of some repositories. Repositories that
+/// could not be fetched are left out, and why is in `errors`.
+pub struct Snapshot {
+    pub pulls: BTreeMap<String, Vec<PullRequest>>,
+    pub runs: BTreeMap<String, WorkflowRuns>,
+    pub default_branches: BTreeMap<String, String>,
+    pub rate_limit: Option<RateLimit>,
+    pub errors: Vec<String>,
+}
+
+enum Fetched {
+    Pulls(String, Vec<PullRequest>),
+    Runs(String, WorkflowRuns),
+    DefaultBranch(String, String),
+    RateLimit(RateLimit),
+}
+
+/// Fetches all repositories in parallel and blocks until every request has answered or failed.
+/// `timeout` only matters when GitHub stops answering: waiting stops once nothing has arrived for
+/// that long, and the requests still out are counted as errors.
+pub fn fetch(github: &GitHubApi, token: &str, repos: &[String], timeout: Duration) -> Snapshot {
+    let (sender, receiver) = mpsc::channel();
+
+    for repo in repos {
+        let (_sender, _github, _token, _repo) = (sender.clone(), github.clone(), token.to_string(), repo.clone());
+        spawn(async move {
+            let result = _github.fetch_pull_requests(&_token, &_repo).await;
+            let _ = _sender.send(result.map(|response| Fetched::Pulls(_repo, response)));
+        });
+
+        let (_sender, _github, _token, _repo) = (sender.clone(), github.clone(), token.to_string(), repo.clone());
+        spawn(async move {
+            let result = _github.fetch_runs(&_token, &_repo).await;
+            let _ = _sender.send(result.map(|response| Fetched::Runs(_repo, response)));
+        });
+
+        let (_sender, _github, _token, _repo) = (sender.clone(), github.clone(), token.to_string(), repo.clone());
+        spawn(async move {
+            let result = _github.fetch_default_branch(&_token, &_repo).await;
+            let _ = _sender.send(result.map(|response| Fetched::DefaultBranch(_repo, response)));
+        });
+    }
+
+    let (_sender, _github, _token) = (sender.clone(), github.clone(), token.to_string());
+    spawn(async move {
+        let result = _github.fetch_rate_limit(&_token).await;
+        let _ = _sender.send(result.map(Fetched::RateLimit));
+    });
+
+    let mut snapshot = Snapshot {
+        pulls: BTreeMap::new(),
+        runs: BTreeMap::new(),
+        default_branches: BTreeMap::new(),
+        rate_limit: None,
+        errors: vec![],
+    };
+
+    let expected = repos.len() * 3 + 1;
+    for received in 0..expected {
+        match receiver.recv_timeout(timeout) {
+            Ok(Ok(Fetched::Pulls(repo, response))) => { snapshot.pulls.insert(repo, response); }
+            Ok(Ok(Fetched::Runs(repo, response))) => { snapshot.runs.insert(repo, response); }
+            Ok(Ok(Fetched::DefaultBranch(repo, response))) => { snapshot.default_branches.insert(repo, response); }
+            Ok(Ok(Fetched::RateLimit(response))) => snapshot.rate_limit = Some(response),
+            Ok(Err(e)) => snapshot.errors.push(e),
+            Err(_) => {
+                snapshot.errors.push(format!("error: {} requests to GitHub timed out", expected - received));
+                break;
+            }
+        }
+    }
+
+    snapshot
+}
@@ -0,0 +1,194 @@
+//! Records the GitHub traffic of a session to a cassette file, and serves it back later without
+//! network, so what was seen on real data can be reproduced offline.
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum Mode {
+    /// Requests go to the network and nothing is kept.
+    #[default]
+    Off,
+    /// Requests go to the network and the responses are kept.
+    Record,
+    /// Requests are answered from the cassette, never from the network.
+    Replay,
+}
+
+/// A request and the response to it. Request headers are left out so the token is never written
+/// to the cassette.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Interaction {
+    pub method: String,
+    pub url: String,
+    /// Of the request, to tell requests to the same URL apart, e.g. GraphQL queries.
+    #[serde(default)]
+    pub request_body: String,
+    pub status: u16,
+    pub status_text: String,
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    #[serde(default)]
+    pub body: String,
+}
+
+impl Interaction {
+    fn response(&self) -> ehttp::Response {
+        ehttp::Response {
+            url: self.url.clone(),
+            ok: (200..300).contains(&self.status),
+            status: self.status,
+            status_text: self.status_text.clone(),
+            bytes: self.body.clone().into_bytes(),
+            headers: self.headers.clone(),
+        }
+    }
+}
+
+#[derive(Default)]
+struct Tape {
+    mode: Mode,
+    interactions: Vec<Interaction>,
+    /// How many times each request has been replayed, so repeated requests get the responses in
+    /// the order they were recorded.
+    replayed: HashMap<(String, String, String), usize>,
+}
+
+/// Shared by every clone of the client it belongs to, so switching mode affects all of them.
+#[derive(Clone, Default)]
+pub struct Cassette {
+    tape: Arc<Mutex<Tape>>,
+}
+
+impl Cassette {
+    pub fn mode(&self) -> Mode {
+        self.tape.lock().unwrap().mode
+    }
+
+    pub fn len(&self) -> usize {
+        self.tape.lock().unwrap().interactions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Starts recording on an empty cassette.
+    pub fn record(&self) {
+        let mut tape = self.tape.lock().unwrap();
+        *tape = Tape { mode: Mode::Record, ..Tape::default() };
+    }
+
+    /// Answers requests with these interactions from now on.
+    pub fn replay(&self, interactions: Vec<Interaction>) {
+        let mut tape = self.tape.lock().unwrap();
+        *tape = Tape { mode: Mode::Replay, interactions, ..Tape::default() };
+    }
+
+    /// Goes back to the network, keeping what was recorded until the next [`Cassette::record`].
+    pub fn stop(&self) {
+        let mut tape = self.tape.lock().unwrap();
+        tape.mode = Mode::Off;
+        tape.replayed.clear();
+    }
+
+    pub fn interactions(&self) -> Vec<Interaction> {
+        self.tape.lock().unwrap().interactions.clone()
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn load(path: &str) -> Result<Vec<Interaction>, String> {
+        let content = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+        serde_json::from_str(&content).map_err(|e| format!("{}: {}", path, e))
+    }
+
+    /// Writes the interactions recorded so far as JSON, returning how many there were.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn save(&self, path: &str) -> Result<usize, String> {
+        let interactions = self.interactions();
+        let content = serde_json::to_string_pretty(&interactions).map_err(|e| format!("{}: {}", path, e))?;
+        std::fs::write(path, content).map_err(|e| format!("{}: {}", path, e))?;
+        Ok(interactions.len())
+    }
+
+    /// Like [`ehttp::fetch`], except that the response is recorded or replayed depending on the mode.
+    pub fn fetch(
+        &self,
+        request: ehttp::Request,
+        on_done: impl 'static + Send + FnOnce(ehttp::Result<ehttp::Response>),
+    ) {
+        match self.mode() {
+            Mode::Off => ehttp::fetch(request, on_done),
+            Mode::Record => {
+                let tape = self.tape.clone();
+                let (method, url) = (request.method.clone(), request.url.clone());
+                let request_body = String::from_utf8_lossy(&request.body).into_owned();
+                ehttp::fetch(request, move |result: ehttp::Result<ehttp::Response>| {
+                    if let Ok(res) = &result {
+                        record(&tape, method, url, request_body, res);
+                    }
+                    on_done(result);
+                });
+            }
+            Mode::Replay => {
+                let result = self.play(&request.method, &request.url, &String::from_utf8_lossy(&request.body))
+                    .ok_or_else(|| format!("{} {} is not in the cassette", request.method, request.url));
+                // Callers may hold locks the callback takes, as they would with a real request.
+                respond_later(move || on_done(result));
+            }
+        }
+    }
+
+    /// Like [`Cassette::fetch`], as a future.
+    pub async fn fetch_async(&self, request: ehttp::Request) -> ehttp::Result<ehttp::Response> {
+        let (sender, receiver) = futures::channel::oneshot::channel();
+        self.fetch(request, move |result| {
+            let _ = sender.send(result);
+        });
+        receiver.await.unwrap_or_else(|_| Err(String::from("the request was dropped")))
+    }
+
+    fn play(&self, method: &str, url: &str, request_body: &str) -> Option<ehttp::Response> {
+        let mut tape = self.tape.lock().unwrap();
+        let Tape { interactions, replayed, .. } = &mut *tape;
+
+        let matching = interactions.iter()
+            .filter(|interaction| interaction.method == method && interaction.url == url && interaction.request_body == request_body)
+            .collect::<Vec<_>>();
+        let times = replayed.entry((method.to_string(), url.to_string(), request_body.to_string())).or_default();
+        // The last response is repeated once the recorded ones are used up, like refreshing would.
+        let interaction = matching.get(*times).or_else(|| matching.last())?;
+        *times += 1;
+        Some(interaction.response())
+    }
+}
+
+/// Binary responses such as avatars are not recorded, the cassette is meant to be readable.
+fn record(tape: &Mutex<Tape>, method: String, url: String, request_body: String, res: &ehttp::Response) {
+    let Ok(body) = String::from_utf8(res.bytes.clone()) else { return };
+
+    let mut tape = tape.lock().unwrap();
+    if tape.mode == Mode::Record {
+        tape.interactions.push(Interaction {
+            method,
+            url,
+            request_body,
+            status: res.status,
+            status_text: res.status_text.clone(),
+            headers: res.headers.clone(),
+            body,
+        });
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn respond_later(respond: impl 'static + Send + FnOnce()) {
+    std::thread::spawn(respond);
+}
+
+#[cfg(target_arch = "wasm32")]
+fn respond_later(respond: impl 'static + Send + FnOnce()) {
+    wasm_bindgen_futures::spawn_local(async move { respond() });
+}
@@ -1,27 +1,159 @@
-use crate::github::pulls::PullRequest;
+use std::collections::BTreeMap;
+use std::future::Future;
+
+use crate::forge::ChangeRequest;
+use crate::github::cassette::Cassette;
+use crate::github::oauth::{AccessToken, DeviceCode};
+use crate::github::pulls::{PullRequest, Review, User};
+use crate::github::rate_limit::RateLimit;
 use crate::github::runs::WorkflowRuns;
+use crate::github::scheduler::{Scheduler, DEFAULT_MAX_CONCURRENCY};
+use crate::github::users::Identity;
 use crate::github::workflows::Workflow;
 
-pub struct GitHubApi {}
+pub const GITHUB_API: &str = "https://api.github.com";
+pub const DEFAULT_ORG: &str = "navikt";
+
+/// Client for the GitHub REST API, or for a backend proxying it when `base_url` points elsewhere.
+#[derive(serde::Deserialize, serde::Serialize, Clone)]
+#[serde(default)]
+pub struct GitHubApi {
+    pub base_url: String,
+    /// Organisation owning the repositories and teams.
+    pub org: String,
+    /// Requests to GitHub in flight at once, the rest wait in the [`Scheduler`].
+    pub max_concurrency: usize,
+    #[serde(skip)]
+    pub cassette: Cassette,
+    /// Shared by the clones handed to the fetches in the background.
+    #[serde(skip)]
+    pub scheduler: Scheduler,
+    /// The view the requests are made for, see [`GitHubApi::for_view`].
+    #[serde(skip)]
+    pub view: Option<String>,
+}
 
 impl Default for GitHubApi {
-    fn default() -> Self { Self {} }
+    fn default() -> Self {
+        Self {
+            base_url: GITHUB_API.to_string(),
+            org: DEFAULT_ORG.to_string(),
+            max_concurrency: DEFAULT_MAX_CONCURRENCY,
+            cassette: Cassette::default(),
+            scheduler: Scheduler::default(),
+            view: None,
+        }
+    }
+}
+
+impl GitHubApi {
+    /// A GET request for the REST API, authorized with `token`.
+    pub fn get(&self, token: &str, url: &str) -> ehttp::Request {
+        ehttp::Request {
+            headers: ehttp::headers(&[
+                ("Accept", "application/vnd.github+json"),
+                ("User-Agent", "rust web-api-client demo"),
+                ("Authorization", format!("Bearer {}", token.trim()).as_str()),
+            ]),
+            ..ehttp::Request::get(url)
+        }
+    }
+
+    /// Sends `request` when the [`Scheduler`] lets it, through the cassette.
+    pub fn fetch(&self, request: ehttp::Request, on_done: impl 'static + Send + FnOnce(ehttp::Result<ehttp::Response>)) {
+        self.scheduler.fetch(&self.cassette, self.max_concurrency, self.view.as_deref(), request, on_done);
+    }
+
+    /// Like [`GitHubApi::fetch`], as a future.
+    pub async fn fetch_async(&self, request: ehttp::Request) -> ehttp::Result<ehttp::Response> {
+        self.scheduler.fetch_async(&self.cassette, self.max_concurrency, self.view.as_deref(), request).await
+    }
+
+    /// A clone making its requests for `view`, which go first while it's on screen and are
+    /// cancelled once it's not. Those made without a view are never cancelled.
+    pub fn for_view(&self, view: &str) -> Self {
+        Self { view: Some(view.to_string()), ..self.clone() }
+    }
+}
+
+/// Runs a future in the background, on a thread of its own natively and on the event loop of the
+/// browser on the web.
+pub fn spawn(future: impl 'static + Send + Future<Output = ()>) {
+    #[cfg(not(target_arch = "wasm32"))]
+    std::thread::spawn(move || futures::executor::block_on(future));
+    #[cfg(target_arch = "wasm32")]
+    wasm_bindgen_futures::spawn_local(future);
+}
+
+/// Calls `callback` with what `future` fetches, or prints to stderr why it couldn't. How the
+/// callback based traits below are made from the `async` methods of [`GitHubApi`].
+pub fn spawn_then<T: 'static>(
+    future: impl 'static + Send + Future<Output = Result<T, String>>,
+    callback: impl 'static + Send + FnOnce(T),
+) {
+    spawn(async move {
+        match future.await {
+            Ok(value) => callback(value),
+            Err(e) => eprintln!("{}", e),
+        }
+    });
 }
 
 pub trait Pulls {
     fn pull_requests(
         &self,
         token: &mut String,
-        repo: &String,
+        repo: &str,
+        callback: impl 'static + Send + FnOnce(Vec<PullRequest>),
+    );
+
+    /// Fetch one page (up to 100) of open and closed pull requests, most recently updated first.
+    fn pull_request_history(
+        &self,
+        token: &mut String,
+        repo: &str,
+        page: i32,
         callback: impl 'static + Send + FnOnce(Vec<PullRequest>),
     );
+
+    fn reviews(
+        &self,
+        token: &mut String,
+        repo: &str,
+        number: i32,
+        callback: impl 'static + Send + FnOnce(Vec<Review>),
+    );
+}
+
+/// Open pull requests of many repositories in a few GraphQL queries, with the review decision,
+/// status of checks, labels and requested reviewers the REST API needs more requests for.
+pub trait BulkPulls {
+    /// Fails when any query does, e.g. through a backend that only proxies the REST API.
+    fn open_pull_requests(
+        &self,
+        token: &mut String,
+        repos: &[String],
+        callback: impl 'static + Send + FnOnce(Result<BTreeMap<String, Vec<ChangeRequest>>, String>),
+    );
 }
 
 pub trait Runs {
     fn runs(
         &self,
         token: &mut String,
-        repo: &String,
+        repo: &str,
+        callback: impl 'static + Send + FnOnce(WorkflowRuns),
+    );
+
+    /// Fetch one page (up to 100 runs) of the run history of a single workflow,
+    /// identified by its id or file name. `created` filters on when runs were created, e.g. `>=2023-01-31`.
+    fn workflow_runs(
+        &self,
+        token: &mut String,
+        repo: &str,
+        workflow: &str,
+        created: Option<&str>,
+        page: i32,
         callback: impl 'static + Send + FnOnce(WorkflowRuns),
     );
 }
@@ -30,7 +162,7 @@ pub trait Workflows {
     fn workflows(
         &self,
         token: &mut String,
-        repo: &String,
+        repo: &str,
         callback: impl 'static + Send + FnOnce(Vec<Workflow>),
     );
 }
@@ -38,8 +170,55 @@ pub trait Workflows {
 pub trait Teams {
     fn teams(
         &self,
-        url: &String,
+        url: &str,
         token: &mut String,
         callback: impl 'static + Send + FnOnce(ehttp::Response),
     );
+
+    fn team_members(
+        &self,
+        token: &mut String,
+        slug: &str,
+        callback: impl 'static + Send + FnOnce(Vec<User>),
+    );
+}
+
+pub trait RateLimits {
+    fn rate_limit(
+        &self,
+        token: &mut String,
+        callback: impl 'static + Send + FnOnce(RateLimit),
+    );
+}
+
+pub trait Users {
+    /// The user the token belongs to and the scopes granted to it, or why the token was rejected.
+    fn authenticated_user(
+        &self,
+        token: &mut String,
+        callback: impl 'static + Send + FnOnce(Result<Identity, String>),
+    );
+
+    fn avatar(
+        &self,
+        url: &str,
+        callback: impl 'static + Send + FnOnce(Vec<u8>),
+    );
+}
+
+/// The OAuth device authorization flow, signing in without pasting a token.
+pub trait DeviceFlow {
+    fn device_code(
+        &self,
+        client_id: &str,
+        scope: &str,
+        callback: impl 'static + Send + FnOnce(Result<DeviceCode, String>),
+    );
+
+    fn access_token(
+        &self,
+        client_id: &str,
+        device_code: &str,
+        callback: impl 'static + Send + FnOnce(Result<AccessToken, String>),
+    );
 }
@@ -0,0 +1,261 @@
+//! Open pull requests of all repositories from the GraphQL API, a query per [`BATCH`] repositories
+//! instead of a REST request per repository and more per pull request.
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+use serde::{Deserialize, Serialize};
+
+use crate::forge::ChangeRequest;
+use crate::github::github_client::{BulkPulls, GitHubApi};
+
+/// Repositories per query, keeping each well below the node limit of GitHub.
+pub const BATCH: usize = 25;
+
+/// Open pull requests per repository, newest first, and labels and review requests per pull request.
+const FIRST: usize = 50;
+
+impl GitHubApi {
+    /// `/graphql` next to the REST API, which on GitHub Enterprise Server is at `/api/v3`.
+    pub fn graphql_url(&self) -> String {
+        let base_url = self.base_url.trim_end_matches('/');
+        match base_url.strip_suffix("/api/v3") {
+            Some(server) => format!("{}/api/graphql", server),
+            None => format!("{}/graphql", base_url),
+        }
+    }
+
+    fn query(&self, repos: &[String]) -> String {
+        let fields = repos.iter().enumerate().map(|(i, repo)| {
+            // JSON strings are valid GraphQL strings, quotes and all.
+            format!(
+                "r{}: repository(owner: {}, name: {}) {{ ...openPulls }}\n",
+                i, serde_json::Value::from(self.org.as_str()), serde_json::Value::from(repo.as_str()),
+            )
+        }).collect::<String>();
+
+        format!(
+            "query {{\n{}}}\n\
+            fragment openPulls on Repository {{\n\
+              pullRequests(states: OPEN, first: {first}, orderBy: {{field: CREATED_AT, direction: DESC}}) {{\n\
+                nodes {{\n\
+                  number title url createdAt updatedAt reviewDecision\n\
+                  author {{ login }}\n\
+                  labels(first: {first}) {{ nodes {{ name }} }}\n\
+                  reviewRequests(first: {first}) {{ nodes {{ requestedReviewer {{ ... on User {{ login }} ... on Team {{ slug }} }} }} }}\n\
+                  commits(last: 1) {{ nodes {{ commit {{ statusCheckRollup {{ state }} }} }} }}\n\
+                }}\n\
+              }}\n\
+            }}\n",
+            fields, first = FIRST,
+        )
+    }
+
+    fn query_batch(
+        &self,
+        token: &str,
+        repos: Vec<String>,
+        callback: impl 'static + Send + FnOnce(Result<BTreeMap<String, Vec<ChangeRequest>>, String>),
+    ) {
+        let url = self.graphql_url();
+        let body = serde_json::json!({ "query": self.query(&repos) });
+
+        let request = ehttp::Request {
+            headers: ehttp::headers(&[
+                ("Accept", "application/json"),
+                ("Content-Type", "application/json"),
+                ("User-Agent", "rust web-api-client demo"),
+                ("Authorization", format!("Bearer {}", token.trim()).as_str()),
+            ]),
+            ..ehttp::Request::post(&url, body.to_string().into_bytes())
+        };
+
+        self.fetch(request, move |result: ehttp::Result<ehttp::Response>| {
+            callback(match result {
+                Ok(res) if !res.ok => Err(format!("error: {} {} from {:?}", res.status, res.status_text, &url)),
+                Ok(res) => serde_json::from_slice::<GraphqlResponse>(&res.bytes)
+                    .map_err(|e| format!("error: {:?} when parsing pull requests from {:?}", e, &url))
+                    .and_then(|response| response.pull_requests(&repos)),
+                Err(e) => Err(format!("Error {:?} from {:?}", e, &url)),
+            })
+        });
+    }
+}
+
+impl BulkPulls for GitHubApi {
+    fn open_pull_requests(
+        &self,
+        token: &mut String,
+        repos: &[String],
+        callback: impl 'static + Send + FnOnce(Result<BTreeMap<String, Vec<ChangeRequest>>, String>),
+    ) {
+        let batches = repos.chunks(BATCH).map(|batch| batch.to_vec()).collect::<Vec<_>>();
+        if batches.is_empty() {
+            callback(Ok(BTreeMap::new()));
+            return;
+        }
+
+        // The callback is called once, when the last batch is in, with the first error if any.
+        let joined = Arc::new(Mutex::new(Joined {
+            remaining: batches.len(),
+            result: Ok(BTreeMap::new()),
+            callback: Some(Box::new(callback)),
+        }));
+
+        for batch in batches {
+            let _joined = joined.clone();
+            self.query_batch(token, batch, move |result| {
+                let mut joined = _joined.lock().unwrap();
+                match (&mut joined.result, result) {
+                    (Ok(all), Ok(found)) => all.extend(found),
+                    (Ok(_), Err(e)) => joined.result = Err(e),
+                    (Err(_), _) => {}
+                }
+                joined.remaining -= 1;
+
+                if joined.remaining == 0 {
+                    let result = std::mem::replace(&mut joined.result, Ok(BTreeMap::new()));
+                    if let Some(callback) = joined.callback.take() {
+                        drop(joined);
+                        callback(result);
+                    }
+                }
+            });
+        }
+    }
+}
+
+type Callback = Box<dyn FnOnce(Result<BTreeMap<String, Vec<ChangeRequest>>, String>) + Send>;
+
+struct Joined {
+    remaining: usize,
+    result: Result<BTreeMap<String, Vec<ChangeRequest>>, String>,
+    callback: Option<Callback>,
+}
+
+#[derive(Deserialize, Debug)]
+struct GraphqlResponse {
+    /// Repositories by alias, `null` for those not found or not accessible.
+    data: Option<BTreeMap<String, Option<Repository>>>,
+    #[serde(default)]
+    errors: Vec<GraphqlError>,
+}
+
+impl GraphqlResponse {
+    /// The repositories answered for, which are all of them unless there were errors.
+    fn pull_requests(self, repos: &[String]) -> Result<BTreeMap<String, Vec<ChangeRequest>>, String> {
+        let Some(mut data) = self.data else {
+            let messages = self.errors.iter().map(|e| e.message.as_str()).collect::<Vec<_>>();
+            return Err(format!("error: {} from GraphQL", messages.join(", ")));
+        };
+        self.errors.iter().for_each(|e| eprintln!("error: {} from GraphQL", e.message));
+
+        Ok(repos.iter().enumerate()
+            .filter_map(|(i, repo)| {
+                let repository = data.remove(&format!("r{}", i)).flatten()?;
+                Some((repo.clone(), repository.pull_requests.nodes.iter().map(ChangeRequest::from).collect()))
+            })
+            .collect())
+    }
+}
+
+#[derive(Deserialize, Debug)]
+struct GraphqlError {
+    message: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct Nodes<T> {
+    nodes: Vec<T>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct Repository {
+    pull_requests: Nodes<PullRequestNode>,
+}
+
+/// What the REST API spreads over the pull request, its reviews and the checks of its head commit.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PullRequestNode {
+    pub number: i64,
+    pub title: String,
+    pub url: String,
+    pub created_at: Option<String>,
+    pub updated_at: Option<String>,
+    /// `APPROVED`, `CHANGES_REQUESTED` or `REVIEW_REQUIRED`, unset when no review is required.
+    review_decision: Option<String>,
+    /// Unset for deleted users.
+    author: Option<Login>,
+    labels: Option<Nodes<Label>>,
+    review_requests: Option<Nodes<ReviewRequest>>,
+    commits: Nodes<CommitNode>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct Login {
+    login: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct Label {
+    name: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+struct ReviewRequest {
+    /// A user or a team, or empty for reviewers the token can't see.
+    requested_reviewer: Option<Reviewer>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct Reviewer {
+    login: Option<String>,
+    slug: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct CommitNode {
+    commit: Commit,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+struct Commit {
+    /// Unset when the commit has no checks.
+    status_check_rollup: Option<StatusCheckRollup>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct StatusCheckRollup {
+    /// `SUCCESS`, `FAILURE`, `PENDING`, `ERROR` or `EXPECTED`.
+    state: String,
+}
+
+impl Nodes<ReviewRequest> {
+    fn reviewers(&self) -> Vec<String> {
+        self.nodes.iter()
+            .filter_map(|request| request.requested_reviewer.as_ref())
+            .filter_map(|reviewer| reviewer.login.clone().or_else(|| reviewer.slug.clone()))
+            .collect()
+    }
+}
+
+impl From<&PullRequestNode> for ChangeRequest {
+    fn from(pr: &PullRequestNode) -> Self {
+        Self {
+            number: pr.number,
+            title: pr.title.clone(),
+            url: pr.url.clone(),
+            author: pr.author.as_ref().map(|author| author.login.clone()).unwrap_or_default(),
+            created_at: pr.created_at.clone(),
+            updated_at: pr.updated_at.clone(),
+            reviewers: pr.review_requests.as_ref().map(Nodes::reviewers).unwrap_or_default(),
+            labels: pr.labels.iter().flat_map(|labels| labels.nodes.iter().map(|label| label.name.clone())).collect(),
+            review_decision: pr.review_decision.clone(),
+            checks: pr.commits.nodes.last().and_then(|node| node.commit.status_check_rollup.as_ref()).map(|rollup| rollup.state.clone()),
+        }
+    }
+}
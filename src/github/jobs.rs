@@ -0,0 +1,44 @@
+use serde::{Deserialize, Serialize};
+
+use crate::github::github_client::GitHubApi;
+
+impl GitHubApi {
+    /// The jobs of one attempt of a workflow run. Re-runs replace the attempts before them in the
+    /// runs listed, so this is where the failed attempt before a successful re-run is found.
+    pub async fn fetch_attempt_jobs(&self, token: &str, repo: &str, run_id: i64, attempt: i32) -> Result<Vec<Job>, String> {
+        let url = format!(
+            "{}/repos/{}/{}/actions/runs/{}/attempts/{}/jobs?per_page=100",
+            self.base_url, self.org, repo, run_id, attempt,
+        );
+
+        match self.fetch_async(self.get(token, &url)).await {
+            Ok(res) if !res.ok => Err(format!("error: {} {} from {:?}", res.status, res.status_text, &url)),
+            Ok(res) => serde_json::from_slice::<JobsResponse>(&res.bytes)
+                .map(|response| response.jobs)
+                .map_err(|e| format!("error: {:?} when parsing jobs with content {:?}", e, res)),
+            Err(e) => Err(format!("Error {:?} from {:?}", e, &url)),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct JobsResponse {
+    jobs: Vec<Job>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Job {
+    pub id: i64,
+    pub run_id: i64,
+    pub run_attempt: i32,
+    pub name: String,
+    pub status: Option<String>,
+    pub conclusion: Option<String>,
+    pub html_url: Option<String>,
+}
+
+impl Job {
+    pub fn is_failure(&self) -> bool {
+        matches!(self.conclusion.as_deref(), Some("failure" | "timed_out"))
+    }
+}
@@ -1,5 +1,17 @@
+#[cfg(not(target_arch = "wasm32"))]
+pub mod app_auth;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod blocking;
+pub mod cassette;
 pub mod github_client;
+pub mod graphql;
+pub mod jobs;
+pub mod oauth;
 pub mod pulls;
+pub mod rate_limit;
+pub mod repos;
 pub mod runs;
+pub mod scheduler;
 pub mod teams;
+pub mod users;
 pub mod workflows;
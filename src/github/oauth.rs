@@ -0,0 +1,85 @@
+use serde::{Deserialize, Serialize};
+
+use crate::github::github_client::{DeviceFlow, GitHubApi, GITHUB_API};
+
+impl GitHubApi {
+    /// Browsers can't reach github.com/login because of CORS, so a backend proxies it as well.
+    fn oauth_url(&self) -> String {
+        match self.base_url.as_str() {
+            GITHUB_API => String::from("https://github.com"),
+            backend => backend.to_string(),
+        }
+    }
+}
+
+impl DeviceFlow for GitHubApi {
+    fn device_code(
+        &self,
+        client_id: &str,
+        scope: &str,
+        callback: impl 'static + Send + FnOnce(Result<DeviceCode, String>),
+    ) {
+        let url = format!("{}/login/device/code", self.oauth_url());
+        let body = serde_json::json!({ "client_id": client_id, "scope": scope });
+        post(&url, body, callback);
+    }
+
+    fn access_token(
+        &self,
+        client_id: &str,
+        device_code: &str,
+        callback: impl 'static + Send + FnOnce(Result<AccessToken, String>),
+    ) {
+        let url = format!("{}/login/oauth/access_token", self.oauth_url());
+        let body = serde_json::json!({
+            "client_id": client_id,
+            "device_code": device_code,
+            "grant_type": "urn:ietf:params:oauth:grant-type:device_code",
+        });
+        post(&url, body, callback);
+    }
+}
+
+fn post<T: 'static + for<'de> Deserialize<'de>>(
+    url: &str,
+    body: serde_json::Value,
+    callback: impl 'static + Send + FnOnce(Result<T, String>),
+) {
+    let request = ehttp::Request {
+        headers: ehttp::headers(&[
+            ("Accept", "application/json"),
+            ("Content-Type", "application/json"),
+            ("User-Agent", "rust web-api-client demo"),
+        ]),
+        ..ehttp::Request::post(url, body.to_string().into_bytes())
+    };
+
+    // Not through the cassette, the response holds the access token.
+    let url = url.to_string();
+    ehttp::fetch(request, move |result: ehttp::Result<ehttp::Response>| {
+        callback(match result {
+            Ok(res) => serde_json::from_slice(&res.bytes)
+                .map_err(|e| format!("error: {:?} when parsing response from {:?}", e, url)),
+            Err(e) => Err(format!("Error {:?} from {:?}", e, url)),
+        })
+    });
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DeviceCode {
+    pub device_code: String,
+    pub user_code: String,
+    pub verification_uri: String,
+    pub expires_in: u64,
+    pub interval: u64,
+}
+
+/// Either the token, or why there is none yet (`authorization_pending`, `slow_down`,
+/// `expired_token`, `access_denied` and so on).
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AccessToken {
+    pub access_token: Option<String>,
+    pub error: Option<String>,
+    pub error_description: Option<String>,
+    pub interval: Option<u64>,
+}
@@ -1,41 +1,78 @@
+use chrono::{DateTime, Utc};
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 
-use crate::github::github_client::{GitHubApi, Pulls};
+use crate::github::github_client::{spawn_then, GitHubApi, Pulls};
+use crate::github::runs::parse_timestamp;
+
+impl GitHubApi {
+    pub async fn fetch_pull_requests(&self, token: &str, repo: &str) -> Result<Vec<PullRequest>, String> {
+        let url = format!("{}/repos/{}/{}/pulls", self.base_url, self.org, repo);
+        self.fetch_list(token, url, "pulls").await
+    }
+
+    /// One page (up to 100) of open and closed pull requests, most recently updated first.
+    pub async fn fetch_pull_request_history(&self, token: &str, repo: &str, page: i32) -> Result<Vec<PullRequest>, String> {
+        let url = format!(
+            "{}/repos/{}/{}/pulls?state=all&sort=updated&direction=desc&per_page=100&page={}",
+            self.base_url, self.org, repo, page,
+        );
+        self.fetch_list(token, url, "pull history").await
+    }
+
+    pub async fn fetch_reviews(&self, token: &str, repo: &str, number: i32) -> Result<Vec<Review>, String> {
+        let url = format!("{}/repos/{}/{}/pulls/{}/reviews?per_page=100", self.base_url, self.org, repo, number);
+        self.fetch_list(token, url, "reviews").await
+    }
+
+    /// A list, where GitHub answers an empty object instead of an empty list.
+    async fn fetch_list<T: DeserializeOwned>(&self, token: &str, url: String, what: &str) -> Result<Vec<T>, String> {
+        match self.fetch_async(self.get(token, &url)).await {
+            // Error messages are objects too, and would be taken for an empty list.
+            Ok(res) if !res.ok => Err(format!("error: {} {} from {:?}", res.status, res.status_text, &url)),
+            Ok(res) => {
+                match serde_json::from_slice::<DataOrEmpty<Vec<T>>>(&res.bytes) {
+                    Ok(DataOrEmpty::Data(list)) => Ok(list),
+                    Ok(DataOrEmpty::Empty {}) => Ok(vec![]),
+                    Err(e) => Err(format!("error: {:?} when parsing {} with content {:?}", e, what, res)),
+                }
+            }
+            Err(e) => Err(format!("Error {:?} from {:?}", e, &url)),
+        }
+    }
+}
 
 impl Pulls for GitHubApi {
     fn pull_requests(
         &self, token:
         &mut String,
-        repo: &String,
+        repo: &str,
         callback: impl 'static + Send + FnOnce(Vec<PullRequest>),
     ) {
-        let url = format!("https://api.github.com/repos/navikt/{}/pulls", repo);
-
-        let request = ehttp::Request {
-            headers: ehttp::headers(&[
-                ("Accept", "application/vnd.github+json"),
-                ("User-Agent", "rust web-api-client demo"),
-                ("Authorization", format!("Bearer {}", token.trim().to_string()).as_str()),
-            ]),
-            ..ehttp::Request::get(&url)
-        };
-
-        ehttp::fetch(request, move |result: ehttp::Result<ehttp::Response>| {
-            match result {
-                Ok(res) => {
-                    match serde_json::from_slice::<DataOrEmpty<Vec<PullRequest>>>(&res.bytes) {
-                        Ok(pulls) => {
-                            match pulls {
-                                DataOrEmpty::Data(prs) => callback(prs),
-                                DataOrEmpty::Empty {} => callback(Vec::<PullRequest>::default()),
-                            };
-                        }
-                        Err(e) => println!("error: {:?} when parsing pulls with content {:?}", e, res)
-                    }
-                }
-                Err(e) => println!("Error {:?} from {:?}", e, &url)
-            }
-        });
+        let (github, token, repo) = (self.clone(), token.clone(), repo.to_string());
+        spawn_then(async move { github.fetch_pull_requests(&token, &repo).await }, callback);
+    }
+
+    fn pull_request_history(
+        &self,
+        token: &mut String,
+        repo: &str,
+        page: i32,
+        callback: impl 'static + Send + FnOnce(Vec<PullRequest>),
+    ) {
+        let (github, token, repo) = (self.clone(), token.clone(), repo.to_string());
+        spawn_then(async move { github.fetch_pull_request_history(&token, &repo, page).await }, callback);
+    }
+
+    fn reviews(
+        &self,
+        token: &mut String,
+        repo: &str,
+        number: i32,
+        callback: impl 'static + Send + FnOnce(Vec<Review>),
+    ) {
+        let (github, token, repo) = (self.clone(), token.clone(), repo.to_string());
+        spawn_then(async move { github.fetch_reviews(&token, &repo, number).await }, callback);
     }
 }
 
@@ -61,10 +98,51 @@ pub struct PullRequest {
     pub html_url: Option<String>,
     pub title: Option<String>,
     body: Option<String>,
-    state: Option<String>,
+    pub state: Option<String>,
     pub user: Option<User>,
-    created_at: Option<String>,
+    pub created_at: Option<String>,
     pub updated_at: Option<String>,
+    pub merged_at: Option<String>,
+    pub merge_commit_sha: Option<String>,
+    #[serde(default)]
+    pub requested_reviewers: Vec<User>,
+}
+
+impl PullRequest {
+    /// Without what the views don't show, to keep offline.
+    pub fn trimmed(&self) -> Self {
+        Self {
+            url: String::new(),
+            body: None,
+            ..self.clone()
+        }
+    }
+
+    pub fn created(&self) -> Option<DateTime<Utc>> {
+        parse_timestamp(self.created_at.as_ref()?)
+    }
+
+    pub fn updated(&self) -> Option<DateTime<Utc>> {
+        parse_timestamp(self.updated_at.as_ref()?)
+    }
+
+    pub fn merged(&self) -> Option<DateTime<Utc>> {
+        parse_timestamp(self.merged_at.as_ref()?)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Review {
+    id: i64,
+    pub user: Option<User>,
+    pub state: String,
+    pub submitted_at: Option<String>,
+}
+
+impl Review {
+    pub fn submitted(&self) -> Option<DateTime<Utc>> {
+        parse_timestamp(self.submitted_at.as_ref()?)
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
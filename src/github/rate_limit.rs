@@ -0,0 +1,42 @@
+use serde::{Deserialize, Serialize};
+
+use crate::github::github_client::{spawn_then, GitHubApi, RateLimits};
+
+impl GitHubApi {
+    /// Not queued by the scheduler, as it is asked for alongside the requests it tells about.
+    pub async fn fetch_rate_limit(&self, token: &str) -> Result<RateLimit, String> {
+        let url = format!("{}/rate_limit", self.base_url);
+
+        match self.cassette.fetch_async(self.get(token, &url)).await {
+            Ok(res) => serde_json::from_slice::<RateLimitResponse>(&res.bytes)
+                .map(|rate_limit| rate_limit.rate)
+                .map_err(|e| format!("error: {:?} when parsing rate limit with content {:?}", e, res)),
+            Err(e) => Err(format!("Error {:?} from {:?}", e, &url)),
+        }
+    }
+}
+
+impl RateLimits for GitHubApi {
+    fn rate_limit(
+        &self,
+        token: &mut String,
+        callback: impl 'static + Send + FnOnce(RateLimit),
+    ) {
+        let (github, token) = (self.clone(), token.clone());
+        spawn_then(async move { github.fetch_rate_limit(&token).await }, callback);
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct RateLimitResponse {
+    rate: RateLimit,
+}
+
+/// The core REST API quota of the token.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RateLimit {
+    pub limit: i64,
+    pub remaining: i64,
+    pub used: i64,
+    pub reset: i64,
+}
@@ -0,0 +1,23 @@
+use serde::Deserialize;
+
+use crate::github::github_client::GitHubApi;
+
+impl GitHubApi {
+    /// The branch the workflows of `repo` are watched on, `main` for most.
+    pub async fn fetch_default_branch(&self, token: &str, repo: &str) -> Result<String, String> {
+        let url = format!("{}/repos/{}/{}", self.base_url, self.org, repo);
+
+        match self.fetch_async(self.get(token, &url)).await {
+            Ok(res) if !res.ok => Err(format!("error: {} {} from {:?}", res.status, res.status_text, &url)),
+            Ok(res) => serde_json::from_slice::<Repository>(&res.bytes)
+                .map(|repository| repository.default_branch)
+                .map_err(|e| format!("error: {:?} when parsing repository with content {:?}", e, res)),
+            Err(e) => Err(format!("Error {:?} from {:?}", e, &url)),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct Repository {
+    default_branch: String,
+}
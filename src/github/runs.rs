@@ -1,62 +1,86 @@
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
-use crate::github::github_client::{GitHubApi, Runs};
+use crate::github::github_client::{spawn_then, GitHubApi, Runs};
 use crate::github::pulls::PullRequest;
 
+impl GitHubApi {
+    pub async fn fetch_runs(&self, token: &str, repo: &str) -> Result<WorkflowRuns, String> {
+        let url = format!("{}/repos/{}/{}/actions/runs?per_page=100", self.base_url, self.org, repo);
+        self.fetch_workflow_runs_from(token, url, "runs").await
+    }
+
+    /// One page (up to 100 runs) of the run history of a single workflow, identified by its id or
+    /// file name. `created` filters on when runs were created, e.g. `>=2023-01-31`.
+    pub async fn fetch_workflow_runs(
+        &self,
+        token: &str,
+        repo: &str,
+        workflow: &str,
+        created: Option<&str>,
+        page: i32,
+    ) -> Result<WorkflowRuns, String> {
+        let mut url = format!(
+            "{}/repos/{}/{}/actions/workflows/{}/runs?per_page=100&page={}",
+            self.base_url, self.org, repo, workflow, page,
+        );
+        if let Some(created) = created {
+            url.push_str(&format!("&created={}", created.replace('>', "%3E").replace('<', "%3C").replace('=', "%3D")));
+        }
+        self.fetch_workflow_runs_from(token, url, "workflow runs").await
+    }
+
+    async fn fetch_workflow_runs_from(&self, token: &str, url: String, what: &str) -> Result<WorkflowRuns, String> {
+        match self.fetch_async(self.get(token, &url)).await {
+            Ok(res) => serde_json::from_slice(&res.bytes)
+                .map_err(|e| format!("error: {:?} when parsing {} with content {:?}", e, what, res)),
+            Err(e) => Err(format!("Error {:?} from {:?}", e, &url)),
+        }
+    }
+}
+
 impl Runs for GitHubApi {
     fn runs(
         &self,
         token: &mut String,
-        repo: &String,
+        repo: &str,
         callback: impl 'static + Send + FnOnce(WorkflowRuns),
     ) {
-        let url = format!("https://api.github.com/repos/navikt/{}/actions/runs", repo);
-
-        let request = ehttp::Request {
-            headers: ehttp::headers(&[
-                ("Accept", "application/vnd.github+json"),
-                ("User-Agent", "rust web-api-client demo"),
-                ("Authorization", format!("Bearer {}", token.trim().to_string()).as_str()),
-            ]),
-            ..ehttp::Request::get(&url)
-        };
-
-        ehttp::fetch(request, move |result: ehttp::Result<ehttp::Response>| {
-            match result {
-                Ok(res) => {
-                    match serde_json::from_slice(&res.bytes) {
-                        Ok(runs) => callback(runs),
-                        Err(e) => println!("error: {:?} when parsing runs with content {:?}", e, res)
-                    }
-                }
-                Err(e) => println!("Error {:?} from {:?}", e, &url)
-            }
-        });
+        let (github, token, repo) = (self.clone(), token.clone(), repo.to_string());
+        spawn_then(async move { github.fetch_runs(&token, &repo).await }, callback);
+    }
+
+    fn workflow_runs(
+        &self,
+        token: &mut String,
+        repo: &str,
+        workflow: &str,
+        created: Option<&str>,
+        page: i32,
+        callback: impl 'static + Send + FnOnce(WorkflowRuns),
+    ) {
+        let (github, token, repo) = (self.clone(), token.clone(), repo.to_string());
+        let (workflow, created) = (workflow.to_string(), created.map(String::from));
+        spawn_then(async move {
+            github.fetch_workflow_runs(&token, &repo, &workflow, created.as_deref(), page).await
+        }, callback);
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
 pub struct WorkflowRuns {
     pub total_count: i32,
     pub workflow_runs: Vec<WorkflowRun>,
 }
 
-impl Default for WorkflowRuns {
-    fn default() -> Self {
-        Self {
-            total_count: 0,
-            workflow_runs: vec![],
-        }
-    }
-}
-
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct WorkflowRun {
-    id: i64,
+    pub id: i64,
     pub name: Option<String>,
     check_suite_id: Option<i64>,
     check_suite_node_id: Option<String>,
-    head_sha: String,
+    pub head_sha: String,
+    pub head_branch: Option<String>,
     path: String,
     run_number: i32,
     pub run_attempt: i32,
@@ -65,10 +89,10 @@ pub struct WorkflowRun {
     pub conclusion: Option<String>,
     pub workflow_id: i64,
     url: String,
-    html_url: String,
+    pub html_url: String,
     pull_requests: Vec<PullRequest>,
-    created_at: String,
-    updated_at: String,
+    pub created_at: String,
+    pub updated_at: String,
     actor: Option<Actor>,
     triggering_actor: Option<Actor>,
     pub run_started_at: Option<String>,
@@ -82,6 +106,68 @@ pub struct WorkflowRun {
     display_title: String,
 }
 
+impl WorkflowRun {
+    /// Without what the views don't show, to keep offline.
+    pub fn trimmed(&self) -> Self {
+        Self {
+            check_suite_node_id: None,
+            url: String::new(),
+            pull_requests: vec![],
+            actor: None,
+            triggering_actor: None,
+            jobs_url: String::new(),
+            logs_url: String::new(),
+            check_suite_url: String::new(),
+            artifacts_url: String::new(),
+            cancel_url: String::new(),
+            rerun_url: String::new(),
+            workflow_url: String::new(),
+            display_title: String::new(),
+            ..self.clone()
+        }
+    }
+
+    /// When the run was created, used as the time axis in charts.
+    pub fn created(&self) -> Option<DateTime<Utc>> {
+        parse_timestamp(&self.created_at)
+    }
+
+    /// When the run was last updated, which for completed runs is when it finished.
+    pub fn updated(&self) -> Option<DateTime<Utc>> {
+        parse_timestamp(&self.updated_at)
+    }
+
+    /// Wall clock time from the latest attempt started until the run was last updated.
+    /// Only meaningful for completed runs.
+    pub fn duration_secs(&self) -> Option<i64> {
+        if self.status.as_deref() != Some("completed") {
+            return None;
+        }
+
+        let started = parse_timestamp(self.run_started_at.as_ref()?)?;
+        let updated = parse_timestamp(&self.updated_at)?;
+        Some((updated - started).num_seconds())
+    }
+
+    /// Whether the run belongs to the workflow with this id or file name, e.g. `deploy.yml`.
+    pub fn is_workflow(&self, workflow: &str) -> bool {
+        let path = self.path.split('@').next().unwrap_or_default();
+        self.workflow_id.to_string() == workflow || path.rsplit('/').next() == Some(workflow)
+    }
+
+    pub fn is_success(&self) -> bool {
+        self.conclusion.as_deref() == Some("success")
+    }
+
+    pub fn is_failure(&self) -> bool {
+        matches!(self.conclusion.as_deref(), Some("failure" | "timed_out" | "startup_failure"))
+    }
+}
+
+pub fn parse_timestamp(timestamp: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(timestamp).ok().map(|t| t.with_timezone(&Utc))
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Actor {
     name: Option<String>,
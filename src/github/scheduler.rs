@@ -0,0 +1,201 @@
+//! Queues the requests for the data shown in the views, so a refresh of many repositories doesn't
+//! open a connection per repository at once.
+//!
+//! At most `max_concurrency` requests are in flight. Requests made for the view on screen go
+//! before those made in the background, and requests still queued for another view are cancelled
+//! when the view changes. Requests made for no view in particular, like the syncs of the history,
+//! are never cancelled, also when the same request was made for a view too. A request identical to one queued or in flight waits for that one's
+//! response instead of being sent again.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+
+use crate::github::cassette::Cassette;
+
+/// Requests in flight unless configured otherwise, below the limit browsers have per host.
+pub const DEFAULT_MAX_CONCURRENCY: usize = 4;
+
+/// What requests cancelled by [`Scheduler::set_view`] are answered with.
+pub const CANCELLED: &str = "cancelled, the view changed";
+
+type Done = Box<dyn FnOnce(ehttp::Result<ehttp::Response>) + Send>;
+
+/// Requests are the same when everything sent is, the token included.
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct Key {
+    method: String,
+    url: String,
+    body: Vec<u8>,
+    authorization: Option<String>,
+}
+
+impl Key {
+    fn of(request: &ehttp::Request) -> Self {
+        Self {
+            method: request.method.clone(),
+            url: request.url.clone(),
+            body: request.body.clone(),
+            authorization: request.headers.get("Authorization").cloned(),
+        }
+    }
+}
+
+struct Job {
+    key: Key,
+    request: ehttp::Request,
+    /// The view the data is for, if any.
+    view: Option<String>,
+    cassette: Cassette,
+}
+
+#[derive(Default)]
+struct Queue {
+    max_concurrency: usize,
+    running: usize,
+    view: Option<String>,
+    waiting: VecDeque<Job>,
+    /// Everyone waiting for the response to each request queued or in flight, with the view each
+    /// asked for.
+    callbacks: HashMap<Key, Vec<(Option<String>, Done)>>,
+}
+
+impl Queue {
+    /// Takes the jobs to start now, those for the view on screen first.
+    fn ready(&mut self) -> Vec<Job> {
+        let mut ready = vec![];
+        while self.running < self.max_concurrency.max(1) {
+            let visible = self.waiting.iter().position(|job| job.view.is_some() && job.view == self.view);
+            let Some(job) = self.waiting.remove(visible.unwrap_or(0)) else { break };
+            self.running += 1;
+            ready.push(job);
+        }
+        ready
+    }
+}
+
+/// Shared by every clone of the client it belongs to.
+#[derive(Clone)]
+pub struct Scheduler {
+    queue: Arc<Mutex<Queue>>,
+}
+
+impl Default for Scheduler {
+    fn default() -> Self {
+        Self {
+            queue: Arc::new(Mutex::new(Queue {
+                max_concurrency: DEFAULT_MAX_CONCURRENCY,
+                ..Queue::default()
+            })),
+        }
+    }
+}
+
+impl Scheduler {
+    /// Requests in flight.
+    pub fn running(&self) -> usize {
+        self.queue.lock().unwrap().running
+    }
+
+    /// Requests waiting for others to finish.
+    pub fn waiting(&self) -> usize {
+        self.queue.lock().unwrap().waiting.len()
+    }
+
+    /// Called with the view on screen when it changes. Those waiting for requests queued for
+    /// another view are called back with [`CANCELLED`], and the requests are dropped unless
+    /// someone else still waits for them. Those in flight can't be taken back, and are left to
+    /// finish.
+    pub fn set_view(&self, view: Option<&str>) {
+        let mut queue = self.queue.lock().unwrap();
+        if queue.view.as_deref() == view {
+            return;
+        }
+        queue.view = view.map(String::from);
+
+        let Queue { waiting, callbacks, .. } = &mut *queue;
+        let stale = |asked: &Option<String>| asked.is_some() && asked.as_deref() != view;
+        let mut cancelled = vec![];
+        waiting.retain_mut(|job| {
+            if !stale(&job.view) {
+                return true;
+            }
+            let (gone, left) = callbacks.remove(&job.key).unwrap_or_default().into_iter()
+                .partition::<Vec<_>, _>(|(asked, _)| stale(asked));
+            cancelled.extend(gone.into_iter().map(|(_, callback)| callback));
+            if left.is_empty() {
+                return false;
+            }
+            // Left to those joining from no view or the new one, so it is cancelled as their own.
+            job.view = match left.iter().all(|(asked, _)| asked.is_some()) {
+                true => view.map(String::from),
+                false => None,
+            };
+            callbacks.insert(job.key.clone(), left);
+            true
+        });
+        drop(queue);
+
+        // Callbacks may fetch again, which shouldn't find the lock taken.
+        cancelled.into_iter().for_each(|callback| callback(Err(String::from(CANCELLED))));
+    }
+
+    /// Like [`Cassette::fetch`], once fewer than `max_concurrency` requests are in flight. `view`
+    /// is the view the data is for, or `None` for requests which shouldn't be cancelled.
+    pub fn fetch(
+        &self,
+        cassette: &Cassette,
+        max_concurrency: usize,
+        view: Option<&str>,
+        request: ehttp::Request,
+        on_done: impl 'static + Send + FnOnce(ehttp::Result<ehttp::Response>),
+    ) {
+        let key = Key::of(&request);
+
+        let mut queue = self.queue.lock().unwrap();
+        queue.max_concurrency = max_concurrency;
+        let view = view.map(String::from);
+        if let Some(callbacks) = queue.callbacks.get_mut(&key) {
+            callbacks.push((view, Box::new(on_done)));
+            return;
+        }
+        queue.callbacks.insert(key.clone(), vec![(view.clone(), Box::new(on_done))]);
+
+        queue.waiting.push_back(Job { key, request, view, cassette: cassette.clone() });
+        let ready = queue.ready();
+        drop(queue);
+
+        ready.into_iter().for_each(|job| self.start(job));
+    }
+
+    /// Like [`Scheduler::fetch`], as a future.
+    pub async fn fetch_async(
+        &self,
+        cassette: &Cassette,
+        max_concurrency: usize,
+        view: Option<&str>,
+        request: ehttp::Request,
+    ) -> ehttp::Result<ehttp::Response> {
+        let (sender, receiver) = futures::channel::oneshot::channel();
+        self.fetch(cassette, max_concurrency, view, request, move |result| {
+            let _ = sender.send(result);
+        });
+        receiver.await.unwrap_or_else(|_| Err(String::from(CANCELLED)))
+    }
+
+    fn start(&self, job: Job) {
+        let scheduler = self.clone();
+        let key = job.key;
+        job.cassette.fetch(job.request, move |result| scheduler.finish(key, result));
+    }
+
+    fn finish(&self, key: Key, result: ehttp::Result<ehttp::Response>) {
+        let mut queue = self.queue.lock().unwrap();
+        queue.running -= 1;
+        let callbacks = queue.callbacks.remove(&key).unwrap_or_default();
+        let ready = queue.ready();
+        drop(queue);
+
+        ready.into_iter().for_each(|job| self.start(job));
+        callbacks.into_iter().for_each(|(_, callback)| callback(result.clone()));
+    }
+}
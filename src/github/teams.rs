@@ -1,34 +1,54 @@
 use serde::{Deserialize, Serialize};
 
-use crate::github::github_client::{GitHubApi, Teams};
+use crate::github::github_client::{spawn_then, GitHubApi, Teams};
+use crate::github::pulls::User;
 
-impl Teams for GitHubApi {
-    fn teams(
-        &self,
-        url: &String,
-        token: &mut String,
-        callback: impl 'static + Send + FnOnce(ehttp::Response),
-    ) {
-
-        let _url = url.to_string();
+impl GitHubApi {
+    /// A page of teams as it came, for the caller to parse and to follow the `Link` header of.
+    pub async fn fetch_teams(&self, url: &str, token: &str) -> Result<ehttp::Response, String> {
         let request = ehttp::Request {
             headers: ehttp::headers(&[
                 ("Accept", "application/vnd.github+json"),
                 ("Access-Control-Allow-Headers", "Link"),
                 ("User-Agent", "Rust-wasm-App"),
-                ("Authorization", format!("Bearer {}", token.trim().to_string()).as_str()),
+                ("Authorization", format!("Bearer {}", token.trim()).as_str()),
             ]),
-            ..ehttp::Request::get(&_url)
+            ..ehttp::Request::get(url)
         };
 
-        ehttp::fetch(request, move |result: ehttp::Result<ehttp::Response>| {
-            match result {
-                Ok(res) => {
-                    callback(res);
-                }
-                Err(e) => println!("Error {:?} from {:?}", e, &_url)
-            }
-        });
+        self.fetch_async(request).await.map_err(|e| format!("Error {:?} from {:?}", e, url))
+    }
+
+    pub async fn fetch_team_members(&self, token: &str, slug: &str) -> Result<Vec<User>, String> {
+        let url = format!("{}/orgs/{}/teams/{}/members?per_page=100", self.base_url, self.org, slug);
+
+        match self.fetch_async(self.get(token, &url)).await {
+            Ok(res) => serde_json::from_slice::<Vec<User>>(&res.bytes)
+                .map_err(|e| format!("error: {:?} when parsing team members with content {:?}", e, res)),
+            Err(e) => Err(format!("Error {:?} from {:?}", e, &url)),
+        }
+    }
+}
+
+impl Teams for GitHubApi {
+    fn teams(
+        &self,
+        url: &str,
+        token: &mut String,
+        callback: impl 'static + Send + FnOnce(ehttp::Response),
+    ) {
+        let (github, token, url) = (self.clone(), token.clone(), url.to_string());
+        spawn_then(async move { github.fetch_teams(&url, &token).await }, callback);
+    }
+
+    fn team_members(
+        &self,
+        token: &mut String,
+        slug: &str,
+        callback: impl 'static + Send + FnOnce(Vec<User>),
+    ) {
+        let (github, token, slug) = (self.clone(), token.clone(), slug.to_string());
+        spawn_then(async move { github.fetch_team_members(&token, &slug).await }, callback);
     }
 }
 
@@ -37,11 +57,11 @@ pub struct Team {
     pub name: String,
     id: i64,
     node_id: String,
-    slug: String,
+    pub slug: String,
     description: Option<String>,
     privacy: String,
     url: String,
-    html_url: String,
+    pub html_url: String,
     members_url: String,
     repositories_url: String,
     permission: String,
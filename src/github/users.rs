@@ -0,0 +1,99 @@
+use serde::{Deserialize, Serialize};
+
+use crate::github::github_client::{GitHubApi, Users};
+
+impl Users for GitHubApi {
+    fn authenticated_user(
+        &self,
+        token: &mut String,
+        callback: impl 'static + Send + FnOnce(Result<Identity, String>),
+    ) {
+        let url = format!("{}/user", self.base_url);
+
+        let request = ehttp::Request {
+            headers: ehttp::headers(&[
+                ("Accept", "application/vnd.github+json"),
+                ("User-Agent", "rust web-api-client demo"),
+                ("Authorization", format!("Bearer {}", token.trim()).as_str()),
+            ]),
+            ..ehttp::Request::get(&url)
+        };
+
+        self.cassette.fetch(request, move |result: ehttp::Result<ehttp::Response>| {
+            callback(match result {
+                Ok(res) if res.ok => serde_json::from_slice::<AuthenticatedUser>(&res.bytes)
+                    .map(|user| Identity {
+                        user,
+                        scopes: res.headers.get("x-oauth-scopes").map(|scopes| parse_scopes(scopes)),
+                    })
+                    .map_err(|e| format!("error: {:?} when parsing user with content {:?}", e, res)),
+                Ok(res) => Err(serde_json::from_slice::<ErrorMessage>(&res.bytes)
+                    .map_or_else(|_| format!("{} {}", res.status, res.status_text), |e| e.message)),
+                Err(e) => Err(format!("Error {:?} from {:?}", e, &url)),
+            });
+        });
+    }
+
+    fn avatar(
+        &self,
+        url: &str,
+        callback: impl 'static + Send + FnOnce(Vec<u8>),
+    ) {
+        let url = url.to_string();
+        self.cassette.fetch(ehttp::Request::get(&url), move |result: ehttp::Result<ehttp::Response>| {
+            match result {
+                Ok(res) if res.ok => callback(res.bytes),
+                Ok(res) => eprintln!("error: {} {} when fetching avatar from {:?}", res.status, res.status_text, &url),
+                Err(e) => eprintln!("Error {:?} from {:?}", e, &url)
+            }
+        });
+    }
+}
+
+/// Splits the `x-oauth-scopes` header, e.g. "read:org, repo, workflow".
+fn parse_scopes(header: &str) -> Vec<String> {
+    header.split(',')
+        .map(|scope| scope.trim().to_string())
+        .filter(|scope| !scope.is_empty())
+        .collect()
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct ErrorMessage {
+    message: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AuthenticatedUser {
+    pub login: String,
+    pub name: Option<String>,
+    pub avatar_url: String,
+    pub html_url: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct Identity {
+    pub user: AuthenticatedUser,
+    /// Scopes of a classic token or OAuth app token. Fine-grained tokens have permissions instead and
+    /// don't report any.
+    pub scopes: Option<Vec<String>>,
+}
+
+impl Identity {
+    /// The scopes among `required` that are neither granted nor implied by a granted scope.
+    pub fn missing_scopes<'a>(&self, required: &[&'a str]) -> Vec<&'a str> {
+        let Some(scopes) = &self.scopes else { return vec![] };
+
+        required.iter()
+            .filter(|required| !scopes.iter().any(|granted| implies(granted, required)))
+            .copied()
+            .collect()
+    }
+}
+
+fn implies(granted: &str, required: &str) -> bool {
+    granted == required || match required {
+        "read:org" => granted == "write:org" || granted == "admin:org",
+        _ => false,
+    }
+}
@@ -1,36 +1,29 @@
 use serde::{Deserialize, Serialize};
 
-use crate::github::github_client::{GitHubApi, Workflows};
+use crate::github::github_client::{spawn_then, GitHubApi, Workflows};
+
+impl GitHubApi {
+    pub async fn fetch_workflows(&self, token: &str, repo: &str) -> Result<Vec<Workflow>, String> {
+        let url = format!("{}/repos/{}/{}/actions/workflows", self.base_url, self.org, repo);
+
+        match self.fetch_async(self.get(token, &url)).await {
+            Ok(res) => serde_json::from_slice::<WorkflowsResponse>(&res.bytes)
+                .map(|workflows| workflows.workflows)
+                .map_err(|e| format!("error: {:?} when parsing workflows with content {:?}", e, res)),
+            Err(e) => Err(format!("Error {:?} from {:?}", e, &url)),
+        }
+    }
+}
 
 impl Workflows for GitHubApi {
     fn workflows(
         &self,
         token: &mut String,
-        repo: &String,
+        repo: &str,
         callback: impl 'static + Send + FnOnce(Vec<Workflow>),
     ) {
-        let url = format!("https://api.github.com/repos/navikt/{}/actions/workflows", repo);
-
-        let request = ehttp::Request {
-            headers: ehttp::headers(&[
-                ("Accept", "application/vnd.github+json"),
-                ("User-Agent", "rust web-api-client demo"),
-                ("Authorization", format!("Bearer {}", token.trim().to_string()).as_str()),
-            ]),
-            ..ehttp::Request::get(&url)
-        };
-
-        ehttp::fetch(request, move |result: ehttp::Result<ehttp::Response>| {
-            match result {
-                Ok(res) => {
-                    match serde_json::from_slice::<WorkflowsResponse>(&res.bytes) {
-                        Ok(workflows) => callback(workflows.workflows),
-                        Err(e) => println!("error: {:?} when parsing runs with content {:?}", e, res)
-                    }
-                }
-                Err(e) => println!("Error {:?} from {:?}", e, &url)
-            }
-        });
+        let (github, token, repo) = (self.clone(), token.clone(), repo.to_string());
+        spawn_then(async move { github.fetch_workflows(&token, &repo).await }, callback);
     }
 }
 
@@ -0,0 +1,128 @@
+//! Prints the status of the repositories without starting the GUI, for use in scripts and cron jobs.
+
+use std::time::Duration;
+
+use serde::Serialize;
+
+use crate::analysis::status::latest_default_branch_runs;
+use crate::cli::Args;
+use crate::github::blocking;
+use crate::github::github_client::GitHubApi;
+use crate::notifications::Notifications;
+
+/// How long to wait for GitHub to answer at all before giving up.
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+const USAGE: &str = "\
+Usage: aap_status --headless [--json] [--token <token>] [--repo <name>]... [--config <file>]
+                             [--app-id <id> --private-key <pem file> [--installation-id <id>]]
+                             [--record <file> | --replay <file>]
+
+Prints open pull requests and the latest workflow runs on the default branch.
+The token defaults to the GITHUB_TOKEN environment variable, or authenticate as a GitHub App.
+The repositories default to those in the configuration exported from the GUI, --config or
+aap_status.toml in the working directory. Workflows muted in its notifications are listed but
+don't count as failing.
+Errors are printed to stderr, so --json output can be piped.
+--record saves the responses from GitHub to a file, which --replay answers from without network.
+
+Exit codes: 0 all green, 1 a workflow on the default branch is failing, 2 error.";
+
+#[derive(Serialize)]
+struct RepoStatus {
+    repo: String,
+    open_pull_requests: Option<usize>,
+    workflows: Option<Vec<WorkflowStatus>>,
+}
+
+#[derive(Serialize)]
+struct WorkflowStatus {
+    name: String,
+    conclusion: String,
+    url: String,
+    failing: bool,
+    muted: bool,
+}
+
+/// Runs the headless mode with the command line arguments following `--headless`, returning the exit code.
+pub fn run(args: &[String]) -> i32 {
+    let args = match Args::parse(args) {
+        Ok(args) => args,
+        Err(e) => { eprintln!("{}\n\n{}", e, USAGE); return 2; }
+    };
+
+    if args.help {
+        println!("{}", USAGE);
+        return 0;
+    }
+
+    let token = match args.current_token() {
+        Ok(token) => token,
+        Err(e) => { eprintln!("{}", e); return 2; }
+    };
+
+    let muted = args.config.as_ref().map(|config| config.notifications());
+    let statuses = fetch(&args.github(), &token, &args.repos, muted.unwrap_or(&Notifications::default()));
+    args.save_recording();
+
+    if args.json {
+        match serde_json::to_string_pretty(&statuses) {
+            Ok(output) => println!("{}", output),
+            Err(e) => { eprintln!("error: {:?} when serializing status", e); return 2; }
+        }
+    } else {
+        print_table(&statuses);
+    }
+
+    let incomplete = statuses.iter().any(|status| status.open_pull_requests.is_none() || status.workflows.is_none());
+    let failing = statuses.iter()
+        .flat_map(|status| status.workflows.iter().flatten())
+        .any(|workflow| workflow.failing && !workflow.muted);
+
+    match (incomplete, failing) {
+        (true, _) => 2,
+        (false, true) => 1,
+        (false, false) => 0,
+    }
+}
+
+fn fetch(github: &GitHubApi, token: &str, repos: &[String], notifications: &Notifications) -> Vec<RepoStatus> {
+    let snapshot = blocking::fetch(github, token, repos, TIMEOUT);
+    snapshot.errors.iter().for_each(|e| eprintln!("{}", e));
+
+    repos.iter().map(|repo| RepoStatus {
+        repo: repo.clone(),
+        open_pull_requests: snapshot.pulls.get(repo).map(Vec::len),
+        workflows: snapshot.runs.get(repo).map(|runs| {
+            latest_default_branch_runs(runs, snapshot.default_branches.get(repo).map(String::as_str)).into_iter().map(|(name, run)| WorkflowStatus {
+                conclusion: run.conclusion.clone().unwrap_or_default(),
+                url: run.html_url.clone(),
+                failing: run.is_failure(),
+                muted: notifications.is_muted(repo, &name),
+                name,
+            }).collect()
+        }),
+    }).collect()
+}
+
+fn print_table(statuses: &[RepoStatus]) {
+    let width = statuses.iter()
+        .flat_map(|status| status.workflows.iter().flatten())
+        .map(|workflow| workflow.name.chars().count())
+        .max()
+        .unwrap_or(0);
+
+    for status in statuses {
+        let pulls = status.open_pull_requests.map_or(String::from("failed to fetch"), |count| format!("{}", count));
+        println!("{} (open pull requests: {})", status.repo, pulls);
+
+        match &status.workflows {
+            Some(workflows) => workflows.iter().for_each(|workflow| {
+                let mark = if workflow.failing { "✗" } else { "✓" };
+                let muted = if workflow.muted { "  (muted)" } else { "" };
+                println!("  {} {:width$}  {}{}", mark, workflow.name, workflow.conclusion, muted, width = width);
+            }),
+            None => println!("  failed to fetch workflow runs"),
+        }
+    }
+}
@@ -0,0 +1,153 @@
+use std::cell::RefCell;
+use std::sync::{Arc, Mutex};
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use wasm_bindgen::closure::Closure;
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::{IdbDatabase, IdbOpenDbRequest, IdbRequest, IdbTransactionMode};
+
+use crate::github::pulls::PullRequest;
+use crate::github::runs::WorkflowRun;
+use crate::history::Records;
+
+const DATABASE_NAME: &str = "aap_status_history";
+const RUNS: &str = "runs";
+const PULLS: &str = "pulls";
+
+thread_local! {
+    /// The database can't be sent to the threads fetch callbacks might run on, but the browser only
+    /// has the one thread anyway.
+    static DATABASE: RefCell<Option<IdbDatabase>> = const { RefCell::new(None) };
+    /// Records added before the database has been opened, as (object store, key, value).
+    static PENDING: RefCell<Vec<(&'static str, String, String)>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Records as JSON in IndexedDB, keyed by repository and id.
+#[derive(Clone, Default)]
+pub struct Store;
+
+impl Store {
+    pub fn open(records: &Arc<Mutex<Records>>) -> Self {
+        if let Err(e) = open(records.clone()) {
+            eprintln!("error: {:?} when opening history database", e);
+        }
+        Self
+    }
+
+    pub fn put_runs(&self, repo: &str, runs: &[WorkflowRun]) {
+        put(RUNS, runs.iter().map(|run| (format!("{}#{}", repo, run.id), value(repo, run))));
+    }
+
+    pub fn put_pulls(&self, repo: &str, pulls: &[PullRequest]) {
+        put(PULLS, pulls.iter().map(|pr| (format!("{}#{}", repo, pr.number), value(repo, pr))));
+    }
+}
+
+fn value<T: Serialize>(repo: &str, record: &T) -> String {
+    serde_json::to_string(&(repo, record)).unwrap_or_default()
+}
+
+fn open(records: Arc<Mutex<Records>>) -> Result<(), JsValue> {
+    let factory = web_sys::window()
+        .and_then(|window| window.indexed_db().ok().flatten())
+        .ok_or_else(|| JsValue::from_str("IndexedDB is not available"))?;
+    let request = factory.open_with_u32(DATABASE_NAME, 1)?;
+
+    let upgrade = Closure::once(move |event: web_sys::Event| {
+        let database = event.target()
+            .and_then(|target| target.dyn_into::<IdbOpenDbRequest>().ok())
+            .and_then(|request| request.result().ok())
+            .and_then(|result| result.dyn_into::<IdbDatabase>().ok());
+
+        if let Some(database) = database {
+            for name in [RUNS, PULLS] {
+                if let Err(e) = database.create_object_store(name) {
+                    eprintln!("error: {:?} when creating {}", e, name);
+                }
+            }
+        }
+    });
+    request.set_onupgradeneeded(Some(upgrade.as_ref().unchecked_ref()));
+    upgrade.forget();
+
+    let success = Closure::once(move |event: web_sys::Event| {
+        let database = event.target()
+            .and_then(|target| target.dyn_into::<IdbOpenDbRequest>().ok())
+            .and_then(|request| request.result().ok())
+            .and_then(|result| result.dyn_into::<IdbDatabase>().ok());
+
+        let Some(database) = database else { return };
+        load::<WorkflowRun>(&database, RUNS, records.clone(), |records, repo, run| {
+            records.runs.entry(repo).or_default().insert(run.id, run);
+        });
+        load::<PullRequest>(&database, PULLS, records, |records, repo, pr| {
+            records.pulls.entry(repo).or_default().insert(pr.number, pr);
+        });
+
+        DATABASE.with(|db| *db.borrow_mut() = Some(database));
+        for (name, key, value) in PENDING.with(|pending| pending.take()) {
+            put(name, std::iter::once((key, value)));
+        }
+    });
+    request.set_onsuccess(Some(success.as_ref().unchecked_ref()));
+    success.forget();
+
+    Ok(())
+}
+
+/// Reads every record of an object store. Records that no longer parse are skipped.
+fn load<T: DeserializeOwned + 'static>(
+    database: &IdbDatabase,
+    name: &str,
+    records: Arc<Mutex<Records>>,
+    insert: impl 'static + Fn(&mut Records, String, T),
+) {
+    let request = database.transaction_with_str(name)
+        .and_then(|transaction| transaction.object_store(name))
+        .and_then(|store| store.get_all());
+
+    let request = match request {
+        Ok(request) => request,
+        Err(e) => return eprintln!("error: {:?} when reading {}", e, name),
+    };
+
+    let success = Closure::once(move |event: web_sys::Event| {
+        let Some(values) = event.target()
+            .and_then(|target| target.dyn_into::<IdbRequest>().ok())
+            .and_then(|request| request.result().ok())
+            .and_then(|result| result.dyn_into::<js_sys::Array>().ok()) else { return };
+
+        let mut records = records.lock().unwrap();
+        for value in values.iter().filter_map(|value| value.as_string()) {
+            if let Ok((repo, record)) = serde_json::from_str::<(String, T)>(&value) {
+                insert(&mut records, repo, record);
+            }
+        }
+    });
+    request.set_onsuccess(Some(success.as_ref().unchecked_ref()));
+    success.forget();
+}
+
+fn put(name: &'static str, values: impl Iterator<Item = (String, String)>) {
+    DATABASE.with(|database| {
+        let database = database.borrow();
+        let Some(database) = database.as_ref() else {
+            PENDING.with(|pending| pending.borrow_mut().extend(values.map(|(key, value)| (name, key, value))));
+            return;
+        };
+
+        let result = database.transaction_with_str_and_mode(name, IdbTransactionMode::Readwrite)
+            .and_then(|transaction| transaction.object_store(name))
+            .and_then(|store| {
+                for (key, value) in values {
+                    store.put_with_key(&JsValue::from_str(&value), &JsValue::from_str(&key))?;
+                }
+                Ok(())
+            });
+
+        if let Err(e) = result {
+            eprintln!("error: {:?} when storing history", e);
+        }
+    });
+}
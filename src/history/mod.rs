@@ -0,0 +1,230 @@
+//! Every workflow run and pull request seen, kept across refreshes in SQLite (native) or
+//! IndexedDB (web), so charts and analytics can cover months of history without fetching
+//! it again on every refresh.
+
+#[cfg(target_arch = "wasm32")]
+mod indexed_db;
+#[cfg(not(target_arch = "wasm32"))]
+mod sqlite;
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+use chrono::{DateTime, Duration, SecondsFormat, Utc};
+
+use crate::github::github_client::{GitHubApi, Pulls, Runs};
+use crate::github::pulls::PullRequest;
+use crate::github::runs::{WorkflowRun, WorkflowRuns};
+
+#[cfg(target_arch = "wasm32")]
+use indexed_db::Store;
+#[cfg(not(target_arch = "wasm32"))]
+use sqlite::Store;
+
+/// How far back the first sync of a repository goes.
+const FIRST_SYNC_DAYS: i64 = 180;
+/// GitHub stops returning results after 1000, i.e. 10 pages.
+const MAX_PAGES: i32 = 10;
+
+/// The stored records, by repository as `org/repo`.
+#[derive(Default)]
+pub struct Records {
+    pub runs: BTreeMap<String, BTreeMap<i64, WorkflowRun>>,
+    pub pulls: BTreeMap<String, BTreeMap<i32, PullRequest>>,
+}
+
+#[derive(Clone, Default)]
+pub struct History {
+    records: Arc<Mutex<Records>>,
+    store: Store,
+}
+
+impl History {
+    /// Opens the database and loads what is in it. Without a database the history only lasts
+    /// until the app is closed.
+    pub fn open() -> Self {
+        let records = Arc::new(Mutex::new(Records::default()));
+        let store = Store::open(&records);
+        Self { records, store }
+    }
+
+    /// Runs of a workflow, identified by its id or file name.
+    pub fn workflow_runs(&self, repo: &str, workflow: &str) -> BTreeMap<i64, WorkflowRun> {
+        self.records.lock().unwrap().runs.get(repo)
+            .map(|runs| runs.iter()
+                .filter(|(_, run)| run.is_workflow(workflow))
+                .map(|(id, run)| (*id, run.clone()))
+                .collect())
+            .unwrap_or_default()
+    }
+
+    pub fn pulls(&self, repo: &str) -> BTreeMap<i32, PullRequest> {
+        self.records.lock().unwrap().pulls.get(repo).cloned().unwrap_or_default()
+    }
+
+    pub fn add_runs(&self, repo: &str, runs: &[WorkflowRun]) {
+        if runs.is_empty() {
+            return;
+        }
+
+        self.records.lock().unwrap().runs.entry(repo.to_string()).or_default()
+            .extend(runs.iter().map(|run| (run.id, run.clone())));
+        self.store.put_runs(repo, runs);
+    }
+
+    pub fn add_pulls(&self, repo: &str, pulls: &[PullRequest]) {
+        if pulls.is_empty() {
+            return;
+        }
+
+        self.records.lock().unwrap().pulls.entry(repo.to_string()).or_default()
+            .extend(pulls.iter().map(|pr| (pr.number, pr.clone())));
+        self.store.put_pulls(repo, pulls);
+    }
+
+    /// Fetches the runs of a workflow created since the oldest one stored that wasn't completed
+    /// yet, so it is stored again once it is, or else since the newest one stored.
+    pub fn sync_workflow_runs(&self, github: &GitHubApi, token: &str, repo: &str, workflow: &str) {
+        let target = SyncTarget { github: github.clone(), token: token.to_string(), repo: repo.to_string() };
+        let since = sync_since(&self.workflow_runs(&target.key(), workflow));
+
+        let created = format!(">={}", since.to_rfc3339_opts(SecondsFormat::Secs, true));
+        self.fetch_workflow_runs(target, workflow.to_string(), created, 1);
+    }
+
+    fn fetch_workflow_runs(&self, target: SyncTarget, workflow: String, created: String, page: i32) {
+        let history = self.clone();
+        let next = target.clone();
+        let (_workflow, _created) = (workflow.clone(), created.clone());
+
+        target.github.workflow_runs(&mut target.token.clone(), &target.repo, &workflow, Some(&created), page, move |response: WorkflowRuns| {
+            history.add_runs(&next.key(), &response.workflow_runs);
+
+            if response.workflow_runs.len() == 100 && page < MAX_PAGES {
+                history.fetch_workflow_runs(next, _workflow, _created, page + 1);
+            }
+        });
+    }
+
+    /// Fetches the pull requests updated since the newest update stored.
+    pub fn sync_pulls(&self, github: &GitHubApi, token: &str, repo: &str) {
+        let target = SyncTarget { github: github.clone(), token: token.to_string(), repo: repo.to_string() };
+        let since = self.pulls(&target.key()).values()
+            .filter_map(|pr| pr.updated())
+            .max()
+            .unwrap_or_else(|| Utc::now() - Duration::days(FIRST_SYNC_DAYS));
+
+        self.fetch_pulls(target, since, 1);
+    }
+
+    fn fetch_pulls(&self, target: SyncTarget, since: DateTime<Utc>, page: i32) {
+        let history = self.clone();
+        let next = target.clone();
+
+        target.github.pull_request_history(&mut target.token.clone(), &target.repo, page, move |response: Vec<PullRequest>| {
+            history.add_pulls(&next.key(), &response);
+
+            // Sorted by when they were last updated, so the next pages have been seen before.
+            let reached_known = response.last().and_then(|pr| pr.updated()).is_none_or(|updated| updated < since);
+            if response.len() == 100 && !reached_known && page < MAX_PAGES {
+                history.fetch_pulls(next, since, page + 1);
+            }
+        });
+    }
+}
+
+/// When the runs to fetch were created after, see [`History::sync_workflow_runs`].
+fn sync_since(runs: &BTreeMap<i64, WorkflowRun>) -> DateTime<Utc> {
+    let pending = runs.values()
+        .filter(|run| run.status.as_deref() != Some("completed"))
+        .filter_map(|run| run.created())
+        .min();
+
+    pending
+        .or_else(|| runs.values().filter_map(|run| run.created()).max())
+        .unwrap_or_else(|| Utc::now() - Duration::days(FIRST_SYNC_DAYS))
+}
+
+/// The repository being synced and how to fetch its next page.
+#[derive(Clone)]
+struct SyncTarget {
+    github: GitHubApi,
+    token: String,
+    repo: String,
+}
+
+impl SyncTarget {
+    fn key(&self) -> String {
+        key(&self.github, &self.repo)
+    }
+}
+
+/// Records are stored by `org/repo`, as profiles may watch repositories of different organisations.
+pub fn key(github: &GitHubApi, repo: &str) -> String {
+    format!("{}/{}", github.org, repo)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::github::runs::parse_timestamp;
+
+    fn run(id: i64, status: &str, created_at: &str) -> (i64, WorkflowRun) {
+        let run = serde_json::from_value(serde_json::json!({
+            "id": id,
+            "name": "Deploy",
+            "head_sha": "c1",
+            "head_branch": "main",
+            "path": ".github/workflows/deploy.yml",
+            "run_number": id,
+            "run_attempt": 1,
+            "event": "push",
+            "status": status,
+            "conclusion": (status == "completed").then_some("success"),
+            "workflow_id": 9,
+            "url": "",
+            "html_url": "",
+            "pull_requests": [],
+            "created_at": created_at,
+            "updated_at": created_at,
+            "jobs_url": "",
+            "logs_url": "",
+            "check_suite_url": "",
+            "artifacts_url": "",
+            "cancel_url": "",
+            "rerun_url": "",
+            "workflow_url": "",
+            "display_title": "Deploy",
+        })).expect("run");
+        (id, run)
+    }
+
+    #[test]
+    fn syncs_continue_from_the_newest_run() {
+        let runs = BTreeMap::from([
+            run(1, "completed", "2024-01-01T10:00:00Z"),
+            run(2, "completed", "2024-01-03T10:00:00Z"),
+        ]);
+
+        assert_eq!(Some(sync_since(&runs)), parse_timestamp("2024-01-03T10:00:00Z"));
+    }
+
+    #[test]
+    fn syncs_go_back_to_the_oldest_run_not_completed() {
+        let runs = BTreeMap::from([
+            run(1, "completed", "2024-01-01T10:00:00Z"),
+            run(2, "in_progress", "2024-01-02T10:00:00Z"),
+            run(3, "queued", "2024-01-02T12:00:00Z"),
+            run(4, "completed", "2024-01-03T10:00:00Z"),
+        ]);
+
+        assert_eq!(Some(sync_since(&runs)), parse_timestamp("2024-01-02T10:00:00Z"));
+    }
+
+    #[test]
+    fn first_syncs_go_back_half_a_year() {
+        let since = sync_since(&BTreeMap::new());
+
+        assert!((Utc::now() - Duration::days(FIRST_SYNC_DAYS) - since).num_seconds().abs() < 60);
+    }
+}
@@ -0,0 +1,107 @@
+use std::sync::{Arc, Mutex};
+
+use rusqlite::{params, Connection};
+use serde::de::DeserializeOwned;
+
+use crate::github::pulls::PullRequest;
+use crate::github::runs::WorkflowRun;
+use crate::history::Records;
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS runs (
+        repo TEXT NOT NULL,
+        id INTEGER NOT NULL,
+        json TEXT NOT NULL,
+        PRIMARY KEY (repo, id)
+    );
+    CREATE TABLE IF NOT EXISTS pulls (
+        repo TEXT NOT NULL,
+        number INTEGER NOT NULL,
+        json TEXT NOT NULL,
+        PRIMARY KEY (repo, number)
+    );
+";
+
+/// Records as JSON in `history.sqlite` next to the persisted app state.
+#[derive(Clone, Default)]
+pub struct Store {
+    connection: Option<Arc<Mutex<Connection>>>,
+}
+
+impl Store {
+    pub fn open(records: &Arc<Mutex<Records>>) -> Self {
+        match connect().and_then(|connection| load(&connection, records).map(|_| connection)) {
+            Ok(connection) => Self { connection: Some(Arc::new(Mutex::new(connection))) },
+            Err(e) => {
+                eprintln!("error: {} when opening history database", e);
+                Self::default()
+            }
+        }
+    }
+
+    pub fn put_runs(&self, repo: &str, runs: &[WorkflowRun]) {
+        self.put("INSERT OR REPLACE INTO runs (repo, id, json) VALUES (?1, ?2, ?3)", repo, runs, |run| run.id);
+    }
+
+    pub fn put_pulls(&self, repo: &str, pulls: &[PullRequest]) {
+        self.put("INSERT OR REPLACE INTO pulls (repo, number, json) VALUES (?1, ?2, ?3)", repo, pulls, |pr| pr.number.into());
+    }
+
+    fn put<T: serde::Serialize>(&self, sql: &str, repo: &str, records: &[T], id: impl Fn(&T) -> i64) {
+        let Some(connection) = &self.connection else { return };
+        let mut connection = connection.lock().unwrap();
+
+        let result = connection.transaction().and_then(|transaction| {
+            {
+                let mut statement = transaction.prepare_cached(sql)?;
+                for record in records {
+                    let json = serde_json::to_string(record).unwrap_or_default();
+                    statement.execute(params![repo, id(record), json])?;
+                }
+            }
+            transaction.commit()
+        });
+
+        if let Err(e) = result {
+            eprintln!("error: {} when storing history", e);
+        }
+    }
+}
+
+fn connect() -> Result<Connection, String> {
+    let dir = directories_next::ProjectDirs::from("", "", "AAP Status")
+        .ok_or("no data directory")?
+        .data_dir()
+        .to_path_buf();
+    std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+
+    let connection = Connection::open(dir.join("history.sqlite")).map_err(|e| e.to_string())?;
+    connection.execute_batch(SCHEMA).map_err(|e| e.to_string())?;
+    Ok(connection)
+}
+
+fn load(connection: &Connection, records: &Arc<Mutex<Records>>) -> Result<(), String> {
+    let runs = select::<WorkflowRun>(connection, "SELECT repo, json FROM runs")?;
+    let pulls = select::<PullRequest>(connection, "SELECT repo, json FROM pulls")?;
+
+    let mut records = records.lock().unwrap();
+    for (repo, run) in runs {
+        records.runs.entry(repo).or_default().insert(run.id, run);
+    }
+    for (repo, pr) in pulls {
+        records.pulls.entry(repo).or_default().insert(pr.number, pr);
+    }
+    Ok(())
+}
+
+/// Rows that no longer parse, e.g. after a field was added, are skipped.
+fn select<T: DeserializeOwned>(connection: &Connection, sql: &str) -> Result<Vec<(String, T)>, String> {
+    let mut statement = connection.prepare(sql).map_err(|e| e.to_string())?;
+    let rows = statement
+        .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))
+        .map_err(|e| e.to_string())?
+        .filter_map(|row| row.ok())
+        .filter_map(|(repo, json)| Some((repo, serde_json::from_str(&json).ok()?)))
+        .collect();
+    Ok(rows)
+}
@@ -1,7 +1,26 @@
 #![warn(clippy::all, rust_2018_idioms)]
 
+mod analysis;
 mod app;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod backend;
+#[cfg(not(target_arch = "wasm32"))]
+mod cli;
+mod config;
+mod demo;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod exporter;
+pub mod forge;
 pub mod github;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod headless;
+mod history;
+mod notifications;
+mod offline;
+mod profile;
+mod token_store;
+#[cfg(all(feature = "tui", not(target_arch = "wasm32")))]
+pub mod tui;
 mod ui;
 
 pub use app::TemplateApp;
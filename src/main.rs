@@ -8,6 +8,14 @@ fn main() {
     // Log to stdout (if you run with `RUST_LOG=debug`).
     tracing_subscriber::fmt::init();
 
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    match args.first().map(String::as_str) {
+        Some("--headless") => std::process::exit(aap_status::headless::run(&args[1..])),
+        Some("--metrics") => std::process::exit(aap_status::exporter::run(&args[1..])),
+        Some("--serve") => std::process::exit(aap_status::backend::run(&args[1..])),
+        _ => {}
+    }
+
     let native_options = eframe::NativeOptions::default();
     eframe::run_native(
         "AAP Status",
@@ -0,0 +1,370 @@
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+
+use chrono::{DateTime, Utc};
+use egui::Ui;
+
+use crate::analysis::status::latest_default_branch_runs;
+use crate::forge::ChangeRequest;
+use crate::github::runs::WorkflowRuns;
+
+/// Raises a notification when a watched workflow on the default branch turns red or recovers,
+/// or when someone requests a review from `login`.
+#[derive(serde::Deserialize, serde::Serialize, Default)]
+#[serde(default)]
+pub struct Notifications {
+    enabled: bool,
+    login: String,
+    muted_repos: HashSet<String>,
+    muted_workflows: HashSet<(String, String)>,
+
+    #[serde(skip)]
+    previous: Option<Snapshot>,
+    /// When the data compared last was fetched.
+    #[serde(skip)]
+    checked: Option<DateTime<Utc>>,
+}
+
+/// What has been notified about so far, compared with the data after each refresh.
+#[derive(Default, Clone, PartialEq)]
+struct Snapshot {
+    /// Whether the latest run on the default branch passed, its conclusion and URL, by repository
+    /// and workflow name.
+    conclusions: BTreeMap<(String, String), (bool, String, String)>,
+    /// Pull requests awaiting review from `login` by repository.
+    review_requests: BTreeMap<String, BTreeSet<(i64, String, String)>>,
+}
+
+impl Notifications {
+    /// The rules worth sharing with others, leaving out whose review requests to watch.
+    pub fn shared(&self) -> Self {
+        Self {
+            enabled: self.enabled,
+            login: String::new(),
+            muted_repos: self.muted_repos.clone(),
+            muted_workflows: self.muted_workflows.clone(),
+            previous: None,
+            checked: None,
+        }
+    }
+
+    /// Takes the rules of `shared`, keeping our own login.
+    pub fn apply(&mut self, shared: Notifications) {
+        self.enabled = shared.enabled;
+        self.muted_repos = shared.muted_repos;
+        self.muted_workflows = shared.muted_workflows;
+    }
+
+    pub fn is_muted(&self, repo: &str, workflow: &str) -> bool {
+        self.muted_repos.contains(repo) || self.muted_workflows.contains(&(repo.to_string(), workflow.to_string()))
+    }
+
+    /// Whether data fetched at `fetched` is yet to be compared, see [`Freshness::last_fetched`].
+    ///
+    /// [`Freshness::last_fetched`]: crate::offline::Freshness::last_fetched
+    pub fn is_unchecked(&self, fetched: Option<DateTime<Utc>>) -> bool {
+        self.checked != fetched
+    }
+
+    /// Compare data fetched at `fetched` with the previous and notify about anything new.
+    pub fn check(
+        &mut self,
+        fetched: Option<DateTime<Utc>>,
+        runs: &BTreeMap<String, WorkflowRuns>,
+        default_branches: &BTreeMap<String, String>,
+        pulls: &BTreeMap<String, Vec<ChangeRequest>>,
+    ) {
+        self.checked = fetched;
+        let current = Snapshot::new(runs, default_branches, pulls, self.login.trim(), self.previous.as_ref());
+
+        let Some(previous) = self.previous.replace(current.clone()) else { return };
+        if !self.enabled {
+            return;
+        }
+        self.changes(&previous, &current).iter().for_each(|(title, body)| show(title, body));
+    }
+
+    /// Title and body of a notification for each change from `previous` to `current` not muted.
+    fn changes(&self, previous: &Snapshot, current: &Snapshot) -> Vec<(String, String)> {
+        let mut changes = vec![];
+
+        for ((repo, workflow), (passed, conclusion, url)) in &current.conclusions {
+            if self.is_muted(repo, workflow) {
+                continue;
+            }
+
+            let Some((was_passing, _, _)) = previous.conclusions.get(&(repo.clone(), workflow.clone())) else { continue };
+            match (was_passing, passed) {
+                (true, false) => changes.push((format!("{} failed in {}", workflow, repo), format!("Conclusion: {}\n{}", conclusion, url))),
+                (false, true) => changes.push((format!("{} recovered in {}", workflow, repo), url.clone())),
+                _ => {}
+            }
+        }
+
+        for (repo, requests) in &current.review_requests {
+            if self.muted_repos.contains(repo) {
+                continue;
+            }
+
+            // Repositories without earlier data were just added or fetched for the first time.
+            let Some(seen) = previous.review_requests.get(repo) else { continue };
+            requests.difference(seen).for_each(|(number, title, url)| {
+                changes.push((format!("Review requested in {} #{}", repo, number), format!("{}\n{}", title, url)));
+            });
+        }
+
+        changes
+    }
+
+    pub fn settings_ui(&mut self, ui: &mut Ui, runs: &BTreeMap<String, WorkflowRuns>, repositories: &HashSet<String>) {
+        ui.horizontal_wrapped(|ui| {
+            if ui.checkbox(&mut self.enabled, "Enable notifications").changed() && self.enabled {
+                request_permission();
+            }
+        });
+
+        ui.horizontal_wrapped(|ui| {
+            ui.label("Notify about review requests for GitHub user:");
+            ui.text_edit_singleline(&mut self.login);
+        });
+
+        ui.separator();
+        ui.label("Watched repositories and workflows on the default branch:");
+
+        let mut repos = repositories.iter().collect::<Vec<_>>();
+        repos.sort();
+
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            repos.into_iter().for_each(|repo| {
+                let mut watched = !self.muted_repos.contains(repo);
+                if ui.checkbox(&mut watched, repo).changed() {
+                    toggle(&mut self.muted_repos, repo.clone(), !watched);
+                }
+
+                let workflows = runs.get(repo)
+                    .map(|runs| runs.workflow_runs.iter().filter_map(|run| run.name.clone()).collect::<BTreeSet<_>>())
+                    .unwrap_or_default();
+
+                ui.indent(repo, |ui| {
+                    ui.add_enabled_ui(watched, |ui| {
+                        workflows.into_iter().for_each(|workflow| {
+                            let key = (repo.clone(), workflow.clone());
+                            let mut watched = !self.muted_workflows.contains(&key);
+                            if ui.checkbox(&mut watched, &workflow).changed() {
+                                toggle(&mut self.muted_workflows, key, !watched);
+                            }
+                        });
+                    });
+                });
+            });
+        });
+    }
+}
+
+impl Snapshot {
+    fn new(
+        runs: &BTreeMap<String, WorkflowRuns>,
+        default_branches: &BTreeMap<String, String>,
+        pulls: &BTreeMap<String, Vec<ChangeRequest>>,
+        login: &str,
+        previous: Option<&Snapshot>,
+    ) -> Self {
+        let mut conclusions: BTreeMap<_, _> = runs.iter().flat_map(|(repo, runs)| {
+            latest_default_branch_runs(runs, default_branches.get(repo).map(String::as_str)).into_iter()
+                .filter(|(_, run)| run.is_success() || run.is_failure())
+                .map(|(workflow, run)| {
+                    ((repo.clone(), workflow), (run.is_success(), run.conclusion.clone().unwrap_or_default(), run.html_url.clone()))
+                })
+                .collect::<Vec<_>>()
+        }).collect();
+
+        // Cancelled and skipped runs neither pass nor fail, so compare with what came before them.
+        if let Some(previous) = previous {
+            let carried = previous.conclusions.iter()
+                .filter(|(key, _)| !conclusions.contains_key(*key) && runs.contains_key(&key.0))
+                .map(|(key, was)| (key.clone(), was.clone()))
+                .collect::<Vec<_>>();
+            conclusions.extend(carried);
+        }
+
+        let review_requests = pulls.iter().map(|(repo, prs)| {
+            let requested = prs.iter()
+                .filter(|pr| !login.is_empty() && pr.reviewers.iter().any(|reviewer| reviewer.eq_ignore_ascii_case(login)))
+                .map(|pr| (pr.number, pr.title.clone(), pr.url.clone()))
+                .collect();
+            (repo.clone(), requested)
+        }).collect();
+
+        Self { conclusions, review_requests }
+    }
+}
+
+fn toggle<T: std::hash::Hash + Eq>(set: &mut HashSet<T>, value: T, insert: bool) {
+    if insert {
+        set.insert(value);
+    } else {
+        set.remove(&value);
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn request_permission() {}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn show(title: &str, body: &str) {
+    let result = notify_rust::Notification::new()
+        .appname("AAP Status")
+        .summary(title)
+        .body(body)
+        .show();
+
+    if let Err(e) = result {
+        eprintln!("error: {:?} when showing notification {:?}", e, title);
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn request_permission() {
+    if let Err(e) = web_sys::Notification::request_permission() {
+        eprintln!("error: {:?} when requesting notification permission", e);
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn show(title: &str, body: &str) {
+    if web_sys::Notification::permission() != web_sys::NotificationPermission::Granted {
+        return;
+    }
+
+    let mut options = web_sys::NotificationOptions::new();
+    options.body(body);
+
+    if let Err(e) = web_sys::Notification::new_with_options(title, &options) {
+        eprintln!("error: {:?} when showing notification {:?}", e, title);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(workflow: &str, conclusion: &str, created_at: &str) -> serde_json::Value {
+        serde_json::json!({
+            "id": 1,
+            "name": workflow,
+            "head_sha": "c1",
+            "head_branch": "main",
+            "path": ".github/workflows/build.yml",
+            "run_number": 1,
+            "run_attempt": 1,
+            "event": "push",
+            "status": "completed",
+            "conclusion": conclusion,
+            "workflow_id": 7,
+            "url": "",
+            "html_url": format!("https://github.com/navikt/aap-api/actions/runs/{}", created_at),
+            "pull_requests": [],
+            "created_at": created_at,
+            "updated_at": created_at,
+            "jobs_url": "",
+            "logs_url": "",
+            "check_suite_url": "",
+            "artifacts_url": "",
+            "cancel_url": "",
+            "rerun_url": "",
+            "workflow_url": "",
+            "display_title": workflow,
+        })
+    }
+
+    fn runs(runs: Vec<serde_json::Value>) -> BTreeMap<String, WorkflowRuns> {
+        let runs = serde_json::from_value(serde_json::json!({ "total_count": runs.len(), "workflow_runs": runs })).expect("runs");
+        BTreeMap::from([(String::from("aap-api"), runs)])
+    }
+
+    fn review_requests(numbers: &[i64]) -> BTreeMap<String, Vec<ChangeRequest>> {
+        let pulls = numbers.iter().map(|number| ChangeRequest {
+            number: *number,
+            title: format!("Pull request {}", number),
+            url: String::new(),
+            author: String::from("alice"),
+            created_at: None,
+            updated_at: None,
+            reviewers: vec![String::from("Bob")],
+            labels: vec![],
+            review_decision: None,
+            checks: None,
+        }).collect();
+        BTreeMap::from([(String::from("aap-api"), pulls)])
+    }
+
+    /// The runs and open pull requests after a refresh.
+    type Step = (BTreeMap<String, WorkflowRuns>, BTreeMap<String, Vec<ChangeRequest>>);
+
+    /// Titles of the notifications for each step after the first, snapshotted the way `check` does.
+    fn titles(notifications: &Notifications, steps: Vec<Step>) -> Vec<Vec<String>> {
+        let mut previous: Option<Snapshot> = None;
+        steps.iter().filter_map(|(runs, pulls)| {
+            let current = Snapshot::new(runs, &BTreeMap::new(), pulls, "bob", previous.as_ref());
+            let changes = previous.replace(current.clone()).map(|previous| notifications.changes(&previous, &current));
+            changes.map(|changes| changes.into_iter().map(|(title, _)| title).collect())
+        }).collect()
+    }
+
+    #[test]
+    fn failing_and_recovering_are_notified() {
+        let titles = titles(&Notifications::default(), vec![
+            (runs(vec![run("Build", "success", "2024-01-01T10:00:00Z")]), BTreeMap::new()),
+            (runs(vec![run("Build", "failure", "2024-01-01T11:00:00Z")]), BTreeMap::new()),
+            (runs(vec![run("Build", "failure", "2024-01-01T12:00:00Z")]), BTreeMap::new()),
+            (runs(vec![run("Build", "success", "2024-01-01T13:00:00Z")]), BTreeMap::new()),
+        ]);
+
+        assert_eq!(titles, vec![
+            vec![String::from("Build failed in aap-api")],
+            vec![],
+            vec![String::from("Build recovered in aap-api")],
+        ]);
+    }
+
+    #[test]
+    fn cancelled_and_skipped_runs_carry_the_conclusion_before_them_forward() {
+        let titles = titles(&Notifications::default(), vec![
+            (runs(vec![run("Build", "failure", "2024-01-01T10:00:00Z")]), BTreeMap::new()),
+            (runs(vec![run("Build", "cancelled", "2024-01-01T11:00:00Z")]), BTreeMap::new()),
+            (runs(vec![run("Build", "skipped", "2024-01-01T12:00:00Z")]), BTreeMap::new()),
+            (runs(vec![run("Build", "success", "2024-01-01T13:00:00Z")]), BTreeMap::new()),
+        ]);
+
+        assert_eq!(titles, vec![vec![], vec![], vec![String::from("Build recovered in aap-api")]]);
+    }
+
+    #[test]
+    fn muted_workflows_and_repositories_are_not_notified() {
+        let steps = || vec![
+            (runs(vec![run("Build", "success", "2024-01-01T10:00:00Z"), run("Deploy", "success", "2024-01-01T10:00:00Z")]), review_requests(&[])),
+            (runs(vec![run("Build", "failure", "2024-01-01T11:00:00Z"), run("Deploy", "failure", "2024-01-01T11:00:00Z")]), review_requests(&[1])),
+        ];
+        let mut notifications = Notifications::default();
+
+        notifications.muted_workflows.insert((String::from("aap-api"), String::from("Deploy")));
+        assert_eq!(titles(&notifications, steps()), vec![vec![
+            String::from("Build failed in aap-api"),
+            String::from("Review requested in aap-api #1"),
+        ]]);
+
+        notifications.muted_repos.insert(String::from("aap-api"));
+        assert_eq!(titles(&notifications, steps()), vec![Vec::<String>::new()]);
+    }
+
+    #[test]
+    fn only_new_review_requests_are_notified() {
+        let titles = titles(&Notifications::default(), vec![
+            (BTreeMap::new(), review_requests(&[1])),
+            (BTreeMap::new(), review_requests(&[1])),
+            (BTreeMap::new(), review_requests(&[1, 2])),
+            (BTreeMap::new(), review_requests(&[2])),
+        ]);
+
+        assert_eq!(titles, vec![vec![], vec![String::from("Review requested in aap-api #2")], vec![]]);
+    }
+}
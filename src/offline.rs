@@ -0,0 +1,140 @@
+//! Keeps the last fetched data with the rest of the app state, so the app has something to show
+//! when it starts without network.
+//!
+//! The state is saved every 30 seconds, in `localStorage` on the web which holds about 5 MB for
+//! everything. Pull requests and runs are therefore kept trimmed to what the views show, and only
+//! the newest of them.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::{Arc, Mutex};
+
+use chrono::{DateTime, Local, Utc};
+use egui::{Color32, Ui};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::forge::{ChangeRequest, PipelineRun};
+use crate::github::pulls::PullRequest;
+use crate::github::runs::{WorkflowRun, WorkflowRuns};
+
+/// Pull requests, runs or change requests kept of each repository at most.
+const MAX_PER_REPOSITORY: usize = 30;
+
+/// Pull requests, runs or change requests kept of all repositories of a profile at most.
+const MAX_TOTAL: usize = 300;
+
+/// The kinds of data kept offline.
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Data {
+    Pulls,
+    Runs,
+    Workflows,
+    Teams,
+}
+
+/// When each kind of data was last fetched, and whether that happened since the app started.
+#[derive(Deserialize, Serialize, Default)]
+#[serde(default)]
+pub struct Freshness {
+    fetched: BTreeMap<Data, DateTime<Utc>>,
+
+    #[serde(skip)]
+    refreshed: BTreeSet<Data>,
+}
+
+impl Freshness {
+    /// Called when a response has been received.
+    pub fn record(&mut self, data: Data) {
+        self.fetched.insert(data, Utc::now());
+        self.refreshed.insert(data);
+    }
+
+    /// When any data was last fetched.
+    pub fn last_fetched(&self) -> Option<DateTime<Utc>> {
+        self.fetched.values().max().copied()
+    }
+
+    /// When data restored from a previous session was fetched, until it is fetched again.
+    pub fn stale_since(&self, data: Data) -> Option<DateTime<Utc>> {
+        match self.refreshed.contains(&data) {
+            true => None,
+            false => self.fetched.get(&data).copied(),
+        }
+    }
+
+    pub fn banner_ui(&self, ui: &mut Ui, data: Data) {
+        if let Some(since) = self.stale_since(data) {
+            ui.colored_label(
+                Color32::from_rgb(255, 200, 100),
+                format!("⚠ Stale since {}, press Refresh to update", since.with_timezone(&Local).format("%Y-%m-%d %H:%M")),
+            );
+        }
+    }
+}
+
+/// (De)serializes data shared with fetch callbacks as the data itself,
+/// for fields with `#[serde(with = "offline::shared")]`.
+pub mod shared {
+    use super::*;
+
+    pub fn serialize<T: Serialize, S: Serializer>(value: &Arc<Mutex<T>>, serializer: S) -> Result<S::Ok, S::Error> {
+        value.lock().unwrap().serialize(serializer)
+    }
+
+    pub fn deserialize<'de, T: Deserialize<'de>, D: Deserializer<'de>>(deserializer: D) -> Result<Arc<Mutex<T>>, D::Error> {
+        T::deserialize(deserializer).map(|value| Arc::new(Mutex::new(value)))
+    }
+}
+
+/// Data which is kept offline trimmed, see [`trimmed`].
+pub trait Trim {
+    fn trimmed(&self) -> Self;
+}
+
+/// Like [`shared`], keeping only what [`Trim`] leaves of the data.
+pub mod trimmed {
+    use super::*;
+
+    pub fn serialize<T: Serialize + Trim, S: Serializer>(value: &Arc<Mutex<T>>, serializer: S) -> Result<S::Ok, S::Error> {
+        let trimmed = value.lock().unwrap().trimmed();
+        trimmed.serialize(serializer)
+    }
+
+    pub use super::shared::deserialize;
+}
+
+/// The first of each repository's list, newest first as fetched, trimmed with `trim`.
+fn capped<T>(lists: &BTreeMap<String, Vec<T>>, trim: impl Fn(&T) -> T) -> BTreeMap<String, Vec<T>> {
+    let mut left = MAX_TOTAL;
+    lists.iter().map(|(repo, list)| {
+        let kept = list.iter().take(MAX_PER_REPOSITORY.min(left)).map(&trim).collect::<Vec<_>>();
+        left -= kept.len();
+        (repo.clone(), kept)
+    }).collect()
+}
+
+impl Trim for BTreeMap<String, Vec<PullRequest>> {
+    fn trimmed(&self) -> Self {
+        capped(self, PullRequest::trimmed)
+    }
+}
+
+impl Trim for BTreeMap<String, WorkflowRuns> {
+    fn trimmed(&self) -> Self {
+        let runs = self.iter().map(|(repo, runs)| (repo.clone(), runs.workflow_runs.clone())).collect();
+        capped(&runs, WorkflowRun::trimmed).into_iter()
+            .map(|(repo, workflow_runs)| (repo, WorkflowRuns { total_count: workflow_runs.len() as i32, workflow_runs }))
+            .collect()
+    }
+}
+
+impl Trim for BTreeMap<String, Vec<ChangeRequest>> {
+    fn trimmed(&self) -> Self {
+        capped(self, ChangeRequest::clone)
+    }
+}
+
+impl Trim for BTreeMap<String, Vec<PipelineRun>> {
+    fn trimmed(&self) -> Self {
+        capped(self, PipelineRun::clone)
+    }
+}
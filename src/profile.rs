@@ -0,0 +1,205 @@
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::sync::{Arc, Mutex};
+
+use ehttp::Response;
+
+use crate::analysis::flaky::AttemptJobs;
+use crate::app::{State, DEFAULT_REPOSITORIES};
+use crate::forge::{ChangeRequest, Forges, Group, PipelineDefinition, PipelineRun};
+use crate::github::github_client::GitHubApi;
+use crate::github::pulls::{PullRequest, Review, User};
+use crate::github::runs::WorkflowRuns;
+use crate::github::teams::Team;
+use crate::github::workflows::Workflow;
+use crate::notifications::Notifications;
+use crate::offline::{self, Freshness};
+use crate::token_store::{TokenStore, PLACEHOLDER};
+use crate::ui::charts::Charts;
+use crate::ui::dora::Dora;
+use crate::ui::identity::TokenCheck;
+use crate::ui::login::Login;
+use crate::ui::reviews::ReviewStats;
+use crate::ui::table::Table;
+
+/// Reviews of each pull request, by repository and pull request number.
+pub type ReviewsByRepo = BTreeMap<String, BTreeMap<i32, Vec<Review>>>;
+
+/// A GitHub account with the repositories watched through it, e.g. one for work and one for
+/// open source. Each profile keeps its own view state and fetched data, with the last fetched
+/// pull requests, runs, workflows and teams persisted for offline use, see [`offline`].
+#[derive(serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct Profile {
+    pub name: String,
+    /// Never persisted with the rest of the state, see [`TokenStore`].
+    #[serde(skip)]
+    pub token: String,
+    pub token_store: TokenStore,
+    pub github: GitHubApi,
+    /// Where the repositories not on GitHub are hosted.
+    pub forges: Forges,
+    pub repositories: HashSet<String>,
+    pub new_repo: String,
+    pub team: Option<Team>,
+
+    pub state: State,
+    pub pr_table: Table,
+    pub run_table: Table,
+    pub flaky_table: Table,
+    pub charts: Charts,
+    pub dora: Dora,
+    pub review_stats: ReviewStats,
+    pub notifications: Notifications,
+
+    #[serde(with = "offline::shared")]
+    pub fetched: Arc<Mutex<Freshness>>,
+
+    #[serde(with = "offline::shared")]
+    pub teams: Arc<Mutex<Vec<Team>>>,
+
+    #[serde(skip)]
+    pub teams_responses: Arc<Mutex<Vec<Response>>>,
+
+    #[serde(with = "offline::trimmed")]
+    pub pulls: Arc<Mutex<BTreeMap<String, Vec<PullRequest>>>>,
+
+    #[serde(with = "offline::shared")]
+    pub workflows: Arc<Mutex<BTreeMap<String, Vec<Workflow>>>>,
+
+    #[serde(with = "offline::trimmed")]
+    pub runs: Arc<Mutex<BTreeMap<String, WorkflowRuns>>>,
+
+    /// Of the repositories on GitHub, where workflows are watched.
+    #[serde(with = "offline::shared")]
+    pub default_branches: Arc<Mutex<BTreeMap<String, String>>>,
+
+    /// Open pull requests of the repositories on GitHub, when fetched with GraphQL rather than into `pulls`.
+    #[serde(with = "offline::trimmed")]
+    pub open_pulls: Arc<Mutex<BTreeMap<String, Vec<ChangeRequest>>>>,
+
+    /// Open change requests of the repositories not on GitHub.
+    #[serde(with = "offline::trimmed")]
+    pub change_requests: Arc<Mutex<BTreeMap<String, Vec<ChangeRequest>>>>,
+
+    /// Latest pipeline runs of the repositories not on GitHub.
+    #[serde(with = "offline::trimmed")]
+    pub pipeline_runs: Arc<Mutex<BTreeMap<String, Vec<PipelineRun>>>>,
+
+    /// Workflows of the repositories not on GitHub, for the Charts view.
+    #[serde(with = "offline::shared")]
+    pub pipeline_definitions: Arc<Mutex<BTreeMap<String, Vec<PipelineDefinition>>>>,
+
+    /// Groups on the forges other than GitHub, by name of the forge.
+    #[serde(with = "offline::shared")]
+    pub groups: Arc<Mutex<BTreeMap<String, Vec<Group>>>>,
+
+    /// Jobs of the attempts failing before a success, for the Flaky view.
+    #[serde(skip)]
+    pub attempt_jobs: Arc<Mutex<AttemptJobs>>,
+
+    #[serde(skip)]
+    pub pull_history: Arc<Mutex<BTreeMap<String, BTreeMap<i32, PullRequest>>>>,
+
+    /// Repositories with more pull requests updated in the Reviews window than fit in the pages fetched.
+    #[serde(skip)]
+    pub truncated_history: Arc<Mutex<BTreeSet<String>>>,
+
+    #[serde(skip)]
+    pub reviews: Arc<Mutex<ReviewsByRepo>>,
+
+    #[serde(skip)]
+    pub team_members: Arc<Mutex<Vec<User>>>,
+
+    #[serde(skip)]
+    pub login: Login,
+
+    #[serde(skip)]
+    pub token_check: TokenCheck,
+}
+
+impl Default for Profile {
+    fn default() -> Self {
+        Self {
+            name: String::from("Default"),
+            token: String::from(PLACEHOLDER),
+            token_store: TokenStore::default(),
+            github: GitHubApi::default(),
+            forges: Forges::default(),
+            repositories: DEFAULT_REPOSITORIES.iter().map(|repo| repo.to_string()).collect(),
+            new_repo: String::from("<repo>"),
+            team: None,
+            state: State::Repositories,
+            pr_table: Table::default(),
+            run_table: Table::default(),
+            flaky_table: Table::default(),
+            charts: Charts::default(),
+            dora: Dora::default(),
+            review_stats: ReviewStats::default(),
+            notifications: Notifications::default(),
+            fetched: Arc::new(Mutex::new(Freshness::default())),
+            teams: Arc::new(Mutex::new(vec![])),
+            teams_responses: Arc::new(Mutex::new(vec![])),
+            pulls: Arc::new(Mutex::new(BTreeMap::new())),
+            workflows: Arc::new(Mutex::new(BTreeMap::new())),
+            runs: Arc::new(Mutex::new(BTreeMap::new())),
+            default_branches: Arc::new(Mutex::new(BTreeMap::new())),
+            open_pulls: Arc::new(Mutex::new(BTreeMap::new())),
+            change_requests: Arc::new(Mutex::new(BTreeMap::new())),
+            pipeline_runs: Arc::new(Mutex::new(BTreeMap::new())),
+            pipeline_definitions: Arc::new(Mutex::new(BTreeMap::new())),
+            groups: Arc::new(Mutex::new(BTreeMap::new())),
+            attempt_jobs: Arc::new(Mutex::new(BTreeMap::new())),
+            pull_history: Arc::new(Mutex::new(BTreeMap::new())),
+            truncated_history: Arc::new(Mutex::new(BTreeSet::new())),
+            reviews: Arc::new(Mutex::new(BTreeMap::new())),
+            team_members: Arc::new(Mutex::new(vec![])),
+            login: Login::default(),
+            token_check: TokenCheck::default(),
+        }
+    }
+}
+
+impl Profile {
+    /// An empty profile, with its tokens stored apart from the tokens of `existing` profiles.
+    fn new(existing: &[Profile]) -> Self {
+        let n = (1..)
+            .find(|n| existing.iter().all(|profile| profile.token_store.entry() != format!("{}-{}", TokenStore::default().entry(), n)))
+            .unwrap_or_default();
+
+        Self {
+            name: format!("Profile {}", existing.len() + 1),
+            token_store: TokenStore::new(&format!("{}-{}", TokenStore::default().entry(), n)),
+            forges: Forges::numbered(n),
+            repositories: HashSet::new(),
+            ..Self::default()
+        }
+    }
+}
+
+/// Selecting, renaming, adding and deleting profiles.
+pub fn switcher_ui(ui: &mut egui::Ui, profiles: &mut Vec<Profile>, active: &mut usize) {
+    egui::ComboBox::from_id_source("profile")
+        .selected_text(&profiles[*active].name)
+        .show_ui(ui, |ui| {
+            for (i, profile) in profiles.iter().enumerate() {
+                ui.selectable_value(active, i, &profile.name);
+            }
+        });
+
+    ui.add(egui::TextEdit::singleline(&mut profiles[*active].name).desired_width(100.0))
+        .on_hover_text("Profile name");
+
+    if ui.button("+").on_hover_text("New profile").clicked() {
+        profiles.push(Profile::new(profiles));
+        *active = profiles.len() - 1;
+    }
+
+    if ui.add_enabled(profiles.len() > 1, egui::Button::new("🗑"))
+        .on_hover_text("Delete profile and its tokens")
+        .clicked() {
+        let mut removed = profiles.remove(*active);
+        removed.token_store.save("");
+        removed.forges.forget_tokens();
+        *active = active.saturating_sub(1);
+    }
+}
@@ -0,0 +1,137 @@
+//! Keeps the token out of the persisted app state. Native builds keep it in memory, or in the OS
+//! keyring (Secret Service on Linux) when the user opts in to remembering it, persisting only the
+//! name of the entry. Web builds keep it in session storage, or in local storage when remembered.
+
+use serde::{Deserialize, Serialize};
+
+#[cfg(not(target_arch = "wasm32"))]
+const SERVICE: &str = "aap_status";
+
+/// Shown in the token field until a token is entered, never stored.
+pub const PLACEHOLDER: &str = "<GitHub PAT>";
+
+/// What remembering the token does, for the checkbox opting in to it.
+#[cfg(not(target_arch = "wasm32"))]
+pub const REMEMBER: &str = "Keep the token in the keyring of the system after closing the app";
+#[cfg(target_arch = "wasm32")]
+pub const REMEMBER: &str = "Keep the token in this browser after closing the tab";
+
+#[derive(Deserialize, Serialize)]
+#[serde(default)]
+pub struct TokenStore {
+    /// Name of the keyring entry or storage key holding the token.
+    entry: String,
+    /// Keep the token after the app or tab is closed, see [`REMEMBER`].
+    pub remember: bool,
+
+    /// What was last stored, to avoid writing to the keyring on every save.
+    #[serde(skip)]
+    stored: Option<String>,
+    /// Why the token could not be read or stored, until it can.
+    #[serde(skip)]
+    error: Option<String>,
+}
+
+impl Default for TokenStore {
+    fn default() -> Self {
+        Self {
+            entry: String::from("github-token"),
+            remember: false,
+            stored: None,
+            error: None,
+        }
+    }
+}
+
+impl TokenStore {
+    /// A store for the token in the keyring entry or storage key `entry`.
+    pub fn new(entry: &str) -> Self {
+        Self {
+            entry: entry.to_string(),
+            ..Self::default()
+        }
+    }
+
+    pub fn entry(&self) -> &str {
+        &self.entry
+    }
+
+    pub fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+
+    pub fn load(&mut self) -> Option<String> {
+        let token = self.read().unwrap_or_else(|e| {
+            self.error = Some(e);
+            None
+        });
+        // A token found in the keyring was stored before remembering it was a choice.
+        #[cfg(not(target_arch = "wasm32"))]
+        if token.is_some() {
+            self.remember = true;
+        }
+        self.stored = token.clone();
+        token
+    }
+
+    /// Stores the token, or removes it when empty or, natively, not to be remembered.
+    pub fn save(&mut self, token: &str) {
+        let token = Some(token.trim().to_string()).filter(|t| !t.is_empty() && t != PLACEHOLDER);
+        #[cfg(not(target_arch = "wasm32"))]
+        let token = token.filter(|_| self.remember);
+        if token == self.stored && !cfg!(target_arch = "wasm32") {
+            return;
+        }
+
+        self.error = self.write(token.as_deref()).err();
+        self.stored = token;
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn read(&self) -> Result<Option<String>, String> {
+        match keyring::Entry::new(SERVICE, &self.entry).and_then(|entry| entry.get_password()) {
+            Ok(token) => Ok(Some(token)),
+            Err(keyring::Error::NoEntry) => Ok(None),
+            Err(e) => Err(format!("Could not read the token from the keyring: {}", e)),
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn write(&self, token: Option<&str>) -> Result<(), String> {
+        keyring::Entry::new(SERVICE, &self.entry).and_then(|entry| match token {
+            Some(token) => entry.set_password(token),
+            None => entry.delete_credential().or_else(|e| match e {
+                keyring::Error::NoEntry => Ok(()),
+                e => Err(e),
+            }),
+        }).map_err(|e| format!("Could not store the token in the keyring: {}", e))
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn read(&self) -> Result<Option<String>, String> {
+        Ok([false, true].into_iter()
+            .filter_map(storage)
+            .find_map(|storage| storage.get_item(&self.entry).ok().flatten()))
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn write(&self, token: Option<&str>) -> Result<(), String> {
+        for local in [false, true] {
+            let Some(storage) = storage(local) else { continue };
+            let result = match token {
+                Some(token) if local == self.remember => storage.set_item(&self.entry, token),
+                _ => storage.remove_item(&self.entry),
+            };
+
+            result.map_err(|e| format!("Could not store the token in the browser: {:?}", e))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn storage(local: bool) -> Option<web_sys::Storage> {
+    let window = web_sys::window()?;
+    let storage = if local { window.local_storage() } else { window.session_storage() };
+    storage.ok().flatten()
+}
@@ -0,0 +1,276 @@
+//! Terminal frontend with the Pulls, Workflows and Repositories views of the GUI, for SSH sessions.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Borders, Cell, Paragraph, Row, Table, TableState, Tabs};
+use ratatui::Frame;
+
+use crate::app::DEFAULT_REPOSITORIES;
+use crate::github::github_client::{spawn, GitHubApi};
+use crate::github::pulls::PullRequest;
+use crate::github::runs::{WorkflowRun, WorkflowRuns};
+
+/// How often to redraw while waiting for key presses, so fetched data shows up.
+const TICK: Duration = Duration::from_millis(250);
+
+const HELP: &str = "1-3/tab: switch view  ↑↓/jk: move  r: refresh  a: add repository  d: remove repository  q: quit";
+
+#[derive(Clone, Copy, PartialEq)]
+enum View {
+    Pulls,
+    Runs,
+    Repositories,
+}
+
+impl View {
+    const ALL: [View; 3] = [View::Pulls, View::Runs, View::Repositories];
+
+    fn title(&self) -> &'static str {
+        match self {
+            View::Pulls => "Pull Requests",
+            View::Runs => "Workflows",
+            View::Repositories => "Repositories",
+        }
+    }
+}
+
+struct App {
+    token: String,
+    github: GitHubApi,
+    view: View,
+    repositories: BTreeSet<String>,
+    table: TableState,
+    new_repo: Option<String>,
+    pulls: Arc<Mutex<BTreeMap<String, Vec<PullRequest>>>>,
+    runs: Arc<Mutex<BTreeMap<String, WorkflowRuns>>>,
+    /// Why the last refresh failed for some repositories. Shown in the footer, as anything printed
+    /// would end up in the middle of the screen.
+    errors: Arc<Mutex<Vec<String>>>,
+}
+
+/// Runs the terminal frontend until the user quits. Takes `--repo <name>` arguments,
+/// the token is read from the GITHUB_TOKEN environment variable.
+pub fn run(args: &[String]) -> std::io::Result<()> {
+    let mut repositories = args.windows(2)
+        .filter(|pair| pair[0] == "--repo")
+        .map(|pair| pair[1].clone())
+        .collect::<BTreeSet<_>>();
+
+    if repositories.is_empty() {
+        repositories = DEFAULT_REPOSITORIES.iter().map(|repo| repo.to_string()).collect();
+    }
+
+    let mut app = App {
+        token: std::env::var("GITHUB_TOKEN").unwrap_or_default(),
+        github: GitHubApi::default(),
+        view: View::Pulls,
+        repositories,
+        table: TableState::default().with_selected(0),
+        new_repo: None,
+        pulls: Arc::new(Mutex::new(BTreeMap::new())),
+        runs: Arc::new(Mutex::new(BTreeMap::new())),
+        errors: Arc::new(Mutex::new(vec![])),
+    };
+    app.refresh();
+
+    let mut terminal = ratatui::init();
+    let result = app.event_loop(&mut terminal);
+    ratatui::restore();
+    result
+}
+
+impl App {
+    fn event_loop(&mut self, terminal: &mut ratatui::DefaultTerminal) -> std::io::Result<()> {
+        loop {
+            terminal.draw(|frame| self.draw(frame))?;
+
+            if !event::poll(TICK)? {
+                continue;
+            }
+
+            let Event::Key(key) = event::read()? else { continue };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+
+            if let Some(new_repo) = &mut self.new_repo {
+                match key.code {
+                    KeyCode::Enter => {
+                        if !new_repo.trim().is_empty() {
+                            self.repositories.insert(new_repo.trim().to_string());
+                        }
+                        self.new_repo = None;
+                    }
+                    KeyCode::Esc => self.new_repo = None,
+                    KeyCode::Backspace => { new_repo.pop(); }
+                    KeyCode::Char(c) => new_repo.push(c),
+                    _ => {}
+                }
+                continue;
+            }
+
+            match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                KeyCode::Char('1') => self.show(View::Pulls),
+                KeyCode::Char('2') => self.show(View::Runs),
+                KeyCode::Char('3') => self.show(View::Repositories),
+                KeyCode::Tab => {
+                    let next = View::ALL.iter().position(|view| *view == self.view).map_or(0, |i| (i + 1) % View::ALL.len());
+                    self.show(View::ALL[next]);
+                }
+                KeyCode::Down | KeyCode::Char('j') => self.table.select_next(),
+                KeyCode::Up | KeyCode::Char('k') => self.table.select_previous(),
+                KeyCode::Char('r') => self.refresh(),
+                KeyCode::Char('a') if self.view == View::Repositories => self.new_repo = Some(String::new()),
+                KeyCode::Char('d') if self.view == View::Repositories => {
+                    let selected = self.table.selected().and_then(|i| self.repositories.iter().nth(i).cloned());
+                    if let Some(repo) = selected {
+                        self.repositories.remove(&repo);
+                        self.pulls.lock().unwrap().remove(&repo);
+                        self.runs.lock().unwrap().remove(&repo);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn show(&mut self, view: View) {
+        self.view = view;
+        self.table.select(Some(0));
+    }
+
+    fn refresh(&mut self) {
+        self.errors.lock().unwrap().clear();
+
+        for repo in self.repositories.clone().into_iter() {
+            let (_github, _token, _repo) = (self.github.clone(), self.token.clone(), repo.clone());
+            let (_pulls, _errors) = (self.pulls.clone(), self.errors.clone());
+            spawn(async move {
+                match _github.fetch_pull_requests(&_token, &_repo).await {
+                    Ok(response) => { _pulls.lock().unwrap().insert(_repo, response); }
+                    Err(e) => _errors.lock().unwrap().push(e),
+                }
+            });
+
+            let (_github, _token, _repo) = (self.github.clone(), self.token.clone(), repo.clone());
+            let (_runs, _errors) = (self.runs.clone(), self.errors.clone());
+            spawn(async move {
+                match _github.fetch_runs(&_token, &_repo).await {
+                    Ok(response) => { _runs.lock().unwrap().insert(_repo, response); }
+                    Err(e) => _errors.lock().unwrap().push(e),
+                }
+            });
+        }
+    }
+
+    fn draw(&mut self, frame: &mut Frame<'_>) {
+        let [tabs, body, footer] = Layout::vertical([
+            Constraint::Length(3),
+            Constraint::Min(0),
+            Constraint::Length(1),
+        ]).areas(frame.area());
+
+        let selected = View::ALL.iter().position(|view| *view == self.view).unwrap_or(0);
+        frame.render_widget(
+            Tabs::new(View::ALL.iter().enumerate().map(|(i, view)| format!("{} {}", i + 1, view.title())))
+                .select(selected)
+                .highlight_style(Style::default().add_modifier(Modifier::BOLD | Modifier::REVERSED))
+                .block(Block::default().borders(Borders::ALL).title("GitHub Status")),
+            tabs,
+        );
+
+        let table = match self.view {
+            View::Pulls => self.pulls_table(),
+            View::Runs => self.runs_table(),
+            View::Repositories => self.repositories_table(),
+        };
+        frame.render_stateful_widget(table, body, &mut self.table);
+
+        let errors = self.errors.lock().unwrap();
+        let footer_line = match (&self.new_repo, errors.last()) {
+            (Some(new_repo), _) => Line::from(format!("Add repository: {}_  (enter: add, esc: cancel)", new_repo)),
+            (None, Some(e)) => Line::styled(format!("{} failed, latest: {}", errors.len(), e), Style::default().fg(Color::Red)),
+            (None, None) => Line::from(HELP),
+        };
+        frame.render_widget(Paragraph::new(footer_line), footer);
+    }
+
+    fn pulls_table(&self) -> Table<'static> {
+        // A refresh still out when a repository is removed may add it back.
+        let rows = self.pulls.lock().unwrap().iter().filter(|(repo, _)| self.repositories.contains(*repo)).flat_map(|(repo, prs)| {
+            prs.iter().map(|pr| Row::new(vec![
+                repo.clone(),
+                format!("{}", pr.number),
+                pr.title.clone().unwrap_or_default(),
+                pr.updated_at.clone().unwrap_or_default(),
+                pr.user.as_ref().map(|user| user.login.clone()).unwrap_or_default(),
+            ])).collect::<Vec<_>>()
+        }).collect::<Vec<_>>();
+
+        table(rows, ["Repository", "ID", "Title", "Last Update", "Author"], &[
+            Constraint::Length(24),
+            Constraint::Length(6),
+            Constraint::Min(30),
+            Constraint::Length(21),
+            Constraint::Length(20),
+        ])
+    }
+
+    fn runs_table(&self) -> Table<'static> {
+        let rows = self.runs.lock().unwrap().iter().filter(|(repo, _)| self.repositories.contains(*repo)).flat_map(|(repo, runs)| {
+            let latest = runs.workflow_runs.iter().fold(BTreeMap::new(), |mut acc: BTreeMap<i64, &WorkflowRun>, run| {
+                acc.entry(run.workflow_id).or_insert(run);
+                acc
+            });
+
+            latest.into_values().map(|run| {
+                let conclusion = run.conclusion.clone().unwrap_or_default();
+                // Runs not completed have no conclusion yet, which is neither good nor bad.
+                let color = match (run.status.as_deref(), conclusion.as_str()) {
+                    (Some("completed"), "success") => Color::Green,
+                    (Some("completed"), _) if run.is_failure() => Color::Red,
+                    (Some("completed"), _) => Color::Gray,
+                    _ => Color::Yellow,
+                };
+                Row::new(vec![
+                    Cell::from(repo.clone()),
+                    Cell::from(run.name.clone().unwrap_or_default()),
+                    Cell::from(run.event.clone()),
+                    Cell::from(run.status.clone().unwrap_or_default()),
+                    Cell::from(conclusion).style(Style::default().fg(color)),
+                    Cell::from(format!("{}", run.run_attempt)),
+                    Cell::from(run.run_started_at.clone().unwrap_or_default()),
+                ])
+            }).collect::<Vec<_>>()
+        }).collect::<Vec<_>>();
+
+        table(rows, ["Repository", "Workflow", "Event", "Status", "Conclusion", "Attempts", "Timestamp"], &[
+            Constraint::Length(24),
+            Constraint::Min(20),
+            Constraint::Length(14),
+            Constraint::Length(12),
+            Constraint::Length(12),
+            Constraint::Length(8),
+            Constraint::Length(21),
+        ])
+    }
+
+    fn repositories_table(&self) -> Table<'static> {
+        let rows = self.repositories.iter().map(|repo| Row::new(vec![repo.clone()])).collect::<Vec<_>>();
+        table(rows, [&format!("Repository (total: {})", self.repositories.len())], &[Constraint::Min(20)])
+    }
+}
+
+fn table<const N: usize>(rows: Vec<Row<'static>>, header: [&str; N], widths: &[Constraint]) -> Table<'static> {
+    Table::new(rows, widths.to_vec())
+        .header(Row::new(header.map(|title| title.to_string())).style(Style::default().add_modifier(Modifier::BOLD)))
+        .row_highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+        .block(Block::default().borders(Borders::ALL))
+}
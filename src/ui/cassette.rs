@@ -0,0 +1,94 @@
+use egui::{Color32, Ui};
+
+use crate::github::cassette::{Cassette, Mode};
+
+/// Records the GitHub traffic of the session to a file, or replays such a file instead of using
+/// the network. AAP_STATUS_RECORD or AAP_STATUS_REPLAY set to a file starts doing so at startup.
+#[derive(serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct CassetteFile {
+    location: String,
+
+    #[serde(skip)]
+    status: Option<Result<String, String>>,
+}
+
+impl Default for CassetteFile {
+    fn default() -> Self {
+        Self {
+            location: String::from("aap_status.cassette.json"),
+            status: None,
+        }
+    }
+}
+
+impl CassetteFile {
+    pub fn start_from_env(&mut self, cassette: &Cassette) {
+        if let Ok(path) = std::env::var("AAP_STATUS_REPLAY") {
+            self.location = path;
+            self.replay(cassette);
+        } else if let Ok(path) = std::env::var("AAP_STATUS_RECORD") {
+            self.location = path;
+            cassette.record();
+        }
+    }
+
+    pub fn ui(&mut self, ui: &mut Ui, cassette: &Cassette) {
+        ui.horizontal_wrapped(|ui| {
+            ui.label("File:");
+            ui.text_edit_singleline(&mut self.location);
+
+            match cassette.mode() {
+                Mode::Off => {
+                    if ui.button("⏺ Record")
+                        .on_hover_text("Keep the responses from GitHub until saved, never the token")
+                        .clicked() {
+                        self.status = None;
+                        cassette.record();
+                    }
+                    if ui.button("▶ Replay")
+                        .on_hover_text("Answer requests from the file instead of GitHub")
+                        .clicked() {
+                        self.replay(cassette);
+                    }
+                }
+                Mode::Record => {
+                    ui.label(format!("{} responses recorded", cassette.len()));
+                    if ui.button("⏹ Save").clicked() {
+                        self.save(cassette);
+                        cassette.stop();
+                    }
+                }
+                Mode::Replay => {
+                    if ui.button("⏹ Stop").on_hover_text("Back to GitHub").clicked() {
+                        self.status = None;
+                        cassette.stop();
+                    }
+                }
+            }
+        });
+
+        match &self.status {
+            Some(Ok(message)) => { ui.label(message); }
+            Some(Err(e)) => { ui.colored_label(Color32::from_rgb(255, 100, 100), e); }
+            None => {}
+        }
+    }
+
+    /// Saves what has been recorded so far, if recording. Also done whenever the app state is saved.
+    pub fn save(&mut self, cassette: &Cassette) {
+        if cassette.mode() == Mode::Record {
+            let location = self.location.trim();
+            self.status = Some(cassette.save(location).map(|count| format!("Saved {} responses to {}", count, location)));
+        }
+    }
+
+    fn replay(&mut self, cassette: &Cassette) {
+        let location = self.location.trim();
+        self.status = Some(Cassette::load(location).map(|interactions| {
+            let message = format!("Replaying {} responses from {}", interactions.len(), location);
+            cassette.replay(interactions);
+            message
+        }));
+    }
+}
@@ -0,0 +1,154 @@
+use std::collections::{BTreeMap, HashSet};
+
+use chrono::{TimeZone, Utc};
+use egui::plot::{Legend, Line, MarkerShape, Plot, PlotPoint, Points};
+use egui::{Color32, Ui};
+
+use crate::forge::{PipelineDefinition, PipelineRun};
+
+/// Clicks closer than this (in points) to a failed run opens it.
+const CLICK_RADIUS: f32 = 8.0;
+
+#[derive(serde::Deserialize, serde::Serialize, Clone)]
+#[serde(default)]
+pub struct Charts {
+    pub repo: Option<String>,
+    /// Id of the [`PipelineDefinition`] charted.
+    pub workflow: Option<String>,
+    rolling_window: usize,
+}
+
+impl Default for Charts {
+    fn default() -> Self {
+        Self {
+            repo: None,
+            workflow: None,
+            rolling_window: 10,
+        }
+    }
+}
+
+impl Charts {
+    pub fn selection_ui(
+        &mut self,
+        ui: &mut Ui,
+        repositories: &HashSet<String>,
+        workflows: &BTreeMap<String, Vec<PipelineDefinition>>,
+    ) {
+        let mut repos = repositories.iter().collect::<Vec<_>>();
+        repos.sort();
+
+        ui.horizontal_wrapped(|ui| {
+            let selected_repo = self.repo.clone().unwrap_or_else(|| String::from("Not selected"));
+            egui::ComboBox::from_label("repository")
+                .selected_text(selected_repo)
+                .show_ui(ui, |ui| {
+                    repos.into_iter().for_each(|repo| {
+                        if ui.selectable_value(&mut self.repo, Some(repo.clone()), repo).changed() {
+                            self.workflow = None;
+                        }
+                    });
+                });
+
+            let repo_workflows = self.repo.as_ref().and_then(|repo| workflows.get(repo)).cloned().unwrap_or_default();
+            let selected_workflow = repo_workflows.iter()
+                .find(|w| Some(&w.id) == self.workflow.as_ref())
+                .map_or(String::from("Not selected"), |w| w.name.clone());
+
+            egui::ComboBox::from_label("workflow")
+                .selected_text(selected_workflow)
+                .show_ui(ui, |ui| {
+                    repo_workflows.iter().for_each(|w| {
+                        ui.selectable_value(&mut self.workflow, Some(w.id.clone()), &w.name);
+                    });
+                });
+
+            ui.add(egui::Slider::new(&mut self.rolling_window, 2..=50).text("rolling window"));
+        });
+    }
+
+    pub fn workflow_runs_ui(&mut self, ui: &mut Ui, runs: &[PipelineRun]) {
+        let mut runs = runs.iter()
+            .filter(|run| run.started().is_some())
+            .cloned()
+            .collect::<Vec<_>>();
+        runs.sort_by_key(|run| run.started());
+
+        ui.label(format!("{} runs", runs.len()));
+
+        let durations = runs.iter()
+            .filter_map(|run| Some([timestamp(run)?, run.duration_secs()? as f64 / 60.0]))
+            .collect::<Vec<_>>();
+
+        let failures = runs.iter()
+            .filter(|run| run.is_failure())
+            .filter_map(|run| Some((timestamp(run)?, run.duration_secs()? as f64 / 60.0, run.url.clone())))
+            .collect::<Vec<_>>();
+
+        let success_rate = rolling_success_rate(&runs, self.rolling_window);
+
+        ui.strong("Duration (minutes)");
+        let clicked = Plot::new("workflow_duration")
+            .height(ui.available_height() / 2.0)
+            .legend(Legend::default())
+            .x_axis_formatter(format_date)
+            .label_formatter(|name, point| format!("{}\n{}\n{:.1} min", name, format_date(point.x, &(0.0..=0.0)), point.y))
+            .show(ui, |plot_ui| {
+                plot_ui.line(Line::new(durations).name("duration"));
+                plot_ui.points(Points::new(failures.iter().map(|(x, y, _)| [*x, *y]).collect::<Vec<_>>())
+                    .name("failed")
+                    .shape(MarkerShape::Cross)
+                    .radius(5.0)
+                    .color(Color32::from_rgb(255, 100, 100)));
+
+                if !plot_ui.plot_clicked() {
+                    return None;
+                }
+
+                let pointer = plot_ui.screen_from_plot(plot_ui.pointer_coordinate()?);
+                failures.iter()
+                    .map(|(x, y, url)| (plot_ui.screen_from_plot(PlotPoint::new(*x, *y)).distance(pointer), url))
+                    .filter(|(distance, _)| *distance < CLICK_RADIUS)
+                    .min_by(|(a, _), (b, _)| a.total_cmp(b))
+                    .map(|(_, url)| url.clone())
+            }).inner;
+
+        if let Some(url) = clicked {
+            ui.ctx().output().open_url(url);
+        }
+
+        ui.strong(format!("Success rate (last {} runs)", self.rolling_window));
+        Plot::new("workflow_success_rate")
+            .legend(Legend::default())
+            .include_y(0.0)
+            .include_y(100.0)
+            .x_axis_formatter(format_date)
+            .y_axis_formatter(|y, _| format!("{:.0}%", y))
+            .show(ui, |plot_ui| {
+                plot_ui.line(Line::new(success_rate).name("success rate").color(Color32::from_rgb(100, 255, 146)));
+            });
+    }
+}
+
+fn timestamp(run: &PipelineRun) -> Option<f64> {
+    run.started().map(|started| started.timestamp() as f64)
+}
+
+fn format_date(x: f64, _range: &std::ops::RangeInclusive<f64>) -> String {
+    Utc.timestamp_opt(x as i64, 0)
+        .single()
+        .map_or(String::new(), |t| t.format("%Y-%m-%d").to_string())
+}
+
+/// Percentage of successful runs among the last `window` completed runs, for every completed run.
+fn rolling_success_rate(runs: &[PipelineRun], window: usize) -> Vec<[f64; 2]> {
+    let completed = runs.iter()
+        .filter(|run| run.conclusion.is_some())
+        .collect::<Vec<_>>();
+
+    completed.iter().enumerate().filter_map(|(i, run)| {
+        let recent = &completed[(i + 1).saturating_sub(window)..=i];
+        let successes = recent.iter().filter(|r| r.is_success()).count();
+        Some([timestamp(run)?, 100.0 * successes as f64 / recent.len() as f64])
+    }).collect()
+}
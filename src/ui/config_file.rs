@@ -0,0 +1,135 @@
+use std::sync::{Arc, Mutex};
+
+use egui::{Color32, Ui};
+
+use crate::config::{Config, Format};
+
+pub enum ConfigAction {
+    /// Export the configuration of the active profile with [`ConfigFile::export`].
+    Export,
+    /// Apply an imported configuration to the active profile.
+    Import(Box<Config>),
+}
+
+/// Exports the configuration of the active profile to a file, and imports it from a file or URL.
+/// The web build downloads the export and only imports from URLs, relative ones included.
+#[derive(serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct ConfigFile {
+    location: String,
+
+    #[serde(skip)]
+    status: Option<Result<String, String>>,
+
+    #[serde(skip)]
+    fetched: Arc<Mutex<Option<Result<String, String>>>>,
+}
+
+impl Default for ConfigFile {
+    fn default() -> Self {
+        Self {
+            location: String::from("aap_status.toml"),
+            status: None,
+            fetched: Arc::new(Mutex::new(None)),
+        }
+    }
+}
+
+impl ConfigFile {
+    pub fn ui(&mut self, ui: &mut Ui) -> Option<ConfigAction> {
+        let mut action = None;
+
+        ui.horizontal_wrapped(|ui| {
+            ui.label("File or URL:");
+            ui.text_edit_singleline(&mut self.location)
+                .on_hover_text("JSON when ending with .json, TOML otherwise");
+
+            if ui.button("Export")
+                .on_hover_text("Repositories, table, chart and notification settings, never the token")
+                .clicked() {
+                action = Some(ConfigAction::Export);
+            }
+
+            if ui.button("Import")
+                .on_hover_text("Replace the settings of this profile")
+                .clicked() {
+                self.status = None;
+                self.import();
+            }
+        });
+
+        if let Some(fetched) = self.fetched.lock().unwrap().take() {
+            match fetched.and_then(|content| Config::parse(&content, Format::of(&self.location))) {
+                Ok(config) => {
+                    self.status = Some(Ok(format!("Imported {}", self.location.trim())));
+                    action = Some(ConfigAction::Import(Box::new(config)));
+                }
+                Err(e) => self.status = Some(Err(e)),
+            }
+        }
+
+        match &self.status {
+            Some(Ok(message)) => { ui.label(message); }
+            Some(Err(e)) => { ui.colored_label(Color32::from_rgb(255, 100, 100), e); }
+            None => {}
+        }
+
+        action
+    }
+
+    pub fn export(&mut self, config: &Config) {
+        let location = self.location.trim();
+        self.status = Some(config.to_string(Format::of(location))
+            .and_then(|content| write(location, &content))
+            .map(|_| format!("Exported to {}", location)));
+    }
+
+    fn import(&mut self) {
+        let location = self.location.trim().to_string();
+
+        #[cfg(not(target_arch = "wasm32"))]
+        if !location.starts_with("http://") && !location.starts_with("https://") {
+            *self.fetched.lock().unwrap() = Some(std::fs::read_to_string(&location).map_err(|e| format!("{}: {}", location, e)));
+            return;
+        }
+
+        let fetched = self.fetched.clone();
+        ehttp::fetch(ehttp::Request::get(&location), move |result: ehttp::Result<ehttp::Response>| {
+            *fetched.lock().unwrap() = Some(match result {
+                Ok(res) if res.ok => String::from_utf8(res.bytes).map_err(|e| format!("{}: {}", location, e)),
+                Ok(res) => Err(format!("{} {} from {}", res.status, res.status_text, location)),
+                Err(e) => Err(format!("{}: {}", location, e)),
+            });
+        });
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn write(location: &str, content: &str) -> Result<(), String> {
+    std::fs::write(location, content).map_err(|e| format!("{}: {}", location, e))
+}
+
+/// Browsers can't write files, so the export is offered as a download named after `location`.
+#[cfg(target_arch = "wasm32")]
+fn write(location: &str, content: &str) -> Result<(), String> {
+    use wasm_bindgen::JsCast;
+
+    let name = location.rsplit('/').next().unwrap_or(location);
+    let encoded = content.bytes()
+        .map(|b| match b.is_ascii_alphanumeric() || b"-_.~".contains(&b) {
+            true => (b as char).to_string(),
+            false => format!("%{:02X}", b),
+        })
+        .collect::<String>();
+
+    let document = web_sys::window().and_then(|window| window.document()).ok_or("no document")?;
+    let anchor = document.create_element("a")
+        .map_err(|e| format!("{:?}", e))?
+        .dyn_into::<web_sys::HtmlAnchorElement>()
+        .map_err(|e| format!("{:?}", e))?;
+
+    anchor.set_href(&format!("data:application/octet-stream,{}", encoded));
+    anchor.set_download(name);
+    anchor.click();
+    Ok(())
+}
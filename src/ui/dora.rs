@@ -0,0 +1,138 @@
+use std::collections::{BTreeMap, HashSet};
+
+use chrono::{Duration, Utc};
+use egui::Ui;
+
+use crate::analysis::dora::{DoraMetrics, DoraSamples};
+use crate::analysis::format_duration;
+use crate::github::pulls::PullRequest;
+use crate::github::runs::WorkflowRun;
+
+const DEFAULT_DEPLOY_WORKFLOW: &str = "deploy.yml";
+const WINDOWS_IN_DAYS: [i64; 4] = [7, 30, 90, 180];
+
+#[derive(serde::Deserialize, serde::Serialize, Clone)]
+#[serde(default)]
+pub struct Dora {
+    window_days: i64,
+    deploy_workflows: BTreeMap<String, String>,
+}
+
+impl Default for Dora {
+    fn default() -> Self {
+        Self {
+            window_days: 30,
+            deploy_workflows: BTreeMap::new(),
+        }
+    }
+}
+
+impl Dora {
+    /// File name (or id) of the workflow that deploys the repository to production.
+    pub fn deploy_workflow(&self, repo: &str) -> String {
+        self.deploy_workflows.get(repo).cloned().unwrap_or_else(|| DEFAULT_DEPLOY_WORKFLOW.to_string())
+    }
+
+    pub fn settings_ui(&mut self, ui: &mut Ui, repositories: &HashSet<String>) {
+        ui.horizontal_wrapped(|ui| {
+            ui.label("Time window:");
+            WINDOWS_IN_DAYS.iter().for_each(|days| {
+                ui.selectable_value(&mut self.window_days, *days, format!("{} days", days));
+            });
+        });
+
+        ui.collapsing("Deploy workflows", |ui| {
+            let mut repos = repositories.iter().collect::<Vec<_>>();
+            repos.sort();
+
+            egui::Grid::new("deploy_workflows").show(ui, |ui| {
+                repos.into_iter().for_each(|repo| {
+                    ui.label(repo);
+                    let workflow = self.deploy_workflows.entry(repo.clone())
+                        .or_insert_with(|| DEFAULT_DEPLOY_WORKFLOW.to_string());
+                    ui.text_edit_singleline(workflow);
+                    ui.end_row();
+                });
+            });
+        });
+    }
+
+    pub fn metrics_ui(
+        &mut self,
+        ui: &mut Ui,
+        pulls: &BTreeMap<String, BTreeMap<i32, PullRequest>>,
+        deploy_runs: &BTreeMap<String, BTreeMap<i64, WorkflowRun>>,
+    ) {
+        use egui_extras::{Column, TableBuilder};
+
+        let window = Duration::days(self.window_days);
+        let since = Utc::now() - window;
+
+        let mut repos = pulls.keys().chain(deploy_runs.keys()).collect::<Vec<_>>();
+        repos.sort();
+        repos.dedup();
+
+        let per_repo = repos.into_iter().map(|repo| {
+            let prs = pulls.get(repo).map(|prs| prs.values().cloned().collect::<Vec<_>>()).unwrap_or_default();
+            let runs = deploy_runs.get(repo).map(|runs| runs.values().cloned().collect::<Vec<_>>()).unwrap_or_default();
+            (repo.clone(), DoraSamples::collect(&prs, &runs, since))
+        }).collect::<Vec<_>>();
+
+        let team = per_repo.iter().fold(DoraSamples::default(), |mut acc, (_, samples)| {
+            acc.extend(samples);
+            acc
+        });
+
+        let table = TableBuilder::new(ui)
+            .cell_layout(egui::Layout::left_to_right(egui::Align::Center))
+            .column(Column::auto())
+            .column(Column::auto())
+            .column(Column::auto())
+            .column(Column::auto())
+            .column(Column::auto())
+            .column(Column::auto())
+            .column(Column::auto())
+            .column(Column::auto())
+            .min_scrolled_height(0.0);
+
+        table.header(20.0, |mut header| {
+            header.col(|ui| { ui.strong("Repository"); });
+            header.col(|ui| { ui.strong("Deploys"); });
+            header.col(|ui| { ui.strong("Per week"); });
+            header.col(|ui| { ui.strong("Lead time"); });
+            header.col(|ui| { ui.strong("Open → merge"); });
+            header.col(|ui| { ui.strong("Merge → deploy"); });
+            header.col(|ui| { ui.strong("Change failure rate"); });
+            header.col(|ui| { ui.strong("Time to restore"); });
+        }).body(|mut body| {
+            body.row(40.0, |mut row| {
+                metrics_row(&mut row, "Team", &team.metrics(window), true);
+            });
+
+            per_repo.iter().for_each(|(repo, samples)| {
+                body.row(18.0, |mut row| {
+                    metrics_row(&mut row, repo, &samples.metrics(window), false);
+                });
+            });
+        });
+    }
+}
+
+fn metrics_row(row: &mut egui_extras::TableRow<'_, '_>, name: &str, metrics: &DoraMetrics, heading: bool) {
+    let cells = [
+        name.to_string(),
+        format!("{}", metrics.deployments),
+        format!("{:.1}", metrics.deployments_per_week),
+        metrics.lead_time.map_or(String::from("-"), format_duration),
+        metrics.open_to_merge.map_or(String::from("-"), format_duration),
+        metrics.merge_to_deploy.map_or(String::from("-"), format_duration),
+        metrics.change_failure_rate.map_or(String::from("-"), |rate| format!("{:.0}%", 100.0 * rate)),
+        metrics.time_to_restore.map_or(String::from("-"), format_duration),
+    ];
+
+    cells.into_iter().for_each(|cell| {
+        row.col(|ui| {
+            if heading { ui.heading(cell); } else { ui.label(cell); }
+        });
+    });
+}
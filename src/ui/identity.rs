@@ -0,0 +1,117 @@
+use std::sync::{Arc, Mutex};
+
+use egui::{Color32, Ui};
+use egui_extras::RetainedImage;
+
+use crate::github::github_client::{GitHubApi, Users};
+use crate::github::users::Identity;
+use crate::token_store::PLACEHOLDER;
+
+const AVATAR_SIZE: f32 = 20.0;
+
+#[derive(Default)]
+enum CheckState {
+    #[default]
+    Unchecked,
+    Checking,
+    Valid {
+        identity: Identity,
+        avatar: Option<Box<RetainedImage>>,
+    },
+    Invalid(String),
+}
+
+#[derive(Default)]
+struct Check {
+    /// The token the state belongs to, so a late response for a replaced token is ignored.
+    token: String,
+    state: CheckState,
+}
+
+/// Validates the token against `/user` once it has been entered, showing who it belongs to and
+/// whether it has the scopes needed by the current view.
+#[derive(Default)]
+pub struct TokenCheck {
+    check: Arc<Mutex<Check>>,
+}
+
+impl TokenCheck {
+    /// Checks the token again when it changed and `editing` is over.
+    pub fn ui(&mut self, ui: &mut Ui, github: &GitHubApi, token: &mut String, editing: bool, required_scopes: &[&str]) {
+        let mut check = self.check.lock().unwrap();
+
+        let current = token.trim().to_string();
+        if current.is_empty() || current == PLACEHOLDER {
+            *check = Check::default();
+            return;
+        }
+
+        if !editing && check.token != current {
+            *check = Check { token: current.clone(), state: CheckState::Checking };
+
+            let _check = self.check.clone();
+            github.authenticated_user(token, move |response: Result<Identity, String>| {
+                let mut check = _check.lock().unwrap();
+                if check.token != current {
+                    return;
+                }
+
+                match response {
+                    Ok(identity) => {
+                        let separator = if identity.user.avatar_url.contains('?') { '&' } else { '?' };
+                        let url = format!("{}{}s={}", identity.user.avatar_url, separator, 2.0 * AVATAR_SIZE);
+                        check.state = CheckState::Valid { identity, avatar: None };
+
+                        let _check = _check.clone();
+                        GitHubApi::default().avatar(&url, move |bytes: Vec<u8>| {
+                            match RetainedImage::from_image_bytes("avatar", &bytes) {
+                                Ok(image) => {
+                                    let mut check = _check.lock().unwrap();
+                                    if check.token == current {
+                                        if let CheckState::Valid { avatar, .. } = &mut check.state {
+                                            *avatar = Some(Box::new(image));
+                                        }
+                                    }
+                                }
+                                Err(e) => eprintln!("error: {} when decoding avatar", e),
+                            }
+                        });
+                    }
+                    Err(e) => check.state = CheckState::Invalid(e),
+                }
+            });
+        }
+
+        match &check.state {
+            CheckState::Unchecked => {}
+            CheckState::Checking => { ui.spinner(); }
+            CheckState::Valid { identity, avatar } => {
+                if let Some(avatar) = avatar {
+                    avatar.show_size(ui, egui::vec2(AVATAR_SIZE, AVATAR_SIZE));
+                }
+                ui.hyperlink_to(&identity.user.login, &identity.user.html_url)
+                    .on_hover_text(identity.user.name.clone().unwrap_or_default());
+
+                match &identity.scopes {
+                    Some(scopes) => {
+                        ui.label(format!("scopes: {}", scopes.join(", ")));
+
+                        let missing = identity.missing_scopes(required_scopes);
+                        if !missing.is_empty() {
+                            ui.colored_label(Color32::from_rgb(255, 200, 100), format!("⚠ missing {}", missing.join(", ")))
+                                .on_hover_text("Needed by this view, some data will be missing");
+                        }
+                    }
+                    None => {
+                        ui.label("fine-grained token")
+                            .on_hover_text("Fine-grained tokens don't report scopes, check their permissions on GitHub");
+                    }
+                }
+            }
+            CheckState::Invalid(e) => {
+                ui.colored_label(Color32::from_rgb(255, 100, 100), format!("❌ {}", e))
+                    .on_hover_text("The token was rejected by GitHub");
+            }
+        }
+    }
+}
@@ -0,0 +1,140 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use egui::{Color32, Ui};
+
+use crate::github::github_client::{DeviceFlow, GitHubApi, GITHUB_API};
+use crate::github::oauth::{AccessToken, DeviceCode};
+
+/// Client id of the OAuth app used for signing in, with device flow enabled. Set when building.
+const CLIENT_ID: Option<&str> = option_env!("GITHUB_OAUTH_CLIENT_ID");
+const SCOPE: &str = "repo workflow read:org";
+
+/// How often GitHub lets the device flow poll unless it says otherwise.
+const DEFAULT_INTERVAL: Duration = Duration::from_secs(5);
+
+#[derive(Default)]
+enum LoginState {
+    #[default]
+    Idle,
+    Requested,
+    Pending {
+        code: DeviceCode,
+        started: Option<f64>,
+        last_poll: f64,
+        polling: bool,
+    },
+    Authorized(String),
+    Failed(String),
+}
+
+/// Signing in with the OAuth device flow: show the user code, poll until it has been entered.
+#[derive(Default)]
+pub struct Login {
+    state: Arc<Mutex<LoginState>>,
+}
+
+impl Login {
+    /// Sign in and sign out controls for the top panel. The token is stored in `token` like a pasted one.
+    pub fn ui(&mut self, ui: &mut Ui, github: &GitHubApi, token: &mut String) {
+        let now = ui.ctx().input().time;
+        let mut state = self.state.lock().unwrap();
+
+        match &mut *state {
+            LoginState::Idle => {
+                let signed_in = !token.trim().is_empty() && token != "<GitHub PAT>";
+                if signed_in {
+                    if ui.button("Sign out").clicked() {
+                        token.clear();
+                    }
+                } else if ui.add_enabled(CLIENT_ID.is_some() && reaches_device_flow(github), egui::Button::new("Sign in with GitHub"))
+                    .on_disabled_hover_text(match CLIENT_ID {
+                        Some(_) => "Browsers can't sign in at github.com directly, point the API URL at a backend to sign in",
+                        None => "Build with GITHUB_OAUTH_CLIENT_ID set to enable signing in",
+                    })
+                    .clicked() {
+                    *state = LoginState::Requested;
+                    let (_state, _ctx) = (self.state.clone(), ui.ctx().clone());
+                    github.device_code(CLIENT_ID.unwrap_or_default(), SCOPE, move |response: Result<DeviceCode, String>| {
+                        *_state.lock().unwrap() = match response {
+                            Ok(code) => LoginState::Pending { code, started: None, last_poll: 0.0, polling: false },
+                            Err(e) => LoginState::Failed(e),
+                        };
+                        _ctx.request_repaint();
+                    });
+                }
+            }
+            LoginState::Requested => {
+                ui.spinner();
+                ui.label("Contacting GitHub…");
+                ui.ctx().request_repaint_after(DEFAULT_INTERVAL);
+            }
+            LoginState::Pending { code, started, last_poll, polling } => {
+                let started = *started.get_or_insert(now);
+
+                ui.label("Enter the code");
+                ui.strong(&code.user_code);
+                if ui.small_button("📋").on_hover_text("Copy code").clicked() {
+                    ui.output().copied_text = code.user_code.clone();
+                }
+                ui.label("at");
+                ui.hyperlink(&code.verification_uri);
+
+                // Woken up by the response while polling, and for the next poll otherwise.
+                let wake_up = match *polling {
+                    true => code.interval as f64,
+                    false => (*last_poll + code.interval as f64 - now).max(0.0),
+                };
+
+                let cancelled = ui.button("Cancel").clicked();
+                let expired = now > started + code.expires_in as f64;
+
+                if cancelled {
+                    *state = LoginState::Idle;
+                } else if expired {
+                    *state = LoginState::Failed(String::from("The code expired, please try again"));
+                } else if !*polling && now >= *last_poll + code.interval as f64 {
+                    *polling = true;
+                    *last_poll = now;
+
+                    let (_state, _ctx) = (self.state.clone(), ui.ctx().clone());
+                    github.access_token(CLIENT_ID.unwrap_or_default(), &code.device_code, move |response: Result<AccessToken, String>| {
+                        let mut state = _state.lock().unwrap();
+                        _ctx.request_repaint();
+                        let LoginState::Pending { code, polling, .. } = &mut *state else { return };
+                        *polling = false;
+
+                        match response {
+                            Ok(AccessToken { access_token: Some(access_token), .. }) => *state = LoginState::Authorized(access_token),
+                            Ok(AccessToken { error: Some(error), interval, .. }) if error == "authorization_pending" || error == "slow_down" => {
+                                code.interval = interval.unwrap_or(code.interval);
+                            }
+                            Ok(AccessToken { error, error_description, .. }) => {
+                                *state = LoginState::Failed(error_description.or(error).unwrap_or_default());
+                            }
+                            Err(e) => *state = LoginState::Failed(e),
+                        }
+                    });
+                }
+
+                ui.ctx().request_repaint_after(Duration::from_secs_f64(wake_up));
+            }
+            LoginState::Authorized(access_token) => {
+                *token = access_token.clone();
+                *state = LoginState::Idle;
+            }
+            LoginState::Failed(e) => {
+                ui.colored_label(Color32::from_rgb(255, 100, 100), format!("Sign in failed: {}", e));
+                if ui.button("OK").clicked() {
+                    *state = LoginState::Idle;
+                }
+            }
+        }
+    }
+}
+
+/// github.com answers the device flow without CORS headers, so in the browser it only works
+/// through a backend passing it on.
+fn reaches_device_flow(github: &GitHubApi) -> bool {
+    cfg!(not(target_arch = "wasm32")) || github.base_url != GITHUB_API
+}
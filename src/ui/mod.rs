@@ -1 +1,9 @@
+#[cfg(not(target_arch = "wasm32"))]
+pub mod cassette;
+pub mod charts;
+pub mod config_file;
+pub mod dora;
+pub mod identity;
+pub mod login;
+pub mod reviews;
 pub mod table;
@@ -0,0 +1,139 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use chrono::{Duration, Utc};
+use egui::plot::{Bar, BarChart, Plot};
+use egui::{Color32, Ui};
+
+use crate::analysis::format_duration;
+use crate::analysis::reviews::{review_report, LatencyStats, BUCKETS, HISTORY_PAGES, PER_PAGE};
+use crate::github::pulls::{PullRequest, Review, User};
+
+#[derive(serde::Deserialize, serde::Serialize, Clone)]
+#[serde(default)]
+pub struct ReviewStats {
+    pub weeks: i64,
+}
+
+impl Default for ReviewStats {
+    fn default() -> Self {
+        Self {
+            weeks: 4,
+        }
+    }
+}
+
+impl ReviewStats {
+    pub fn since(&self) -> chrono::DateTime<Utc> {
+        Utc::now() - Duration::weeks(self.weeks)
+    }
+
+    pub fn settings_ui(&mut self, ui: &mut Ui) {
+        ui.add(egui::Slider::new(&mut self.weeks, 1..=26).text("weeks"));
+    }
+
+    pub fn report_ui(
+        &mut self,
+        ui: &mut Ui,
+        pulls: &BTreeMap<String, BTreeMap<i32, PullRequest>>,
+        reviews: &BTreeMap<String, BTreeMap<i32, Vec<Review>>>,
+        members: &[User],
+        truncated: &BTreeSet<String>,
+    ) {
+        use egui_extras::{Column, TableBuilder};
+
+        let report = review_report(pulls, reviews, members, self.since());
+
+        if !truncated.is_empty() {
+            let repos = truncated.iter().cloned().collect::<Vec<_>>().join(", ");
+            ui.colored_label(
+                Color32::from_rgb(255, 100, 100),
+                format!("Only the {} most recently updated pull requests of {} are included, shorten the window to see all of it", HISTORY_PAGES as usize * PER_PAGE, repos),
+            );
+        }
+
+        let latencies = [
+            ("Open pull request age", &report.open_age),
+            ("Time to first review", &report.time_to_first_review),
+            ("Time to merge", &report.time_to_merge),
+        ];
+
+        ui.push_id("latencies", |ui| {
+            TableBuilder::new(ui)
+                .cell_layout(egui::Layout::left_to_right(egui::Align::Center))
+                .column(Column::auto())
+                .column(Column::auto())
+                .column(Column::auto())
+                .column(Column::auto())
+                .header(20.0, |mut header| {
+                    header.col(|ui| { ui.strong(""); });
+                    header.col(|ui| { ui.strong("Pull requests"); });
+                    header.col(|ui| { ui.strong("Median"); });
+                    header.col(|ui| { ui.strong("90th percentile"); });
+                })
+                .body(|mut body| {
+                    latencies.iter().for_each(|(name, stats)| {
+                        body.row(18.0, |mut row| {
+                            row.col(|ui| { ui.label(*name); });
+                            row.col(|ui| { ui.label(format!("{}", stats.count)); });
+                            row.col(|ui| { ui.label(stats.median.map_or(String::from("-"), format_duration)); });
+                            row.col(|ui| { ui.label(stats.p90.map_or(String::from("-"), format_duration)); });
+                        });
+                    });
+                });
+        });
+
+        ui.separator();
+
+        ui.columns(latencies.len(), |columns| {
+            latencies.iter().zip(columns.iter_mut()).for_each(|((name, stats), ui)| {
+                ui.strong(*name);
+                histogram_ui(ui, name, stats);
+            });
+        });
+
+        ui.separator();
+        ui.strong("Reviewer load");
+
+        ui.push_id("reviewers", |ui| {
+            TableBuilder::new(ui)
+                .cell_layout(egui::Layout::left_to_right(egui::Align::Center))
+                .column(Column::auto())
+                .column(Column::auto())
+                .column(Column::auto())
+                .min_scrolled_height(0.0)
+                .header(20.0, |mut header| {
+                    header.col(|ui| { ui.strong("Reviewer"); });
+                    header.col(|ui| { ui.strong("Reviews"); });
+                    header.col(|ui| { ui.strong("Pull requests"); });
+                })
+                .body(|mut body| {
+                    report.reviewers.iter().for_each(|reviewer| {
+                        body.row(18.0, |mut row| {
+                            row.col(|ui| { ui.label(&reviewer.login); });
+                            row.col(|ui| { ui.label(format!("{}", reviewer.reviews)); });
+                            row.col(|ui| { ui.label(format!("{}", reviewer.pull_requests)); });
+                        });
+                    });
+                });
+        });
+    }
+}
+
+fn histogram_ui(ui: &mut Ui, name: &str, stats: &LatencyStats) {
+    let bars = stats.histogram.iter().enumerate()
+        .map(|(i, count)| Bar::new(i as f64, *count as f64).name(BUCKETS[i].0))
+        .collect();
+
+    Plot::new(name)
+        .height(150.0)
+        .allow_drag(false)
+        .allow_zoom(false)
+        .allow_scroll(false)
+        .include_y(0.0)
+        .x_axis_formatter(|x, _| {
+            let i = x.round();
+            if (x - i).abs() > f64::EPSILON || i < 0.0 { return String::new(); }
+            BUCKETS.get(i as usize).map_or(String::new(), |(label, _)| label.to_string())
+        })
+        .show(ui, |plot_ui| plot_ui.bar_chart(BarChart::new(bars).width(0.7)));
+}
@@ -2,26 +2,17 @@ use std::collections::BTreeMap;
 
 use egui::{Color32, TextFormat, Ui};
 
-use crate::github::pulls::PullRequest;
-use crate::github::runs::WorkflowRuns;
-use crate::github::runs::WorkflowRun;
+use crate::analysis::flaky::Flakiness;
+use crate::forge::{ChangeRequest, PipelineRun};
 
-#[derive(serde::Deserialize, serde::Serialize)]
+#[derive(serde::Deserialize, serde::Serialize, Clone, Default)]
 #[serde(default)]
 pub struct Table {
     striped: bool,
 }
 
-impl Default for Table {
-    fn default() -> Self {
-        Self {
-            striped: false,
-        }
-    }
-}
-
 impl Table {
-    pub fn pull_requests_ui(&mut self, ui: &mut Ui, pulls: &BTreeMap<String, Vec<PullRequest>>) {
+    pub fn pull_requests_ui(&mut self, ui: &mut Ui, pulls: &BTreeMap<String, Vec<ChangeRequest>>) {
         use egui_extras::{Column, TableBuilder};
 
         let table = TableBuilder::new(ui)
@@ -30,6 +21,9 @@ impl Table {
             .column(Column::auto().resizable(true).clip(true))
             .column(Column::auto())
             .column(Column::auto())
+            .column(Column::auto())
+            .column(Column::auto())
+            .column(Column::auto().clip(true))
             .min_scrolled_height(0.0);
 
         table.header(20.0, |mut header| {
@@ -37,25 +31,41 @@ impl Table {
             header.col(|ui| { ui.strong("Title"); });
             header.col(|ui| { ui.strong("Last Update"); });
             header.col(|ui| { ui.strong("Author"); });
+            header.col(|ui| { ui.strong("Review"); });
+            header.col(|ui| { ui.strong("Checks"); });
+            header.col(|ui| { ui.strong("Labels"); });
         })
             .body(|mut body| {
-                for (name, prs) in pulls.into_iter() {
+                for (name, prs) in pulls.iter() {
                     if !prs.is_empty() {
                         body.row(40.0, |mut row| {
                             row.col(|ui| { ui.heading(""); });
                             row.col(|ui| { ui.heading(name); });
                             row.col(|ui| { ui.heading(""); });
                             row.col(|ui| { ui.heading(""); });
+                            row.col(|ui| { ui.heading(""); });
+                            row.col(|ui| { ui.heading(""); });
+                            row.col(|ui| { ui.heading(""); });
                         });
                     }
 
-                    prs.into_iter().for_each(|pr| {
-                        let _pr = pr.clone();
+                    prs.iter().for_each(|pr| {
                         body.row(18.0, |mut row| {
-                            row.col(|ui| { ui.label(format!("{}", &_pr.number)); });
-                            row.col(|ui| { ui.hyperlink_to(&_pr.title.unwrap(), &_pr.html_url.unwrap()); });
-                            row.col(|ui| { ui.label(&_pr.updated_at.unwrap()); });
-                            row.col(|ui| { ui.label(&_pr.user.unwrap().login); });
+                            row.col(|ui| { ui.label(format!("{}", &pr.number)); });
+                            row.col(|ui| { ui.hyperlink_to(&pr.title, &pr.url); });
+                            row.col(|ui| { ui.label(pr.updated_at.clone().unwrap_or_default()); });
+                            row.col(|ui| { ui.label(&pr.author); });
+                            row.col(|ui| { ui.label(pr.review_decision.clone().unwrap_or_default().to_lowercase().replace('_', " ")); });
+                            row.col(|ui| {
+                                let checks = pr.checks.clone().unwrap_or_default().to_lowercase();
+                                let color = match checks.as_str() {
+                                    "success" => Color32::from_rgb(100, 255, 146),
+                                    "failure" | "error" => Color32::from_rgb(255, 100, 100),
+                                    _ => ui.visuals().text_color(),
+                                };
+                                ui.colored_label(color, checks);
+                            });
+                            row.col(|ui| { ui.label(pr.labels.join(", ")); });
                         });
                     });
                 }
@@ -65,7 +75,7 @@ impl Table {
     pub fn workflow_runs_ui(
         &mut self,
         ui: &mut Ui,
-        repo_with_runs: &BTreeMap<String, WorkflowRuns>,
+        repo_with_runs: &BTreeMap<String, Vec<PipelineRun>>,
     ) {
         use egui_extras::{Column, TableBuilder};
 
@@ -87,7 +97,7 @@ impl Table {
             header.col(|ui| { ui.strong("Attempts"); });
             header.col(|ui| { ui.strong("Timestamp"); });
         }).body(|mut body| {
-            for (repo_name, runs) in repo_with_runs.into_iter() {
+            for (repo_name, runs) in repo_with_runs.iter() {
                 body.row(40.0, |mut row| {
                     row.col(|ui| { ui.heading(repo_name); });
                     row.col(|ui| { ui.heading(""); });
@@ -97,8 +107,8 @@ impl Table {
                     row.col(|ui| { ui.heading(""); });
                 });
 
-                let group_by_workflow_id = runs.workflow_runs.clone().into_iter().fold(BTreeMap::new(), |mut acc: BTreeMap<i64, Vec<WorkflowRun>>, wr| {
-                    acc.entry(wr.workflow_id).or_default().push(wr);
+                let group_by_workflow_id = runs.clone().into_iter().fold(BTreeMap::new(), |mut acc: BTreeMap<String, Vec<PipelineRun>>, wr| {
+                    acc.entry(wr.definition_id.clone()).or_default().push(wr);
                     acc
                 });
 
@@ -106,9 +116,9 @@ impl Table {
                     workflow_runs.into_iter().take(1).for_each(|workflow_run| {
                         let run = workflow_run.clone();
                         body.row(18.0, |mut row| {
-                            row.col(|ui| { ui.label(&run.name.unwrap_or(String::new())); });
+                            row.col(|ui| { ui.label(&run.name); });
                             row.col(|ui| { ui.label(&run.event); });
-                            row.col(|ui| { ui.label(&run.status.unwrap_or(String::new())); });
+                            row.col(|ui| { ui.label(run.status.unwrap_or(String::new())); });
 
                             let conclusion = &run.conclusion.unwrap_or(String::new());
                             row.col(|ui| {
@@ -121,12 +131,59 @@ impl Table {
                                 ui.label(job);
                             });
 
-                            row.col(|ui| { ui.label(format!("{}", &run.run_attempt)); });
-                            row.col(|ui| { ui.label(&run.run_started_at.unwrap_or(String::new())); });
+                            row.col(|ui| { ui.label(format!("{}", &run.attempt)); });
+                            row.col(|ui| { ui.label(run.started_at.unwrap_or(String::new())); });
                         });
                     });
                 });
             };
         })
     }
+
+    pub fn flaky_workflows_ui(&mut self, ui: &mut Ui, report: &[Flakiness]) {
+        use egui_extras::{Column, TableBuilder};
+
+        let table = TableBuilder::new(ui)
+            .cell_layout(egui::Layout::left_to_right(egui::Align::Center))
+            .column(Column::auto())
+            .column(Column::auto().resizable(true).clip(true))
+            .column(Column::auto())
+            .column(Column::auto())
+            .column(Column::auto())
+            .column(Column::auto())
+            .column(Column::auto().resizable(true).clip(true))
+            .min_scrolled_height(0.0);
+
+        table.header(20.0, |mut header| {
+            header.col(|ui| { ui.strong("Repository"); });
+            header.col(|ui| { ui.strong("Workflow"); });
+            header.col(|ui| { ui.strong("Flake rate"); });
+            header.col(|ui| { ui.strong("Flaky commits"); });
+            header.col(|ui| { ui.strong("Failed commits"); });
+            header.col(|ui| { ui.strong("Commits"); });
+            header.col(|ui| { ui.strong("Failing jobs"); });
+        }).body(|mut body| {
+            report.iter().for_each(|flakiness| {
+                body.row(18.0, |mut row| {
+                    row.col(|ui| { ui.label(&flakiness.repo); });
+                    row.col(|ui| {
+                        match &flakiness.latest_flake_url {
+                            Some(url) => ui.hyperlink_to(&flakiness.workflow, url),
+                            None => ui.label(&flakiness.workflow),
+                        };
+                    });
+                    row.col(|ui| { ui.label(format!("{:.0}%", 100.0 * flakiness.flake_rate())); });
+                    row.col(|ui| { ui.label(format!("{}", flakiness.flaky_commits)); });
+                    row.col(|ui| { ui.label(format!("{}", flakiness.failed_commits)); });
+                    row.col(|ui| { ui.label(format!("{}", flakiness.commits)); });
+                    row.col(|ui| {
+                        let jobs = flakiness.flakiest_jobs().iter()
+                            .map(|(job, count)| format!("{} ({})", job, count))
+                            .collect::<Vec<_>>();
+                        ui.label(jobs.join(", "));
+                    });
+                });
+            });
+        })
+    }
 }
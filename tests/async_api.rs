@@ -0,0 +1,108 @@
+//! The `async` methods of `GitHubApi`, composed and awaited on the executor of `futures` against
+//! the mock server in `common`.
+// The mock server needs sockets and threads, which the browser has neither of.
+#![cfg(not(target_arch = "wasm32"))]
+
+mod common;
+
+use futures::executor::block_on;
+use futures::future::join_all;
+
+use common::{MockGitHub, Route, TOKEN};
+
+#[test]
+fn pull_requests_are_awaited() {
+    let server = MockGitHub::start(vec![Route::fixture("/repos/navikt/aap-api/pulls", "pulls.json")]);
+
+    let pulls = block_on(server.github().fetch_pull_requests(TOKEN, "aap-api")).expect("pull requests");
+
+    assert_eq!(pulls.iter().map(|pr| pr.number).collect::<Vec<_>>(), vec![101, 102]);
+    assert_eq!(server.requests()[0].authorization.as_deref(), Some("Bearer ghp_test"));
+}
+
+#[test]
+fn errors_are_returned_instead_of_printed() {
+    let server = MockGitHub::start(vec![
+        Route::fixture("/repos/navikt/aap-api/pulls", "bad_credentials.json").status(401),
+    ]);
+
+    let error = block_on(server.github().fetch_pull_requests(TOKEN, "aap-api")).expect_err("unauthorized");
+
+    assert!(error.contains("401"), "{}", error);
+}
+
+#[test]
+fn default_branch_is_read_from_the_repository() {
+    let server = MockGitHub::start(vec![Route::fixture("/repos/navikt/aap-api", "repository.json")]);
+
+    let branch = block_on(server.github().fetch_default_branch(TOKEN, "aap-api")).expect("default branch");
+
+    assert_eq!(branch, "main");
+}
+
+#[test]
+fn jobs_of_an_attempt_are_parsed() {
+    let server = MockGitHub::start(vec![Route::fixture("/repos/navikt/aap-api/actions/runs/9003/attempts/1/jobs", "jobs.json")]);
+
+    let jobs = block_on(server.github().fetch_attempt_jobs(TOKEN, "aap-api", 9003, 1)).expect("jobs");
+
+    assert_eq!(jobs.iter().filter(|job| job.is_failure()).map(|job| job.name.as_str()).collect::<Vec<_>>(), vec!["test"]);
+    assert!(server.requests()[0].url.ends_with("per_page=100"));
+}
+
+#[test]
+fn reviews_of_each_pull_request_follow_the_pull_requests() {
+    let server = MockGitHub::start(vec![
+        Route::fixture("/repos/navikt/aap-api/pulls", "pulls.json"),
+        Route::fixture("/repos/navikt/aap-api/pulls/101/reviews", "reviews.json"),
+        Route::fixture("/repos/navikt/aap-api/pulls/102/reviews", "reviews.json"),
+    ]);
+    let github = server.github();
+
+    let reviews = block_on(async {
+        let pulls = github.fetch_pull_requests(TOKEN, "aap-api").await?;
+        let reviews = join_all(pulls.iter().map(|pr| github.fetch_reviews(TOKEN, "aap-api", pr.number))).await;
+        reviews.into_iter().collect::<Result<Vec<_>, String>>()
+    }).expect("reviews");
+
+    assert_eq!(reviews.len(), 2);
+    assert_eq!(server.requests().len(), 3);
+}
+
+#[test]
+fn runs_of_failing_workflows_are_fetched_after_the_latest_runs() {
+    let server = MockGitHub::start(vec![
+        Route::fixture("/repos/navikt/aap-api/actions/runs", "runs.json"),
+        Route::fixture("/repos/navikt/aap-api/actions/workflows", "workflows.json"),
+        Route::fixture("/repos/navikt/aap-api/actions/workflows/77/runs", "workflow_runs.json"),
+        Route::fixture("/repos/navikt/aap-api/actions/workflows/78/runs", "workflow_runs.json"),
+    ]);
+    let github = &server.github();
+
+    let history = block_on(async {
+        let (runs, workflows) = futures::join!(
+            github.fetch_runs(TOKEN, "aap-api"),
+            github.fetch_workflows(TOKEN, "aap-api"),
+        );
+        let failing = runs?.workflow_runs.into_iter()
+            .filter(|run| run.is_failure())
+            .map(|run| run.workflow_id)
+            .collect::<Vec<_>>();
+        let histories = workflows?.into_iter()
+            .filter(|workflow| failing.contains(&workflow.id))
+            .map(|workflow| async move { github.fetch_workflow_runs(TOKEN, "aap-api", &workflow.id.to_string(), None, 1).await });
+        join_all(histories).await.into_iter().collect::<Result<Vec<_>, String>>()
+    }).expect("history of failing workflows");
+
+    assert!(!history.is_empty());
+    assert!(server.requests().iter().any(|request| request.url.contains("/actions/workflows/") && request.url.contains("/runs")));
+}
+
+#[test]
+fn team_members_are_awaited() {
+    let server = MockGitHub::start(vec![Route::fixture("/orgs/navikt/teams/aap/members", "team_members.json")]);
+
+    let members = block_on(server.github().fetch_team_members(TOKEN, "aap")).expect("members");
+
+    assert!(!members.is_empty());
+}
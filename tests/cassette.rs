@@ -0,0 +1,109 @@
+//! Records traffic from the mock server in `common`, and replays it once the server is gone.
+// The mock server needs sockets and threads, which the browser has neither of.
+#![cfg(not(target_arch = "wasm32"))]
+
+mod common;
+
+use aap_status::github::cassette::{Cassette, Interaction, Mode};
+use aap_status::github::github_client::{GitHubApi, Pulls, Runs};
+use aap_status::github::pulls::PullRequest;
+use aap_status::github::runs::WorkflowRuns;
+
+use common::{channel, token, MockGitHub, Route, TIMEOUT, TOKEN};
+
+fn record(cassette: &Cassette) -> GitHubApi {
+    let server = MockGitHub::start(vec![
+        Route::fixture("/repos/navikt/aap-api/pulls", "pulls.json"),
+        Route::fixture("/repos/navikt/aap-api/actions/runs", "runs.json"),
+    ]);
+    let github = GitHubApi { cassette: cassette.clone(), ..server.github() };
+    cassette.record();
+
+    let (callback, receiver) = channel::<Vec<PullRequest>>();
+    github.pull_requests(&mut token(), "aap-api", callback);
+    receiver.recv_timeout(TIMEOUT).expect("pull requests");
+
+    let (callback, receiver) = channel::<WorkflowRuns>();
+    github.runs(&mut token(), "aap-api", callback);
+    receiver.recv_timeout(TIMEOUT).expect("runs");
+
+    github
+}
+
+#[test]
+fn recorded_responses_are_replayed_without_network() {
+    let cassette = Cassette::default();
+    let github = record(&cassette);
+    assert_eq!(cassette.len(), 2);
+
+    cassette.replay(cassette.interactions());
+
+    let (callback, receiver) = channel::<Vec<PullRequest>>();
+    github.pull_requests(&mut token(), "aap-api", callback);
+    let pulls = receiver.recv_timeout(TIMEOUT).expect("replayed pull requests");
+    assert_eq!(pulls.iter().map(|pr| pr.number).collect::<Vec<_>>(), vec![101, 102]);
+
+    let (callback, receiver) = channel::<WorkflowRuns>();
+    github.runs(&mut token(), "aap-api", callback);
+    assert_eq!(receiver.recv_timeout(TIMEOUT).expect("replayed runs").workflow_runs.len(), 3);
+
+    // Requests are answered from the last recorded response as often as they are made.
+    let (callback, receiver) = channel::<Vec<PullRequest>>();
+    github.pull_requests(&mut token(), "aap-api", callback);
+    assert_eq!(receiver.recv_timeout(TIMEOUT).expect("pull requests again").len(), 2);
+}
+
+#[test]
+fn requests_missing_from_the_cassette_fail() {
+    let cassette = Cassette::default();
+    cassette.replay(vec![]);
+    let github = GitHubApi { cassette: cassette.clone(), ..GitHubApi::default() };
+
+    let (callback, receiver) = channel::<Vec<PullRequest>>();
+    github.pull_requests(&mut token(), "aap-api", callback);
+
+    assert!(receiver.recv_timeout(TIMEOUT / 10).is_err());
+    assert_eq!(cassette.mode(), Mode::Replay);
+}
+
+#[test]
+fn cassettes_are_saved_without_the_token() {
+    let cassette = Cassette::default();
+    record(&cassette);
+
+    let path = std::env::temp_dir().join(format!("aap_status_{}.cassette.json", std::process::id()));
+    let path = path.to_str().expect("utf-8 path");
+    assert_eq!(cassette.save(path), Ok(2));
+
+    let content = std::fs::read_to_string(path).expect("cassette");
+    let _ = std::fs::remove_file(path);
+    assert!(!content.contains(TOKEN));
+
+    let interactions: Vec<aap_status::github::cassette::Interaction> = serde_json::from_str(&content).expect("json");
+    assert_eq!(interactions.len(), 2);
+    assert!(interactions.iter().all(|interaction| interaction.status == 200));
+}
+
+#[test]
+fn posts_to_the_same_url_are_told_apart_by_their_body() {
+    let interaction = |query: &str, body: &str| Interaction {
+        method: String::from("POST"),
+        url: String::from("https://api.github.com/graphql"),
+        request_body: query.to_string(),
+        status: 200,
+        status_text: String::from("OK"),
+        headers: Default::default(),
+        body: body.to_string(),
+    };
+    let cassette = Cassette::default();
+    cassette.replay(vec![interaction("{ a }", "first"), interaction("{ b }", "second")]);
+
+    let post = |query: &str| {
+        let request = ehttp::Request::post("https://api.github.com/graphql", query.as_bytes().to_vec());
+        let response = futures::executor::block_on(cassette.fetch_async(request)).expect("replayed");
+        String::from_utf8(response.bytes).expect("utf-8")
+    };
+    assert_eq!(post("{ b }"), "second");
+    assert_eq!(post("{ a }"), "first");
+    assert!(futures::executor::block_on(cassette.fetch_async(ehttp::Request::post("https://api.github.com/graphql", b"{ c }".to_vec()))).is_err());
+}
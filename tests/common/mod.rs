@@ -0,0 +1,242 @@
+//! A stand-in for the GitHub API serving recorded responses from `tests/fixtures`, so the
+//! fetchers can be tested without network or a token.
+// Each test crate compiles its own copy and uses only part of it.
+#![allow(dead_code)]
+
+use std::collections::BTreeMap;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use aap_status::github::github_client::GitHubApi;
+
+/// How long to wait for a callback before concluding it won't be called.
+pub const TIMEOUT: Duration = Duration::from_secs(5);
+
+pub const TOKEN: &str = "ghp_test";
+
+/// What to answer for requests to a path, when all query parameters of the route are present.
+pub struct Route {
+    path: String,
+    query: BTreeMap<String, String>,
+    status: u16,
+    body: String,
+    headers: Vec<(String, String)>,
+    delay: Duration,
+}
+
+impl Route {
+    /// Responds with a fixture, e.g. `fixture("/repos/navikt/aap-api/pulls", "pulls.json")`.
+    /// A query in `path` only matches requests with those parameters.
+    pub fn fixture(path: &str, fixture: &str) -> Self {
+        let (path, query) = path.split_once('?').unwrap_or((path, ""));
+        Self {
+            path: path.to_string(),
+            query: parse_query(query),
+            status: 200,
+            body: std::fs::read_to_string(format!("{}/tests/fixtures/{}", env!("CARGO_MANIFEST_DIR"), fixture))
+                .unwrap_or_else(|e| panic!("fixture {}: {}", fixture, e)),
+            headers: vec![(String::from("Content-Type"), String::from("application/json; charset=utf-8"))],
+            delay: Duration::ZERO,
+        }
+    }
+
+    pub fn status(mut self, status: u16) -> Self {
+        self.status = status;
+        self
+    }
+
+    /// `{base}` in the value is replaced with the URL of the server, for `Link` headers.
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// Waits before answering, to keep requests in flight for a while.
+    pub fn delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    /// Answers 304 Not Modified without a body, like GitHub does for a matching `If-None-Match`.
+    pub fn not_modified(path: &str) -> Self {
+        let (path, query) = path.split_once('?').unwrap_or((path, ""));
+        Self {
+            path: path.to_string(),
+            query: parse_query(query),
+            status: 304,
+            body: String::new(),
+            headers: vec![(String::from("ETag"), String::from("\"fixture\""))],
+            delay: Duration::ZERO,
+        }
+    }
+
+    fn matches(&self, path: &str, query: &BTreeMap<String, String>) -> bool {
+        self.path == path && self.query.iter().all(|(key, value)| query.get(key) == Some(value))
+    }
+}
+
+/// A request received by the server.
+#[derive(Clone, Debug)]
+pub struct Recorded {
+    pub method: String,
+    pub url: String,
+    pub authorization: Option<String>,
+}
+
+pub struct MockGitHub {
+    pub base_url: String,
+    stopped: Arc<AtomicBool>,
+    requests: Arc<Mutex<Vec<Recorded>>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl MockGitHub {
+    /// Listens on a free port until dropped. Requests without a route get GitHub's 404.
+    ///
+    /// Every connection is closed after one response. The client keeps connections alive
+    /// otherwise, and a pooled server can run out of threads to serve the next ones with.
+    pub fn start(routes: Vec<Route>) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("mock server");
+        let port = listener.local_addr().expect("address").port();
+        let base_url = format!("http://127.0.0.1:{}", port);
+        let stopped = Arc::new(AtomicBool::new(false));
+        let requests = Arc::new(Mutex::new(vec![]));
+
+        let routes = Arc::new(routes);
+        let _stopped = stopped.clone();
+        let _requests = requests.clone();
+        let _base_url = base_url.clone();
+        let thread = std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                if _stopped.load(Ordering::Acquire) {
+                    break;
+                }
+                let Ok(stream) = stream else { continue };
+
+                let routes = routes.clone();
+                let requests = _requests.clone();
+                let base_url = _base_url.clone();
+                std::thread::spawn(move || {
+                    if let Some(request) = read_request(&stream) {
+                        requests.lock().unwrap().push(request.clone());
+                        respond(stream, &request, &routes, &base_url);
+                    }
+                });
+            }
+        });
+
+        Self { base_url, stopped, requests, thread: Some(thread) }
+    }
+
+    /// A client for this server, for the default organisation.
+    pub fn github(&self) -> GitHubApi {
+        GitHubApi {
+            base_url: self.base_url.clone(),
+            ..GitHubApi::default()
+        }
+    }
+
+    pub fn requests(&self) -> Vec<Recorded> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+impl Drop for MockGitHub {
+    fn drop(&mut self) {
+        // Wakes up the listener so it sees that it should stop.
+        self.stopped.store(true, Ordering::Release);
+        let _ = TcpStream::connect(self.base_url.trim_start_matches("http://"));
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+fn read_request(stream: &TcpStream) -> Option<Recorded> {
+    let mut reader = BufReader::new(stream);
+
+    let mut line = String::new();
+    reader.read_line(&mut line).ok()?;
+    let mut parts = line.split_whitespace();
+    let method = parts.next()?.to_string();
+    let url = parts.next()?.to_string();
+
+    let mut authorization = None;
+    let mut content_length = 0;
+    loop {
+        let mut line = String::new();
+        reader.read_line(&mut line).ok()?;
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            match name.to_ascii_lowercase().as_str() {
+                "authorization" => authorization = Some(value.trim().to_string()),
+                "content-length" => content_length = value.trim().parse().unwrap_or(0),
+                _ => {}
+            }
+        }
+    }
+
+    // The body isn't used, but has to be read for the client to take the response.
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body).ok()?;
+
+    Some(Recorded { method, url, authorization })
+}
+
+fn respond(mut stream: TcpStream, request: &Recorded, routes: &[Route], base_url: &str) {
+    let (path, query) = request.url.split_once('?').unwrap_or((&request.url, ""));
+    let query = parse_query(query);
+    let (status, body, headers) = match routes.iter().find(|route| route.matches(path, &query)) {
+        Some(route) => {
+            std::thread::sleep(route.delay);
+            (route.status, route.body.as_str(), route.headers.as_slice())
+        }
+        None => (404, r#"{"message":"Not Found"}"#, &[][..]),
+    };
+    let headers = headers.iter()
+        .map(|(name, value)| format!("{}: {}\r\n", name, value.replace("{base}", base_url)))
+        .collect::<String>();
+
+    let _ = write!(
+        stream,
+        "HTTP/1.1 {} {}\r\nContent-Length: {}\r\nConnection: close\r\n{}\r\n{}",
+        status, reason(status), body.len(), headers, body,
+    );
+    let _ = stream.flush();
+}
+
+/// A callback for the fetchers, and where to receive what it was called with.
+pub fn channel<T: 'static + Send>() -> (impl 'static + Send + FnOnce(T), Receiver<T>) {
+    let (sender, receiver) = mpsc::channel();
+    (move |value: T| { let _ = sender.send(value); }, receiver)
+}
+
+pub fn token() -> String {
+    TOKEN.to_string()
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        304 => "Not Modified",
+        401 => "Unauthorized",
+        403 => "Forbidden",
+        404 => "Not Found",
+        _ => "",
+    }
+}
+
+fn parse_query(query: &str) -> BTreeMap<String, String> {
+    query.split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect()
+}
@@ -0,0 +1,313 @@
+//! Drives the GitHub, GitLab and Forgejo providers against the mock server in `common`, checking that both
+//! end up as the same forge-neutral types.
+// The mock server needs sockets and threads, which the browser has neither of.
+#![cfg(not(target_arch = "wasm32"))]
+
+mod common;
+
+use std::collections::BTreeMap;
+
+use aap_status::forge::forgejo::ForgejoApi;
+use aap_status::forge::gitlab::GitLabApi;
+use aap_status::forge::{ChangeRequest, Forge, Forges, Group, PipelineDefinition, PipelineRun, Provider};
+use aap_status::github::github_client::Pulls;
+use aap_status::github::pulls::PullRequest;
+
+use common::{channel, token, MockGitHub, Route, TIMEOUT};
+
+fn gitlab(server: &MockGitHub) -> GitLabApi {
+    GitLabApi {
+        base_url: server.base_url.clone(),
+        group: String::from("navikt"),
+        ..GitLabApi::default()
+    }
+}
+
+fn forgejo(server: &MockGitHub) -> ForgejoApi {
+    ForgejoApi {
+        base_url: format!("{}/api/v1", server.base_url),
+        owner: String::from("navikt"),
+        ..ForgejoApi::default()
+    }
+}
+
+#[test]
+fn merge_requests_are_change_requests() {
+    let server = MockGitHub::start(vec![
+        Route::fixture("/projects/navikt%2Faap-api/merge_requests?state=opened", "gitlab_merge_requests.json"),
+    ]);
+    let (callback, receiver) = channel::<Vec<ChangeRequest>>();
+
+    gitlab(&server).change_requests(&mut token(), "aap-api", callback);
+    let change_requests = receiver.recv_timeout(TIMEOUT).expect("merge requests");
+
+    assert_eq!(change_requests.iter().map(|cr| cr.number).collect::<Vec<_>>(), vec![7, 8]);
+    assert_eq!(change_requests[0].author, "alice");
+    assert_eq!(change_requests[0].reviewers, vec!["bob"]);
+    assert!(change_requests[1].reviewers.is_empty());
+    assert_eq!(server.requests()[0].authorization.as_deref(), Some("Bearer ghp_test"));
+}
+
+#[test]
+fn pipeline_statuses_are_mapped_to_those_of_actions() {
+    let server = MockGitHub::start(vec![
+        Route::fixture("/projects/navikt%2Faap-api/pipelines", "gitlab_pipelines.json"),
+    ]);
+    let (callback, receiver) = channel::<Vec<PipelineRun>>();
+
+    gitlab(&server).pipeline_runs(&mut token(), "aap-api", callback);
+    let runs = receiver.recv_timeout(TIMEOUT).expect("pipelines");
+
+    let statuses = runs.iter()
+        .map(|run| (run.status.as_deref(), run.conclusion.as_deref()))
+        .collect::<Vec<_>>();
+    assert_eq!(statuses, vec![
+        (Some("in_progress"), None),
+        (Some("completed"), Some("failure")),
+        (Some("completed"), Some("cancelled")),
+    ]);
+    assert!(runs.iter().all(|run| run.definition_id == ".gitlab-ci.yml"));
+    assert_eq!(runs[2].event, "merge_request_event");
+    assert_eq!(runs[2].branch.as_deref(), Some("feature"));
+}
+
+#[test]
+fn projects_in_nested_groups_are_found() {
+    let server = MockGitHub::start(vec![
+        Route::fixture("/projects/navikt%2Faap%2Faap-api/pipelines", "gitlab_pipelines.json"),
+    ]);
+    let gitlab = GitLabApi { group: String::from("navikt/aap/"), ..gitlab(&server) };
+    let (callback, receiver) = channel::<Vec<PipelineRun>>();
+
+    gitlab.pipeline_runs(&mut token(), "aap-api", callback);
+
+    assert_eq!(receiver.recv_timeout(TIMEOUT).expect("pipelines").len(), 3);
+}
+
+#[test]
+fn subgroups_are_groups() {
+    let server = MockGitHub::start(vec![
+        Route::fixture("/groups/navikt/subgroups", "gitlab_subgroups.json"),
+    ]);
+    let (callback, receiver) = channel::<Vec<Group>>();
+
+    gitlab(&server).groups(&mut token(), callback);
+    let groups = receiver.recv_timeout(TIMEOUT).expect("groups");
+
+    assert_eq!(groups.len(), 1);
+    assert_eq!(groups[0].slug, "navikt/aap");
+}
+
+#[test]
+fn gitlab_has_a_single_pipeline_definition() {
+    let (callback, receiver) = channel::<Vec<PipelineDefinition>>();
+
+    GitLabApi::default().pipeline_definitions(&mut token(), "aap-api", callback);
+
+    let definitions = receiver.recv_timeout(TIMEOUT).expect("definitions");
+    assert_eq!(definitions.iter().map(|d| d.path.as_str()).collect::<Vec<_>>(), vec![".gitlab-ci.yml"]);
+}
+
+#[test]
+fn github_provides_the_same_types() {
+    let server = MockGitHub::start(vec![
+        Route::fixture("/repos/navikt/aap-api/pulls", "pulls.json"),
+        Route::fixture("/repos/navikt/aap-api/actions/runs", "runs.json"),
+        Route::fixture("/repos/navikt/aap-api/actions/workflows", "workflows.json"),
+        Route::fixture("/orgs/navikt/teams", "teams_page1.json"),
+    ]);
+    let github = server.github();
+
+    let (callback, receiver) = channel::<Vec<ChangeRequest>>();
+    github.change_requests(&mut token(), "aap-api", callback);
+    let change_requests = receiver.recv_timeout(TIMEOUT).expect("change requests");
+    assert_eq!(change_requests.iter().map(|cr| cr.number).collect::<Vec<_>>(), vec![101, 102]);
+    assert_eq!(change_requests[0].reviewers, vec!["bob"]);
+
+    let (callback, receiver) = channel::<Vec<PipelineRun>>();
+    github.pipeline_runs(&mut token(), "aap-api", callback);
+    assert_eq!(receiver.recv_timeout(TIMEOUT).expect("runs").len(), 3);
+
+    let (callback, receiver) = channel::<Vec<PipelineDefinition>>();
+    github.pipeline_definitions(&mut token(), "aap-api", callback);
+    let definitions = receiver.recv_timeout(TIMEOUT).expect("workflows");
+    assert_eq!(definitions.iter().map(|d| d.id.as_str()).collect::<Vec<_>>(), vec!["77", "78"]);
+
+    let (callback, receiver) = channel::<Vec<Group>>();
+    github.groups(&mut token(), callback);
+    assert!(!receiver.recv_timeout(TIMEOUT).expect("teams").is_empty());
+}
+
+#[test]
+fn each_repository_is_shown_from_its_own_forge() {
+    let server = MockGitHub::start(vec![Route::fixture("/repos/navikt/aap-api/pulls", "pulls.json")]);
+    let (callback, receiver) = channel::<Vec<PullRequest>>();
+    server.github().pull_requests(&mut token(), "aap-api", callback);
+    let pulls = receiver.recv_timeout(TIMEOUT).expect("pull requests");
+
+    let mut forges = Forges::default();
+    forges.set("aap-gitlab", Forge::GitLab);
+    // Left over from before the repository moved.
+    forges.set("aap-moved", Forge::GitLab);
+
+    let github = BTreeMap::from([
+        (String::from("aap-api"), pulls.clone()),
+        (String::from("aap-moved"), pulls),
+    ]);
+    let merge_request = ChangeRequest {
+        number: 7,
+        title: String::from("Bump ktor"),
+        url: String::new(),
+        author: String::from("alice"),
+        created_at: None,
+        updated_at: None,
+        reviewers: vec![],
+        labels: vec![],
+        review_decision: None,
+        checks: None,
+    };
+    let others = BTreeMap::from([
+        (String::from("aap-gitlab"), vec![merge_request.clone()]),
+        (String::from("aap-moved"), vec![merge_request]),
+    ]);
+
+    let shown = forges.all_change_requests(&github, &BTreeMap::new(), &others);
+
+    assert_eq!(shown.keys().collect::<Vec<_>>(), vec!["aap-api", "aap-gitlab", "aap-moved"]);
+    assert_eq!(shown["aap-api"].len(), 2);
+    assert_eq!(shown["aap-moved"].iter().map(|cr| cr.number).collect::<Vec<_>>(), vec![7]);
+
+    forges.set("aap-moved", Forge::GitHub);
+    assert_eq!(forges.of("aap-moved"), Forge::GitHub);
+    assert!(!forges.repositories.contains_key("aap-moved"));
+}
+
+#[test]
+fn forgejo_pull_requests_are_change_requests() {
+    let server = MockGitHub::start(vec![
+        Route::fixture("/api/v1/repos/navikt/aap-tools/pulls?state=open", "forgejo_pulls.json"),
+    ]);
+    let (callback, receiver) = channel::<Vec<ChangeRequest>>();
+
+    forgejo(&server).change_requests(&mut token(), "aap-tools", callback);
+    let change_requests = receiver.recv_timeout(TIMEOUT).expect("pull requests");
+
+    assert_eq!(change_requests.iter().map(|cr| cr.number).collect::<Vec<_>>(), vec![12, 13]);
+    assert_eq!(change_requests[0].reviewers, vec!["bob"]);
+    assert!(change_requests[1].reviewers.is_empty());
+    assert_eq!(server.requests()[0].authorization.as_deref(), Some("token ghp_test"));
+}
+
+#[test]
+fn forgejo_jobs_are_shown_once_per_run() {
+    let server = MockGitHub::start(vec![
+        Route::fixture("/api/v1/repos/navikt/aap-tools/actions/tasks", "forgejo_tasks.json"),
+    ]);
+    let (callback, receiver) = channel::<Vec<PipelineRun>>();
+
+    forgejo(&server).pipeline_runs(&mut token(), "aap-tools", callback);
+    let runs = receiver.recv_timeout(TIMEOUT).expect("runs");
+
+    assert_eq!(runs.iter().map(|run| run.id).collect::<Vec<_>>(), vec![904, 902, 901]);
+    let statuses = runs.iter()
+        .map(|run| (run.status.as_deref(), run.conclusion.as_deref()))
+        .collect::<Vec<_>>();
+    assert_eq!(statuses, vec![
+        (Some("in_progress"), None),
+        (Some("completed"), Some("failure")),
+        (Some("completed"), Some("cancelled")),
+    ]);
+    assert_eq!(runs[1].name, "deploy");
+    assert_eq!(runs[1].definition_id, "deploy.yaml");
+}
+
+#[test]
+fn forgejo_runs_fail_if_any_job_failed() {
+    let server = MockGitHub::start(vec![
+        Route::fixture("/api/v1/repos/navikt/aap-tools/actions/tasks", "forgejo_tasks_mixed.json"),
+    ]);
+    let (callback, receiver) = channel::<Vec<PipelineRun>>();
+
+    forgejo(&server).pipeline_runs(&mut token(), "aap-tools", callback);
+    let runs = receiver.recv_timeout(TIMEOUT).expect("runs");
+
+    let statuses = runs.iter()
+        .map(|run| (run.id, run.status.as_deref(), run.conclusion.as_deref()))
+        .collect::<Vec<_>>();
+    assert_eq!(statuses, vec![
+        (916, Some("completed"), Some("success")),
+        (914, Some("waiting"), None),
+        (912, Some("completed"), Some("failure")),
+    ]);
+    assert_eq!(runs[2].started_at.as_deref(), Some("2023-02-03T12:00:05Z"));
+}
+
+#[test]
+fn forgejo_workflows_are_those_with_runs() {
+    let server = MockGitHub::start(vec![
+        Route::fixture("/api/v1/repos/navikt/aap-tools/actions/tasks", "forgejo_tasks.json"),
+    ]);
+    let (callback, receiver) = channel::<Vec<PipelineDefinition>>();
+
+    forgejo(&server).pipeline_definitions(&mut token(), "aap-tools", callback);
+    let definitions = receiver.recv_timeout(TIMEOUT).expect("workflows");
+
+    assert_eq!(definitions.iter().map(|d| d.id.as_str()).collect::<Vec<_>>(), vec!["build.yml", "deploy.yaml"]);
+}
+
+#[test]
+fn forgejo_teams_link_to_the_web_interface() {
+    let server = MockGitHub::start(vec![
+        Route::fixture("/api/v1/orgs/navikt/teams", "forgejo_teams.json"),
+    ]);
+    let (callback, receiver) = channel::<Vec<Group>>();
+
+    forgejo(&server).groups(&mut token(), callback);
+    let groups = receiver.recv_timeout(TIMEOUT).expect("teams");
+
+    assert_eq!(groups[1].slug, "aap");
+    assert_eq!(groups[1].url, format!("{}/org/navikt/teams/aap", server.base_url));
+}
+
+#[test]
+fn repositories_are_fetched_from_their_forge() {
+    let server = MockGitHub::start(vec![
+        Route::fixture("/api/v1/repos/navikt/aap-tools/pulls", "forgejo_pulls.json"),
+        Route::fixture("/projects/navikt%2Faap-api/merge_requests", "gitlab_merge_requests.json"),
+    ]);
+    let mut forges = Forges { forgejo: forgejo(&server), gitlab: gitlab(&server), ..Forges::default() };
+    forges.set("aap-tools", Forge::Forgejo);
+    forges.set("aap-api", Forge::GitLab);
+
+    let (callback, receiver) = channel::<Vec<ChangeRequest>>();
+    forges.change_requests("aap-tools", callback);
+    assert_eq!(receiver.recv_timeout(TIMEOUT).expect("forgejo")[0].number, 12);
+
+    let (callback, receiver) = channel::<Vec<ChangeRequest>>();
+    forges.change_requests("aap-api", callback);
+    assert_eq!(receiver.recv_timeout(TIMEOUT).expect("gitlab")[0].number, 7);
+}
+
+#[test]
+fn charts_and_groups_are_fetched_from_the_forges_in_use() {
+    let server = MockGitHub::start(vec![
+        Route::fixture("/api/v1/repos/navikt/aap-tools/actions/tasks", "forgejo_tasks.json"),
+        Route::fixture("/api/v1/orgs/navikt/teams", "forgejo_teams.json"),
+    ]);
+    let mut forges = Forges { forgejo: forgejo(&server), gitlab: gitlab(&server), ..Forges::default() };
+    forges.set("aap-tools", Forge::Forgejo);
+    assert_eq!(forges.in_use(), vec![Forge::Forgejo]);
+
+    let (callback, receiver) = channel::<Vec<PipelineDefinition>>();
+    forges.pipeline_definitions("aap-tools", callback);
+    assert_eq!(receiver.recv_timeout(TIMEOUT).expect("definitions").len(), 2);
+
+    let (callback, receiver) = channel::<Vec<PipelineRun>>();
+    forges.pipeline_runs("aap-tools", callback);
+    let runs = receiver.recv_timeout(TIMEOUT).expect("runs");
+    assert_eq!(runs.iter().map(|run| run.duration_secs()).collect::<Vec<_>>(), vec![None, Some(715), Some(115)]);
+
+    let (callback, receiver) = channel::<Vec<Group>>();
+    forges.groups(Forge::Forgejo, callback);
+    assert!(!receiver.recv_timeout(TIMEOUT).expect("groups").is_empty());
+}
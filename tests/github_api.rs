@@ -0,0 +1,273 @@
+//! Drives `GitHubApi` against the mock server in `common`, one fetcher at a time.
+// The mock server needs sockets and threads, which the browser has neither of.
+#![cfg(not(target_arch = "wasm32"))]
+
+mod common;
+
+use std::time::{Duration, Instant};
+
+use aap_status::github::blocking;
+use aap_status::github::github_client::{DeviceFlow, GitHubApi, Pulls, RateLimits, Runs, Teams, Users, Workflows};
+use aap_status::github::oauth::{AccessToken, DeviceCode};
+use aap_status::github::pulls::{PullRequest, Review, User};
+use aap_status::github::rate_limit::RateLimit;
+use aap_status::github::runs::WorkflowRuns;
+use aap_status::github::teams::Team;
+use aap_status::github::users::Identity;
+use aap_status::github::workflows::Workflow;
+
+use common::{channel, token, MockGitHub, Route, TIMEOUT};
+
+/// Responses come from localhost, so a callback that hasn't been called by then never will be.
+const NOT_CALLED: Duration = Duration::from_millis(500);
+
+#[test]
+fn pull_requests_are_parsed() {
+    let server = MockGitHub::start(vec![Route::fixture("/repos/navikt/aap-api/pulls", "pulls.json")]);
+    let (callback, receiver) = channel::<Vec<PullRequest>>();
+
+    server.github().pull_requests(&mut token(), "aap-api", callback);
+    let pulls = receiver.recv_timeout(TIMEOUT).expect("pull requests");
+
+    assert_eq!(pulls.iter().map(|pr| pr.number).collect::<Vec<_>>(), vec![101, 102]);
+    assert_eq!(pulls[0].requested_reviewers.iter().map(|u| u.login.as_str()).collect::<Vec<_>>(), vec!["bob"]);
+    assert!(pulls[0].created().is_some());
+
+    let requests = server.requests();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0].authorization.as_deref(), Some("Bearer ghp_test"));
+}
+
+#[test]
+fn requests_go_to_the_configured_organisation() {
+    let server = MockGitHub::start(vec![Route::fixture("/repos/other-org/aap-api/pulls", "pulls.json")]);
+    let github = GitHubApi { org: String::from("other-org"), ..server.github() };
+    let (callback, receiver) = channel::<Vec<PullRequest>>();
+
+    github.pull_requests(&mut token(), "aap-api", callback);
+
+    assert_eq!(receiver.recv_timeout(TIMEOUT).expect("pull requests").len(), 2);
+}
+
+#[test]
+fn pull_request_history_asks_for_a_page_of_all_states() {
+    let server = MockGitHub::start(vec![Route::fixture("/repos/navikt/aap-api/pulls?state=all&page=2", "pulls.json")]);
+    let (callback, receiver) = channel::<Vec<PullRequest>>();
+
+    server.github().pull_request_history(&mut token(), "aap-api", 2, callback);
+
+    assert_eq!(receiver.recv_timeout(TIMEOUT).expect("pull requests").len(), 2);
+    assert!(server.requests()[0].url.contains("sort=updated&direction=desc"));
+}
+
+#[test]
+fn reviews_are_parsed() {
+    let server = MockGitHub::start(vec![Route::fixture("/repos/navikt/aap-api/pulls/101/reviews", "reviews.json")]);
+    let (callback, receiver) = channel::<Vec<Review>>();
+
+    server.github().reviews(&mut token(), "aap-api", 101, callback);
+    let reviews = receiver.recv_timeout(TIMEOUT).expect("reviews");
+
+    assert_eq!(reviews.len(), 1);
+    assert_eq!(reviews[0].state, "APPROVED");
+    assert_eq!(reviews[0].user.as_ref().map(|u| u.login.as_str()), Some("bob"));
+}
+
+#[test]
+fn runs_are_parsed() {
+    let server = MockGitHub::start(vec![Route::fixture("/repos/navikt/aap-api/actions/runs", "runs.json")]);
+    let (callback, receiver) = channel::<WorkflowRuns>();
+
+    server.github().runs(&mut token(), "aap-api", callback);
+    let runs = receiver.recv_timeout(TIMEOUT).expect("runs").workflow_runs;
+
+    assert_eq!(runs.len(), 3);
+    assert!(runs[0].is_failure());
+    assert!(runs[1].is_success());
+    assert_eq!(runs[0].duration_secs(), Some(450));
+    assert!(runs[0].is_workflow("deploy.yml"));
+    assert!(runs[0].is_workflow("77"));
+    assert!(!runs[1].is_workflow("deploy.yml"));
+}
+
+#[test]
+fn workflow_runs_filter_on_creation_time() {
+    let server = MockGitHub::start(vec![Route::fixture("/repos/navikt/aap-api/actions/workflows/deploy.yml/runs", "workflow_runs.json")]);
+    let (callback, receiver) = channel::<WorkflowRuns>();
+
+    server.github().workflow_runs(&mut token(), "aap-api", "deploy.yml", Some(">=2024-01-01T00:00:00Z"), 2, callback);
+
+    assert_eq!(receiver.recv_timeout(TIMEOUT).expect("runs").workflow_runs.len(), 2);
+    let url = &server.requests()[0].url;
+    assert!(url.contains("page=2"), "{}", url);
+    assert!(url.contains("created=%3E%3D2024-01-01T00:00:00Z"), "{}", url);
+}
+
+#[test]
+fn workflows_are_parsed() {
+    let server = MockGitHub::start(vec![Route::fixture("/repos/navikt/aap-api/actions/workflows", "workflows.json")]);
+    let (callback, receiver) = channel::<Vec<Workflow>>();
+
+    server.github().workflows(&mut token(), "aap-api", callback);
+    let workflows = receiver.recv_timeout(TIMEOUT).expect("workflows");
+
+    assert_eq!(workflows.iter().map(|w| w.name.as_str()).collect::<Vec<_>>(), vec!["Deploy", "Test"]);
+}
+
+#[test]
+fn teams_are_paginated_with_link_headers() {
+    let server = MockGitHub::start(vec![
+        Route::fixture("/orgs/navikt/teams?page=1", "teams_page1.json")
+            .header("Link", r#"<{base}/orgs/navikt/teams?per_page=100&page=2>; rel="next", <{base}/orgs/navikt/teams?per_page=100&page=2>; rel="last""#),
+        Route::fixture("/orgs/navikt/teams?page=2", "teams_page2.json")
+            .header("Link", r#"<{base}/orgs/navikt/teams?per_page=100&page=1>; rel="prev", <{base}/orgs/navikt/teams?per_page=100&page=1>; rel="first""#),
+    ]);
+    let github = server.github();
+
+    let mut teams = vec![];
+    let mut next = Some(format!("{}/orgs/navikt/teams?per_page=100&page=1", server.base_url));
+    while let Some(url) = next.take() {
+        let (callback, receiver) = channel::<ehttp::Response>();
+        github.teams(&url, &mut token(), callback);
+        let response = receiver.recv_timeout(TIMEOUT).expect("teams");
+
+        teams.extend(serde_json::from_slice::<Vec<Team>>(&response.bytes).expect("teams json"));
+        next = response.headers.get("link")
+            .and_then(|link| parse_link_header::parse_with_rel(link).ok())
+            .and_then(|links| links.get("next").map(|next| next.raw_uri.clone()));
+    }
+
+    assert_eq!(teams.iter().map(|t| t.slug.as_str()).collect::<Vec<_>>(), vec!["aap", "aap-admin", "tiltak"]);
+    assert_eq!(server.requests().len(), 2);
+}
+
+#[test]
+fn team_members_are_parsed() {
+    let server = MockGitHub::start(vec![Route::fixture("/orgs/navikt/teams/aap/members", "team_members.json")]);
+    let (callback, receiver) = channel::<Vec<User>>();
+
+    server.github().team_members(&mut token(), "aap", callback);
+
+    let members = receiver.recv_timeout(TIMEOUT).expect("members");
+    assert_eq!(members.iter().map(|u| u.login.as_str()).collect::<Vec<_>>(), vec!["alice", "bob"]);
+}
+
+#[test]
+fn rate_limit_is_parsed() {
+    let server = MockGitHub::start(vec![Route::fixture("/rate_limit", "rate_limit.json")]);
+    let (callback, receiver) = channel::<RateLimit>();
+
+    server.github().rate_limit(&mut token(), callback);
+    let rate_limit = receiver.recv_timeout(TIMEOUT).expect("rate limit");
+
+    assert_eq!((rate_limit.limit, rate_limit.remaining, rate_limit.used), (5000, 4990, 10));
+}
+
+#[test]
+fn authenticated_user_reports_missing_scopes() {
+    let server = MockGitHub::start(vec![Route::fixture("/user", "user.json").header("X-OAuth-Scopes", "repo, read:org")]);
+    let (callback, receiver) = channel::<Result<Identity, String>>();
+
+    server.github().authenticated_user(&mut token(), callback);
+    let identity = receiver.recv_timeout(TIMEOUT).expect("user").expect("identity");
+
+    assert_eq!(identity.user.login, "alice");
+    assert_eq!(identity.scopes, Some(vec![String::from("repo"), String::from("read:org")]));
+    assert_eq!(identity.missing_scopes(&["repo", "workflow", "read:org"]), vec!["workflow"]);
+}
+
+#[test]
+fn fine_grained_tokens_have_no_scopes() {
+    let server = MockGitHub::start(vec![Route::fixture("/user", "user.json")]);
+    let (callback, receiver) = channel::<Result<Identity, String>>();
+
+    server.github().authenticated_user(&mut token(), callback);
+    let identity = receiver.recv_timeout(TIMEOUT).expect("user").expect("identity");
+
+    assert_eq!(identity.scopes, None);
+    assert!(identity.missing_scopes(&["repo"]).is_empty());
+}
+
+#[test]
+fn bad_credentials_are_reported() {
+    let server = MockGitHub::start(vec![
+        Route::fixture("/user", "bad_credentials.json").status(401),
+        Route::fixture("/repos/navikt/aap-api/pulls", "bad_credentials.json").status(401),
+    ]);
+
+    let (callback, receiver) = channel::<Result<Identity, String>>();
+    server.github().authenticated_user(&mut token(), callback);
+    assert_eq!(receiver.recv_timeout(TIMEOUT).expect("user").err(), Some(String::from("Bad credentials")));
+
+    let (callback, receiver) = channel::<Vec<PullRequest>>();
+    server.github().pull_requests(&mut token(), "aap-api", callback);
+    assert!(receiver.recv_timeout(NOT_CALLED).is_err(), "an error is not an empty list of pull requests");
+}
+
+#[test]
+fn not_modified_keeps_the_data_already_fetched() {
+    let server = MockGitHub::start(vec![
+        Route::not_modified("/repos/navikt/aap-api/pulls"),
+        Route::not_modified("/repos/navikt/aap-api/actions/runs"),
+    ]);
+
+    let (callback, receiver) = channel::<Vec<PullRequest>>();
+    server.github().pull_requests(&mut token(), "aap-api", callback);
+    assert!(receiver.recv_timeout(NOT_CALLED).is_err());
+
+    let (callback, receiver) = channel::<WorkflowRuns>();
+    server.github().runs(&mut token(), "aap-api", callback);
+    assert!(receiver.recv_timeout(NOT_CALLED).is_err());
+}
+
+#[test]
+fn rate_limited_repositories_are_left_out_of_snapshots() {
+    let server = MockGitHub::start(vec![
+        Route::fixture("/repos/navikt/aap-api/pulls", "pulls.json"),
+        Route::fixture("/repos/navikt/aap-api/actions/runs", "runs.json"),
+        Route::fixture("/repos/navikt/aap-vedtak/pulls", "rate_limited.json")
+            .status(403)
+            .header("X-RateLimit-Limit", "5000")
+            .header("X-RateLimit-Remaining", "0")
+            .header("X-RateLimit-Reset", "1704300000"),
+        Route::fixture("/repos/navikt/aap-vedtak/actions/runs", "runs.json"),
+        Route::fixture("/repos/navikt/aap-api", "repository.json"),
+        Route::fixture("/repos/navikt/aap-vedtak", "repository.json"),
+        Route::fixture("/rate_limit", "rate_limit.json"),
+    ]);
+
+    let repos = vec![String::from("aap-api"), String::from("aap-vedtak")];
+    let started = Instant::now();
+    let snapshot = blocking::fetch(&server.github(), &token(), &repos, TIMEOUT);
+
+    // The failed request is answered for instead of waited for.
+    assert!(started.elapsed() < TIMEOUT);
+    assert_eq!(snapshot.pulls.keys().collect::<Vec<_>>(), vec!["aap-api"]);
+    assert_eq!(snapshot.runs.keys().collect::<Vec<_>>(), vec!["aap-api", "aap-vedtak"]);
+    assert_eq!(snapshot.default_branches.get("aap-api").map(String::as_str), Some("main"));
+    assert_eq!(snapshot.rate_limit.map(|r| r.remaining), Some(4990));
+    assert_eq!(snapshot.errors.len(), 1);
+    assert!(snapshot.errors[0].contains("403"), "{}", snapshot.errors[0]);
+}
+
+#[test]
+fn device_flow_goes_through_the_configured_server() {
+    let server = MockGitHub::start(vec![
+        Route::fixture("/login/device/code", "device_code.json"),
+        Route::fixture("/login/oauth/access_token", "authorization_pending.json"),
+    ]);
+    let github = server.github();
+
+    let (callback, receiver) = channel::<Result<DeviceCode, String>>();
+    github.device_code("client", "repo", callback);
+    let code = receiver.recv_timeout(TIMEOUT).expect("device code").expect("code");
+    assert_eq!(code.user_code, "WDJB-MJHT");
+
+    let (callback, receiver) = channel::<Result<AccessToken, String>>();
+    github.access_token("client", &code.device_code, callback);
+    let response = receiver.recv_timeout(TIMEOUT).expect("access token").expect("response");
+    assert_eq!(response.access_token, None);
+    assert_eq!(response.error.as_deref(), Some("authorization_pending"));
+
+    assert!(server.requests().iter().all(|request| request.method == "POST"));
+}
@@ -0,0 +1,104 @@
+//! Fetches open pull requests of several repositories at once from the GraphQL API of the mock
+//! server in `common`.
+// The mock server needs sockets and threads, which the browser has neither of.
+#![cfg(not(target_arch = "wasm32"))]
+
+mod common;
+
+use std::collections::BTreeMap;
+
+use aap_status::forge::ChangeRequest;
+use aap_status::github::github_client::{BulkPulls, GitHubApi};
+use aap_status::github::graphql::BATCH;
+
+use common::{channel, token, MockGitHub, Route, TIMEOUT};
+
+type Result = std::result::Result<BTreeMap<String, Vec<ChangeRequest>>, String>;
+
+fn repos(names: &[&str]) -> Vec<String> {
+    names.iter().map(|name| name.to_string()).collect()
+}
+
+#[test]
+fn open_pull_requests_come_with_reviews_checks_and_labels() {
+    let server = MockGitHub::start(vec![Route::fixture("/graphql", "graphql_pulls.json")]);
+    let (callback, receiver) = channel::<Result>();
+
+    server.github().open_pull_requests(&mut token(), &repos(&["aap-api", "aap-bot", "aap-gone"]), callback);
+    let pulls = receiver.recv_timeout(TIMEOUT).expect("callback").expect("pull requests");
+
+    // Repositories GitHub couldn't resolve are left out, not failing the others.
+    assert_eq!(pulls.keys().collect::<Vec<_>>(), vec!["aap-api", "aap-bot"]);
+    assert!(pulls["aap-bot"].is_empty());
+
+    let first = &pulls["aap-api"][0];
+    assert_eq!(first.number, 101);
+    assert_eq!(first.author, "dependabot");
+    assert_eq!(first.reviewers, vec!["bob", "aap"]);
+    assert_eq!(first.labels, vec!["dependencies"]);
+    assert_eq!(first.review_decision.as_deref(), Some("REVIEW_REQUIRED"));
+    assert_eq!(first.checks.as_deref(), Some("SUCCESS"));
+
+    let second = &pulls["aap-api"][1];
+    assert_eq!(second.author, "");
+    assert_eq!(second.checks, None);
+
+    let requests = server.requests();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0].method, "POST");
+    assert_eq!(requests[0].authorization.as_deref(), Some("Bearer ghp_test"));
+}
+
+#[test]
+fn many_repositories_are_fetched_in_batches() {
+    let server = MockGitHub::start(vec![Route::fixture("/graphql", "graphql_pulls.json")]);
+    let names = (0..BATCH + 1).map(|i| format!("aap-{}", i)).collect::<Vec<_>>();
+    let (callback, receiver) = channel::<Result>();
+
+    server.github().open_pull_requests(&mut token(), &names, callback);
+    let pulls = receiver.recv_timeout(TIMEOUT).expect("callback").expect("pull requests");
+
+    assert_eq!(server.requests().len(), 2);
+    // Each batch is answered by the same fixture, naming its first two repositories.
+    assert_eq!(pulls.keys().cloned().collect::<Vec<_>>(), vec!["aap-0", "aap-1", &format!("aap-{}", BATCH)]);
+}
+
+#[test]
+fn failed_queries_are_reported_for_falling_back_to_rest() {
+    let server = MockGitHub::start(vec![Route::fixture("/graphql", "graphql_error.json")]);
+    let (callback, receiver) = channel::<Result>();
+
+    server.github().open_pull_requests(&mut token(), &repos(&["aap-api"]), callback);
+    let error = receiver.recv_timeout(TIMEOUT).expect("callback").expect_err("error");
+
+    assert!(error.contains("Something went wrong"), "{}", error);
+}
+
+#[test]
+fn backends_without_graphql_fail_the_same_way() {
+    let server = MockGitHub::start(vec![]);
+    let (callback, receiver) = channel::<Result>();
+
+    server.github().open_pull_requests(&mut token(), &repos(&["aap-api"]), callback);
+
+    assert!(receiver.recv_timeout(TIMEOUT).expect("callback").is_err());
+}
+
+#[test]
+fn no_repositories_need_no_query() {
+    let server = MockGitHub::start(vec![]);
+    let (callback, receiver) = channel::<Result>();
+
+    server.github().open_pull_requests(&mut token(), &[], callback);
+
+    assert_eq!(receiver.recv_timeout(TIMEOUT).expect("callback"), Ok(BTreeMap::new()));
+    assert!(server.requests().is_empty());
+}
+
+#[test]
+fn graphql_is_found_next_to_the_rest_api() {
+    assert_eq!(GitHubApi::default().graphql_url(), "https://api.github.com/graphql");
+
+    let enterprise = GitHubApi { base_url: String::from("https://github.example.com/api/v3/"), ..GitHubApi::default() };
+    assert_eq!(enterprise.graphql_url(), "https://github.example.com/api/graphql");
+}
@@ -0,0 +1,185 @@
+//! How the requests of `GitHubApi` are queued by its scheduler, against slow routes of the mock
+//! server in `common` so that they stay in flight for a while.
+// The mock server needs sockets and threads, which the browser has neither of.
+#![cfg(not(target_arch = "wasm32"))]
+
+mod common;
+
+use std::sync::mpsc::{self, Sender};
+use std::time::Duration;
+
+use aap_status::github::github_client::GitHubApi;
+use aap_status::github::scheduler::CANCELLED;
+
+use common::{MockGitHub, Route, TIMEOUT, TOKEN};
+
+const SLOW: Duration = Duration::from_millis(300);
+
+fn slow(path: &str) -> Route {
+    Route::fixture(path, "pulls.json").delay(SLOW)
+}
+
+/// Sends the status of the response to `path` when it is in.
+fn fetch(github: &GitHubApi, path: &str, sender: &Sender<(String, u16)>) {
+    let url = format!("{}{}", github.base_url, path);
+    let (sender, path) = (sender.clone(), path.to_string());
+    github.fetch(github.get(TOKEN, &url), move |result| {
+        let _ = sender.send((path, result.map(|res| res.status).unwrap_or_default()));
+    });
+}
+
+fn paths(server: &MockGitHub) -> Vec<String> {
+    server.requests().into_iter().map(|request| request.url).collect()
+}
+
+#[test]
+fn no_more_requests_than_the_limit_are_in_flight() {
+    let repos = ["a", "b", "c", "d"].map(|repo| format!("/repos/navikt/{}/pulls", repo));
+    let server = MockGitHub::start(repos.iter().map(|path| slow(path)).collect());
+    let github = GitHubApi { max_concurrency: 2, ..server.github() };
+    let (sender, receiver) = mpsc::channel();
+
+    repos.iter().for_each(|path| fetch(&github, path, &sender));
+
+    assert_eq!((github.scheduler.running(), github.scheduler.waiting()), (2, 2));
+    for _ in &repos {
+        assert_eq!(receiver.recv_timeout(TIMEOUT).expect("response").1, 200);
+    }
+    assert_eq!(server.requests().len(), 4);
+    assert_eq!((github.scheduler.running(), github.scheduler.waiting()), (0, 0));
+}
+
+#[test]
+fn identical_requests_are_sent_once() {
+    let server = MockGitHub::start(vec![slow("/repos/navikt/aap-api/pulls")]);
+    let github = server.github();
+    let (sender, receiver) = mpsc::channel();
+
+    fetch(&github, "/repos/navikt/aap-api/pulls", &sender);
+    fetch(&github, "/repos/navikt/aap-api/pulls", &sender);
+
+    assert_eq!(receiver.recv_timeout(TIMEOUT).expect("first").1, 200);
+    assert_eq!(receiver.recv_timeout(TIMEOUT).expect("second").1, 200);
+    assert_eq!(server.requests().len(), 1);
+}
+
+#[test]
+fn requests_for_the_view_on_screen_go_first() {
+    let server = MockGitHub::start(vec![
+        slow("/repos/navikt/first/pulls"),
+        slow("/repos/navikt/background/pulls"),
+        slow("/repos/navikt/visible/pulls"),
+    ]);
+    let github = GitHubApi { max_concurrency: 1, ..server.github() };
+    let (sender, receiver) = mpsc::channel();
+
+    github.scheduler.set_view(Some("Pulls"));
+    fetch(&github, "/repos/navikt/first/pulls", &sender);
+    fetch(&github, "/repos/navikt/background/pulls", &sender);
+    fetch(&github.for_view("Pulls"), "/repos/navikt/visible/pulls", &sender);
+
+    let order = (0..3).map(|_| receiver.recv_timeout(TIMEOUT).expect("response").0).collect::<Vec<_>>();
+    assert_eq!(order, vec!["/repos/navikt/first/pulls", "/repos/navikt/visible/pulls", "/repos/navikt/background/pulls"]);
+    assert_eq!(paths(&server), order);
+}
+
+#[test]
+fn requests_made_for_the_view_on_screen_later_go_first() {
+    let server = MockGitHub::start(vec![
+        slow("/repos/navikt/first/pulls"),
+        slow("/repos/navikt/queued/pulls"),
+        slow("/repos/navikt/runs/actions/runs"),
+    ]);
+    let github = GitHubApi { max_concurrency: 1, ..server.github() };
+    let (sender, receiver) = mpsc::channel();
+
+    github.scheduler.set_view(Some("Pulls"));
+    fetch(&github.for_view("Pulls"), "/repos/navikt/first/pulls", &sender);
+    fetch(&github.for_view("Pulls"), "/repos/navikt/queued/pulls", &sender);
+    fetch(&github.for_view("Runs"), "/repos/navikt/runs/actions/runs", &sender);
+
+    let order = (0..3).map(|_| receiver.recv_timeout(TIMEOUT).expect("response").0).collect::<Vec<_>>();
+    assert_eq!(order, vec!["/repos/navikt/first/pulls", "/repos/navikt/queued/pulls", "/repos/navikt/runs/actions/runs"]);
+}
+
+#[test]
+fn requests_queued_for_a_view_left_are_cancelled() {
+    let server = MockGitHub::start(vec![slow("/repos/navikt/aap-api/pulls"), slow("/repos/navikt/aap-api/actions/runs")]);
+    let github = GitHubApi { max_concurrency: 1, ..server.github() };
+    let (in_flight, in_flight_done) = mpsc::channel();
+    let (queued, queued_done) = mpsc::channel();
+
+    github.scheduler.set_view(Some("Pulls"));
+    fetch(&github.for_view("Pulls"), "/repos/navikt/aap-api/pulls", &in_flight);
+    let url = format!("{}/repos/navikt/aap-api/actions/runs", github.base_url);
+    github.for_view("Pulls").fetch(github.get(TOKEN, &url), move |result| { let _ = queued.send(result.map(|res| res.status)); });
+    github.scheduler.set_view(Some("Runs"));
+
+    assert_eq!(queued_done.recv_timeout(TIMEOUT).expect("called back"), Err(String::from(CANCELLED)));
+    assert_eq!(in_flight_done.recv_timeout(TIMEOUT).expect("in flight").1, 200);
+    assert_eq!(paths(&server), vec!["/repos/navikt/aap-api/pulls"]);
+}
+
+#[test]
+fn requests_for_no_view_are_never_cancelled() {
+    let server = MockGitHub::start(vec![slow("/repos/navikt/aap-api/pulls"), slow("/repos/navikt/aap-api/actions/runs")]);
+    let github = GitHubApi { max_concurrency: 1, ..server.github() };
+    let (sender, receiver) = mpsc::channel();
+
+    github.scheduler.set_view(Some("Pulls"));
+    fetch(&github, "/repos/navikt/aap-api/pulls", &sender);
+    fetch(&github, "/repos/navikt/aap-api/actions/runs", &sender);
+    github.scheduler.set_view(Some("Runs"));
+
+    for _ in 0..2 {
+        assert_eq!(receiver.recv_timeout(TIMEOUT).expect("response").1, 200);
+    }
+    assert_eq!(server.requests().len(), 2);
+}
+
+#[test]
+fn awaiting_a_dropped_request_is_an_error() {
+    let server = MockGitHub::start(vec![slow("/repos/navikt/aap-api/pulls"), slow("/repos/navikt/aap-api/actions/runs")]);
+    let github = GitHubApi { max_concurrency: 1, ..server.github() };
+    let (sender, receiver) = mpsc::channel();
+
+    github.scheduler.set_view(Some("Pulls"));
+    fetch(&github, "/repos/navikt/aap-api/pulls", &sender);
+    let _github = github.for_view("Pulls");
+    let waiting = std::thread::spawn(move || futures::executor::block_on(_github.fetch_pull_requests(TOKEN, "aap-api-2")));
+    while github.scheduler.waiting() == 0 {
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    github.scheduler.set_view(Some("Runs"));
+
+    assert!(waiting.join().expect("thread").is_err_and(|e| e.contains(CANCELLED)));
+    assert_eq!(receiver.recv_timeout(TIMEOUT).expect("in flight").1, 200);
+}
+
+#[test]
+fn requests_also_made_for_no_view_outlive_the_view() {
+    let server = MockGitHub::start(vec![slow("/repos/navikt/aap-api/pulls"), slow("/repos/navikt/aap-api/actions/runs")]);
+    let github = GitHubApi { max_concurrency: 1, ..server.github() };
+    let (sender, receiver) = mpsc::channel();
+    let (cancelled, cancelled_done) = mpsc::channel();
+
+    github.scheduler.set_view(Some("Runs"));
+    fetch(&github.for_view("Runs"), "/repos/navikt/aap-api/pulls", &sender);
+    let url = format!("{}/repos/navikt/aap-api/actions/runs", github.base_url);
+    github.for_view("Runs").fetch(github.get(TOKEN, &url), move |result| { let _ = cancelled.send(result.map(|res| res.status)); });
+    // The same request from the history sync joins the one queued for the view.
+    fetch(&github, "/repos/navikt/aap-api/actions/runs", &sender);
+    github.scheduler.set_view(Some("Pulls"));
+
+    assert_eq!(cancelled_done.recv_timeout(TIMEOUT).expect("called back"), Err(String::from(CANCELLED)));
+    let mut responses = vec![
+        receiver.recv_timeout(TIMEOUT).expect("in flight"),
+        receiver.recv_timeout(TIMEOUT).expect("for no view"),
+    ];
+    responses.sort();
+    assert_eq!(responses, vec![
+        (String::from("/repos/navikt/aap-api/actions/runs"), 200),
+        (String::from("/repos/navikt/aap-api/pulls"), 200),
+    ]);
+    assert_eq!(server.requests().len(), 2);
+}